@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+import "./SafeToken.sol";
+
+interface IERC20 {
+    function balanceOf(address account) external view returns (uint256);
+}
+
+interface IVat {
+    function hope(address usr) external;
+    function dai(address usr) external view returns (uint256);
+}
+
+interface IGemJoin {
+    function gem() external view returns (IERC20);
+    function dec() external view returns (uint256);
+    function exit(address usr, uint256 amount) external;
+}
+
+interface IDaiJoin {
+    function vat() external view returns (IVat);
+    function dai() external view returns (IERC20);
+    function join(address usr, uint256 wad) external;
+    function exit(address usr, uint256 wad) external;
+}
+
+interface IClipper {
+    function take(uint256 id, uint256 amt, uint256 max, address who, bytes calldata data) external;
+}
+
+interface IUniswapV2Router {
+    function swapExactTokensForTokens(uint256 amountIn, uint256 amountOutMin, address[] calldata path, address to, uint256 deadline)
+        external
+        returns (uint256[] memory amounts);
+}
+
+/// Takes Maker Clipper auctions without holding DAI: the seized collateral is sold on a DEX
+/// inside the `clipperCall` callback, the proceeds pay for the lot, and the rest goes to the owner.
+/// Not a MultiActionExecutor program: the lot size is only known inside the callback, and paying
+/// goes through the Vat's internal DAI balance, neither of which a fixed list of actions can follow.
+contract ClipperTaker {
+    using SafeToken for address;
+
+    uint256 constant RAY = 10 ** 27;
+
+    struct Take {
+        IClipper clipper;
+        IGemJoin gemJoin;
+        uint256 id;
+        uint256 amt;
+        uint256 max;
+        address router;
+        // gem -> ... -> DAI
+        address[] path;
+        uint256 minProfit;
+    }
+
+    address public immutable owner;
+    IDaiJoin public immutable daiJoin;
+    IVat public immutable vat;
+    IERC20 public immutable dai;
+    mapping(address => bool) public operators;
+    // Set for the duration of a take so only that clipper can call back
+    address private activeClipper;
+
+    constructor(address _daiJoin) {
+        owner = msg.sender;
+        daiJoin = IDaiJoin(_daiJoin);
+        vat = IDaiJoin(_daiJoin).vat();
+        dai = IDaiJoin(_daiJoin).dai();
+        vat.hope(_daiJoin);
+        operators[msg.sender] = true;
+    }
+
+    function setOperator(address operator, bool allowed) external {
+        require(msg.sender == owner, "ClipperTaker: not owner");
+        operators[operator] = allowed;
+    }
+
+    function take(Take calldata order) external {
+        require(operators[msg.sender], "ClipperTaker: not operator");
+        vat.hope(address(order.clipper));
+
+        activeClipper = address(order.clipper);
+        order.clipper.take(order.id, order.amt, order.max, address(this), abi.encode(order.gemJoin, order.router, order.path));
+        activeClipper = address(0);
+
+        uint256 profit = dai.balanceOf(address(this));
+        require(profit >= order.minProfit, "ClipperTaker: insufficient profit");
+        if (profit > 0) {
+            address(dai).safeTransfer(owner, profit);
+        }
+    }
+
+    /// Called by the clipper after moving `slice` of collateral to us in the Vat and before
+    /// pulling `owe` (a rad) of Vat DAI from us.
+    function clipperCall(address sender, uint256 owe, uint256 slice, bytes calldata data) external {
+        require(msg.sender == activeClipper && sender == address(this), "ClipperTaker: untrusted callback");
+        (IGemJoin gemJoin, address router, address[] memory path) = abi.decode(data, (IGemJoin, address, address[]));
+
+        uint256 amount = slice / 10 ** (18 - gemJoin.dec());
+        gemJoin.exit(address(this), amount);
+
+        // Vat DAI left from earlier redo incentives is spent first
+        uint256 held = vat.dai(address(this));
+        uint256 shortfall = owe > held ? (owe - held + RAY - 1) / RAY : 0;
+
+        address gem = address(gemJoin.gem());
+        gem.safeApprove(router, amount);
+        IUniswapV2Router(router).swapExactTokensForTokens(amount, shortfall, path, address(this), block.timestamp);
+        gem.safeApprove(router, 0);
+        if (shortfall > 0) {
+            address(dai).safeApprove(address(daiJoin), shortfall);
+            daiJoin.join(address(this), shortfall);
+        }
+    }
+
+    /// Withdraws Vat DAI, e.g. accumulated redo incentives.
+    function exitVatDai() external {
+        require(msg.sender == owner, "ClipperTaker: not owner");
+        daiJoin.exit(owner, vat.dai(address(this)) / RAY);
+    }
+}
@@ -0,0 +1,463 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+import "./SafeToken.sol";
+
+interface IERC20 {
+    function balanceOf(address account) external view returns (uint256);
+}
+
+interface IWETH is IERC20 {
+    function deposit() external payable;
+    function withdraw(uint256 amount) external;
+}
+
+interface IAaveV3Pool {
+    function flashLoanSimple(address receiverAddress, address asset, uint256 amount, bytes calldata params, uint16 referralCode) external;
+    function liquidationCall(address collateralAsset, address debtAsset, address user, uint256 debtToCover, bool receiveAToken) external;
+}
+
+interface IBalancerVault {
+    function flashLoan(address recipient, address[] calldata tokens, uint256[] calldata amounts, bytes calldata userData) external;
+}
+
+interface IUniswapV2Pair {
+    function token0() external view returns (address);
+    function token1() external view returns (address);
+    function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast);
+    function swap(uint256 amount0Out, uint256 amount1Out, address to, bytes calldata data) external;
+}
+
+interface IUniswapV3Pool {
+    function token0() external view returns (address);
+    function token1() external view returns (address);
+    function swap(address recipient, bool zeroForOne, int256 amountSpecified, uint160 sqrtPriceLimitX96, bytes calldata data)
+        external
+        returns (int256 amount0, int256 amount1);
+    function flash(address recipient, uint256 amount0, uint256 amount1, bytes calldata data) external;
+}
+
+interface ICurvePool {
+    function exchange(int128 i, int128 j, uint256 dx, uint256 min_dy) external returns (uint256);
+}
+
+interface ICToken {
+    function liquidateBorrow(address borrower, uint256 repayAmount, address cTokenCollateral) external returns (uint256);
+    function redeem(uint256 redeemTokens) external returns (uint256);
+}
+
+interface ICEther {
+    function liquidateBorrow(address borrower, address cTokenCollateral) external payable;
+}
+
+/// Runs a packed list of actions, encoded by `src/execution/actions.rs`.
+///
+/// Each action is an opcode byte followed by its fields: addresses as 20 bytes, small integers
+/// and flags as one byte, amounts as a length byte and that many big-endian bytes. A length of
+/// 0xff stands for the contract's whole balance of the token involved. A flash borrow carries
+/// the program run inside the loan, which must include its repayment, behind a two-byte length;
+/// the actions after it run once the loan is repaid. Call data has the same two-byte length.
+contract MultiActionExecutor {
+    using SafeToken for address;
+
+    uint8 constant SWAP_UNISWAP_V2 = 0x01;
+    uint8 constant SWAP_UNISWAP_V3 = 0x02;
+    uint8 constant SWAP_CURVE = 0x03;
+    uint8 constant FLASH_BORROW = 0x04;
+    uint8 constant REPAY = 0x05;
+    uint8 constant LIQUIDATE = 0x06;
+    uint8 constant TRANSFER = 0x07;
+    uint8 constant CHECK_MIN_BALANCE = 0x08;
+    uint8 constant APPROVE = 0x09;
+    uint8 constant CALL = 0x0a;
+    uint8 constant PULL = 0x0b;
+    uint8 constant REDEEM = 0x0c;
+    uint8 constant WRAP = 0x0d;
+
+    uint8 constant AAVE_V3 = 0;
+    uint8 constant BALANCER = 1;
+    uint8 constant UNISWAP_V2 = 2;
+    uint8 constant UNISWAP_V3 = 3;
+
+    uint8 constant VENUE_AAVE = 0;
+    uint8 constant VENUE_COMPOUND_V2 = 1;
+
+    uint8 constant FLAG = 0x01;
+    uint256 constant BALANCE = type(uint256).max;
+
+    uint160 constant MIN_SQRT_RATIO = 4295128739;
+    uint160 constant MAX_SQRT_RATIO = 1461446703485210103287273052203988822378723970342;
+
+    address public immutable owner;
+    IWETH public immutable weth;
+    mapping(address => bool) public operators;
+    // Set while a loan or a V3 swap is open so only that contract can call back
+    address private activeCallback;
+    // Operator running the current program, the only account PULL takes from
+    address private activeOperator;
+
+    constructor(address _weth) {
+        owner = msg.sender;
+        weth = IWETH(_weth);
+        operators[msg.sender] = true;
+    }
+
+    receive() external payable {}
+
+    function setOperator(address operator, bool allowed) external {
+        require(msg.sender == owner, "Executor: not owner");
+        operators[operator] = allowed;
+    }
+
+    function execute(bytes calldata program) external {
+        require(operators[msg.sender], "Executor: not operator");
+        activeOperator = msg.sender;
+        _run(program);
+        activeOperator = address(0);
+    }
+
+    /// Sweeps anything a program left behind to the owner; address(0) sweeps ETH.
+    function withdraw(address token) external {
+        require(msg.sender == owner, "Executor: not owner");
+        if (token == address(0)) {
+            (bool ok,) = owner.call{value: address(this).balance}("");
+            require(ok, "Executor: ETH transfer failed");
+        } else {
+            token.safeTransfer(owner, IERC20(token).balanceOf(address(this)));
+        }
+    }
+
+    /// Aave V3 flash-loan callback; the repay action approves the pool.
+    function executeOperation(address, uint256, uint256, address initiator, bytes calldata params) external returns (bool) {
+        require(initiator == address(this), "Executor: untrusted initiator");
+        _callback(params);
+        return true;
+    }
+
+    /// Balancer vault callback.
+    function receiveFlashLoan(address[] calldata, uint256[] calldata, uint256[] calldata, bytes calldata userData) external {
+        _callback(userData);
+    }
+
+    /// Uniswap V2 flash-swap callback.
+    function uniswapV2Call(address sender, uint256, uint256, bytes calldata data) external {
+        require(sender == address(this), "Executor: untrusted initiator");
+        _callback(data);
+    }
+
+    /// Uniswap V3 flash callback.
+    function uniswapV3FlashCallback(uint256, uint256, bytes calldata data) external {
+        _callback(data);
+    }
+
+    /// Uniswap V3 swap callback; pays the pool what the swap owes it.
+    function uniswapV3SwapCallback(int256 amount0Delta, int256 amount1Delta, bytes calldata) external {
+        require(msg.sender == activeCallback, "Executor: untrusted callback");
+        IUniswapV3Pool pool = IUniswapV3Pool(msg.sender);
+        if (amount0Delta > 0) {
+            pool.token0().safeTransfer(msg.sender, uint256(amount0Delta));
+        } else {
+            pool.token1().safeTransfer(msg.sender, uint256(amount1Delta));
+        }
+    }
+
+    function _callback(bytes calldata program) internal {
+        require(msg.sender == activeCallback, "Executor: untrusted callback");
+        activeCallback = address(0);
+        _run(program);
+    }
+
+    function _run(bytes calldata p) internal {
+        uint256 i = 0;
+        while (i < p.length) {
+            uint8 op = uint8(p[i]);
+            i++;
+            if (op == SWAP_UNISWAP_V2) {
+                i = _swapUniswapV2(p, i);
+            } else if (op == SWAP_UNISWAP_V3) {
+                i = _swapUniswapV3(p, i);
+            } else if (op == SWAP_CURVE) {
+                i = _swapCurve(p, i);
+            } else if (op == FLASH_BORROW) {
+                i = _flashBorrow(p, i);
+            } else if (op == REPAY) {
+                i = _repay(p, i);
+            } else if (op == LIQUIDATE) {
+                i = _liquidate(p, i);
+            } else if (op == TRANSFER) {
+                i = _transfer(p, i);
+            } else if (op == CHECK_MIN_BALANCE) {
+                i = _checkMinBalance(p, i);
+            } else if (op == APPROVE) {
+                i = _approve(p, i);
+            } else if (op == CALL) {
+                i = _call(p, i);
+            } else if (op == PULL) {
+                i = _pull(p, i);
+            } else if (op == REDEEM) {
+                i = _redeem(p, i);
+            } else if (op == WRAP) {
+                weth.deposit{value: address(this).balance}();
+            } else {
+                revert("Executor: unknown action");
+            }
+        }
+    }
+
+    function _swapUniswapV2(bytes calldata p, uint256 i) internal returns (uint256) {
+        address pair;
+        bool zeroForOne;
+        uint256 amountIn;
+        uint256 minOut;
+        (pair, i) = _address(p, i);
+        (zeroForOne, i) = _flag(p, i);
+        (amountIn, i) = _amount(p, i);
+        (minOut, i) = _amount(p, i);
+
+        address tokenIn = zeroForOne ? IUniswapV2Pair(pair).token0() : IUniswapV2Pair(pair).token1();
+        amountIn = _resolve(tokenIn, amountIn);
+        (uint112 reserve0, uint112 reserve1,) = IUniswapV2Pair(pair).getReserves();
+        (uint256 reserveIn, uint256 reserveOut) = zeroForOne ? (reserve0, reserve1) : (reserve1, reserve0);
+        uint256 amountInWithFee = amountIn * 997;
+        uint256 amountOut = amountInWithFee * reserveOut / (reserveIn * 1000 + amountInWithFee);
+        require(amountOut >= minOut, "Executor: V2 output too low");
+
+        tokenIn.safeTransfer(pair, amountIn);
+        IUniswapV2Pair(pair).swap(zeroForOne ? 0 : amountOut, zeroForOne ? amountOut : 0, address(this), "");
+        return i;
+    }
+
+    function _swapUniswapV3(bytes calldata p, uint256 i) internal returns (uint256) {
+        address pool;
+        bool zeroForOne;
+        uint256 amountIn;
+        uint256 minOut;
+        (pool, i) = _address(p, i);
+        (zeroForOne, i) = _flag(p, i);
+        (amountIn, i) = _amount(p, i);
+        (minOut, i) = _amount(p, i);
+
+        amountIn = _resolve(zeroForOne ? IUniswapV3Pool(pool).token0() : IUniswapV3Pool(pool).token1(), amountIn);
+        address outerCallback = activeCallback;
+        activeCallback = pool;
+        (int256 amount0, int256 amount1) = IUniswapV3Pool(pool).swap(
+            address(this), zeroForOne, int256(amountIn), zeroForOne ? MIN_SQRT_RATIO + 1 : MAX_SQRT_RATIO - 1, ""
+        );
+        activeCallback = outerCallback;
+        require(uint256(-(zeroForOne ? amount1 : amount0)) >= minOut, "Executor: V3 output too low");
+        return i;
+    }
+
+    function _swapCurve(bytes calldata p, uint256 i) internal returns (uint256) {
+        address pool;
+        address tokenIn;
+        uint8 from;
+        uint8 to;
+        uint256 amountIn;
+        uint256 minOut;
+        (pool, i) = _address(p, i);
+        (tokenIn, i) = _address(p, i);
+        (from, i) = _uint8(p, i);
+        (to, i) = _uint8(p, i);
+        (amountIn, i) = _amount(p, i);
+        (minOut, i) = _amount(p, i);
+
+        amountIn = _resolve(tokenIn, amountIn);
+        tokenIn.safeApprove(pool, amountIn);
+        ICurvePool(pool).exchange(int128(uint128(from)), int128(uint128(to)), amountIn, minOut);
+        return i;
+    }
+
+    function _flashBorrow(bytes calldata p, uint256 i) internal returns (uint256) {
+        uint8 source;
+        address lender;
+        address token;
+        uint256 amount;
+        uint256 length;
+        (source, i) = _uint8(p, i);
+        (lender, i) = _address(p, i);
+        (token, i) = _address(p, i);
+        (amount, i) = _amount(p, i);
+        (length, i) = _uint16(p, i);
+        bytes calldata rest = p[i:i + length];
+
+        activeCallback = lender;
+        if (source == AAVE_V3) {
+            IAaveV3Pool(lender).flashLoanSimple(address(this), token, amount, rest, 0);
+        } else if (source == BALANCER) {
+            address[] memory tokens = new address[](1);
+            uint256[] memory amounts = new uint256[](1);
+            tokens[0] = token;
+            amounts[0] = amount;
+            IBalancerVault(lender).flashLoan(address(this), tokens, amounts, rest);
+        } else if (source == UNISWAP_V2) {
+            bool isToken0 = IUniswapV2Pair(lender).token0() == token;
+            IUniswapV2Pair(lender).swap(isToken0 ? amount : 0, isToken0 ? 0 : amount, address(this), rest);
+        } else if (source == UNISWAP_V3) {
+            bool isToken0 = IUniswapV3Pool(lender).token0() == token;
+            IUniswapV3Pool(lender).flash(address(this), isToken0 ? amount : 0, isToken0 ? 0 : amount, rest);
+        } else {
+            revert("Executor: unknown loan source");
+        }
+        require(activeCallback == address(0), "Executor: loan not taken");
+        return i + length;
+    }
+
+    function _repay(bytes calldata p, uint256 i) internal returns (uint256) {
+        uint8 source;
+        address lender;
+        address token;
+        uint256 amount;
+        (source, i) = _uint8(p, i);
+        (lender, i) = _address(p, i);
+        (token, i) = _address(p, i);
+        (amount, i) = _amount(p, i);
+
+        // Aave pulls its repayment once the callback returns
+        if (source == AAVE_V3) {
+            token.safeApprove(lender, amount);
+        } else {
+            token.safeTransfer(lender, amount);
+        }
+        return i;
+    }
+
+    function _liquidate(bytes calldata p, uint256 i) internal returns (uint256) {
+        uint8 venue;
+        bool debtIsNative;
+        address market;
+        address collateral;
+        address debt;
+        address user;
+        uint256 amount;
+        (venue, i) = _uint8(p, i);
+        (debtIsNative, i) = _flag(p, i);
+        (market, i) = _address(p, i);
+        (collateral, i) = _address(p, i);
+        (debt, i) = _address(p, i);
+        (user, i) = _address(p, i);
+        (amount, i) = _amount(p, i);
+
+        amount = _resolve(debt, amount);
+        // Allowances are reset afterwards: the market may take less than approved
+        if (venue == VENUE_AAVE) {
+            debt.safeApprove(market, amount);
+            IAaveV3Pool(market).liquidationCall(collateral, debt, user, amount, false);
+            debt.safeApprove(market, 0);
+        } else if (venue == VENUE_COMPOUND_V2) {
+            if (debtIsNative) {
+                weth.withdraw(amount);
+                ICEther(market).liquidateBorrow{value: amount}(user, collateral);
+            } else {
+                debt.safeApprove(market, amount);
+                require(ICToken(market).liquidateBorrow(user, amount, collateral) == 0, "Executor: liquidation failed");
+                debt.safeApprove(market, 0);
+            }
+        } else {
+            revert("Executor: unknown venue");
+        }
+        return i;
+    }
+
+    function _transfer(bytes calldata p, uint256 i) internal returns (uint256) {
+        address token;
+        address to;
+        uint256 amount;
+        (token, i) = _address(p, i);
+        (to, i) = _address(p, i);
+        (amount, i) = _amount(p, i);
+
+        token.safeTransfer(to, _resolve(token, amount));
+        return i;
+    }
+
+    function _checkMinBalance(bytes calldata p, uint256 i) internal view returns (uint256) {
+        address token;
+        uint256 minimum;
+        (token, i) = _address(p, i);
+        (minimum, i) = _amount(p, i);
+
+        require(IERC20(token).balanceOf(address(this)) >= minimum, "Executor: balance below minimum");
+        return i;
+    }
+
+    function _approve(bytes calldata p, uint256 i) internal returns (uint256) {
+        address token;
+        address spender;
+        uint256 amount;
+        (token, i) = _address(p, i);
+        (spender, i) = _address(p, i);
+        (amount, i) = _amount(p, i);
+
+        token.safeApprove(spender, _resolve(token, amount));
+        return i;
+    }
+
+    function _call(bytes calldata p, uint256 i) internal returns (uint256) {
+        address target;
+        uint256 length;
+        (target, i) = _address(p, i);
+        (length, i) = _uint16(p, i);
+
+        (bool ok, bytes memory result) = target.call(p[i:i + length]);
+        if (!ok) {
+            // Bubble the target's revert reason up
+            assembly {
+                revert(add(result, 32), mload(result))
+            }
+        }
+        return i + length;
+    }
+
+    function _pull(bytes calldata p, uint256 i) internal returns (uint256) {
+        address token;
+        uint256 amount;
+        (token, i) = _address(p, i);
+        (amount, i) = _amount(p, i);
+
+        require(amount != BALANCE, "Executor: pull needs an amount");
+        token.safeTransferFrom(activeOperator, address(this), amount);
+        return i;
+    }
+
+    function _redeem(bytes calldata p, uint256 i) internal returns (uint256) {
+        address cToken;
+        uint256 amount;
+        (cToken, i) = _address(p, i);
+        (amount, i) = _amount(p, i);
+
+        require(ICToken(cToken).redeem(_resolve(cToken, amount)) == 0, "Executor: redeem failed");
+        return i;
+    }
+
+    function _resolve(address token, uint256 amount) internal view returns (uint256) {
+        return amount == BALANCE ? IERC20(token).balanceOf(address(this)) : amount;
+    }
+
+    function _address(bytes calldata p, uint256 i) internal pure returns (address, uint256) {
+        return (address(bytes20(p[i:i + 20])), i + 20);
+    }
+
+    function _uint8(bytes calldata p, uint256 i) internal pure returns (uint8, uint256) {
+        return (uint8(p[i]), i + 1);
+    }
+
+    function _uint16(bytes calldata p, uint256 i) internal pure returns (uint256, uint256) {
+        return (uint16(bytes2(p[i:i + 2])), i + 2);
+    }
+
+    function _flag(bytes calldata p, uint256 i) internal pure returns (bool, uint256) {
+        return (uint8(p[i]) & FLAG != 0, i + 1);
+    }
+
+    function _amount(bytes calldata p, uint256 i) internal pure returns (uint256, uint256) {
+        uint256 length = uint8(p[i]);
+        i++;
+        if (length == 0xff) {
+            return (BALANCE, i);
+        }
+        require(length <= 32, "Executor: bad amount");
+        // Left-aligned in the word, so shift the padding out
+        return (uint256(bytes32(p[i:i + length])) >> (8 * (32 - length)), i + length);
+    }
+}
@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+import "./SafeToken.sol";
+
+interface IERC20 {
+    function balanceOf(address account) external view returns (uint256);
+}
+
+interface IWETH is IERC20 {
+    function deposit() external payable;
+    function withdraw(uint256 amount) external;
+}
+
+interface IAaveV3Pool {
+    function flashLoanSimple(address receiverAddress, address asset, uint256 amount, bytes calldata params, uint16 referralCode) external;
+}
+
+interface IAavePool {
+    function repay(address asset, uint256 amount, uint256 interestRateMode, address onBehalfOf) external returns (uint256);
+    function withdraw(address asset, uint256 amount, address to) external returns (uint256);
+    // V3 keeps V2's name for supply
+    function deposit(address asset, uint256 amount, address onBehalfOf, uint16 referralCode) external;
+}
+
+interface ICToken is IERC20 {
+    function repayBorrowBehalf(address borrower, uint256 repayAmount) external returns (uint256);
+    function redeemUnderlying(uint256 redeemAmount) external returns (uint256);
+    function mint(uint256 mintAmount) external returns (uint256);
+    function exchangeRateCurrent() external returns (uint256);
+}
+
+interface ICEther is IERC20 {
+    function repayBorrowBehalf(address borrower) external payable;
+    function mint() external payable;
+}
+
+interface IUniswapV2Router {
+    function swapTokensForExactTokens(uint256 amountOut, uint256 amountInMax, address[] calldata path, address to, uint256 deadline)
+        external
+        returns (uint256[] memory amounts);
+}
+
+/// Protects our own borrowing accounts: unwinds part of a position with a flash loan of the debt
+/// asset (repay, withdraw collateral, sell just enough of it to repay the loan), or tops up its
+/// collateral from a reserve wallet. Accounts approve this contract for their collateral tokens.
+/// Kept apart from MultiActionExecutor on purpose: it holds standing allowances over our borrowing
+/// accounts, which an executor that calls arbitrary targets must never have.
+contract PositionUnwinder {
+    using SafeToken for address;
+
+    uint8 constant AAVE = 0;
+    uint8 constant COMPOUND_V2 = 1;
+    uint256 constant VARIABLE_RATE = 2;
+
+    struct Unwind {
+        uint8 venue;
+        // Aave pool, or the borrowed cToken
+        address market;
+        address account;
+        // aToken, or the collateral cToken
+        address collateralToken;
+        address collateralUnderlying;
+        // Flash-borrowed token
+        address debtUnderlying;
+        uint256 repayAmount;
+        bool debtIsNative;
+        bool collateralIsNative;
+        address router;
+        // collateralUnderlying -> ... -> debtUnderlying; unused when they are the same token
+        address[] path;
+        // Underlying collateral withdrawn; what the sale does not use goes back to the account
+        uint256 maxCollateralIn;
+    }
+
+    address public immutable owner;
+    IAaveV3Pool public immutable pool;
+    IWETH public immutable weth;
+    mapping(address => bool) public operators;
+
+    constructor(address _pool, address _weth) {
+        owner = msg.sender;
+        pool = IAaveV3Pool(_pool);
+        weth = IWETH(_weth);
+        operators[msg.sender] = true;
+    }
+
+    receive() external payable {}
+
+    function setOperator(address operator, bool allowed) external {
+        require(msg.sender == owner, "PositionUnwinder: not owner");
+        operators[operator] = allowed;
+    }
+
+    function unwind(Unwind calldata order) external {
+        require(operators[msg.sender] || msg.sender == order.account, "PositionUnwinder: not operator");
+        pool.flashLoanSimple(address(this), order.debtUnderlying, order.repayAmount, abi.encode(order), 0);
+    }
+
+    /// Supplies `amount` of `underlying` from `reserve` as collateral of `account`.
+    function topUp(uint8 venue, address market, address account, address underlying, bool isNative, uint256 amount, address reserve)
+        external
+    {
+        require(operators[msg.sender], "PositionUnwinder: not operator");
+        underlying.safeTransferFrom(reserve, address(this), amount);
+
+        if (venue == AAVE) {
+            underlying.safeApprove(market, amount);
+            IAavePool(market).deposit(underlying, amount, account, 0);
+        } else if (venue == COMPOUND_V2) {
+            if (isNative) {
+                weth.withdraw(amount);
+                ICEther(market).mint{value: amount}();
+            } else {
+                underlying.safeApprove(market, amount);
+                require(ICToken(market).mint(amount) == 0, "PositionUnwinder: mint failed");
+                underlying.safeApprove(market, 0);
+            }
+            market.safeTransfer(account, ICToken(market).balanceOf(address(this)));
+        } else {
+            revert("PositionUnwinder: unknown venue");
+        }
+    }
+
+    /// Aave V3 flash-loan callback.
+    function executeOperation(address asset, uint256 amount, uint256 premium, address initiator, bytes calldata params)
+        external
+        returns (bool)
+    {
+        require(msg.sender == address(pool) && initiator == address(this), "PositionUnwinder: untrusted callback");
+        Unwind memory order = abi.decode(params, (Unwind));
+        uint256 owed = amount + premium;
+
+        _repay(order, amount);
+        _withdrawCollateral(order);
+        if (order.collateralUnderlying != asset) {
+            order.collateralUnderlying.safeApprove(order.router, order.maxCollateralIn);
+            IUniswapV2Router(order.router).swapTokensForExactTokens(
+                owed, order.maxCollateralIn, order.path, address(this), block.timestamp
+            );
+            order.collateralUnderlying.safeApprove(order.router, 0);
+        }
+
+        // Aave repays at most the outstanding debt, leaving some of the loan unspent
+        uint256 leftover = IERC20(order.collateralUnderlying).balanceOf(address(this));
+        if (order.collateralUnderlying == asset) {
+            leftover -= owed;
+        } else {
+            uint256 unspent = IERC20(asset).balanceOf(address(this)) - owed;
+            if (unspent > 0) {
+                asset.safeTransfer(order.account, unspent);
+            }
+        }
+
+        asset.safeApprove(address(pool), owed);
+        if (leftover > 0) {
+            order.collateralUnderlying.safeTransfer(order.account, leftover);
+        }
+        return true;
+    }
+
+    function _repay(Unwind memory order, uint256 amount) internal {
+        if (order.venue == AAVE) {
+            order.debtUnderlying.safeApprove(order.market, amount);
+            IAavePool(order.market).repay(order.debtUnderlying, amount, VARIABLE_RATE, order.account);
+            // Aave repays at most the outstanding debt
+            order.debtUnderlying.safeApprove(order.market, 0);
+        } else if (order.venue == COMPOUND_V2) {
+            if (order.debtIsNative) {
+                weth.withdraw(amount);
+                ICEther(order.market).repayBorrowBehalf{value: amount}(order.account);
+            } else {
+                order.debtUnderlying.safeApprove(order.market, amount);
+                require(ICToken(order.market).repayBorrowBehalf(order.account, amount) == 0, "PositionUnwinder: repay failed");
+                order.debtUnderlying.safeApprove(order.market, 0);
+            }
+        } else {
+            revert("PositionUnwinder: unknown venue");
+        }
+    }
+
+    function _withdrawCollateral(Unwind memory order) internal {
+        if (order.venue == AAVE) {
+            // aTokens are 1:1 with the underlying
+            order.collateralToken.safeTransferFrom(order.account, address(this), order.maxCollateralIn);
+            IAavePool(order.market).withdraw(order.collateralUnderlying, order.maxCollateralIn, address(this));
+        } else {
+            ICToken cToken = ICToken(order.collateralToken);
+            uint256 shares = order.maxCollateralIn * 1e18 / cToken.exchangeRateCurrent() + 1;
+            order.collateralToken.safeTransferFrom(order.account, address(this), shares);
+            require(cToken.redeemUnderlying(order.maxCollateralIn) == 0, "PositionUnwinder: redeem failed");
+            uint256 dust = cToken.balanceOf(address(this));
+            if (dust > 0) {
+                order.collateralToken.safeTransfer(order.account, dust);
+            }
+            if (order.collateralIsNative) {
+                weth.deposit{value: address(this).balance}();
+            }
+        }
+    }
+}
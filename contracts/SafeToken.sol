@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+/// ERC20 calls that work with tokens returning nothing, like USDT, as well as those returning a
+/// bool. Allowances are forced: USDT refuses to change a nonzero allowance to another nonzero one,
+/// so a failed approve is retried after resetting to 0.
+library SafeToken {
+    function safeTransfer(address token, address to, uint256 amount) internal {
+        require(_call(token, abi.encodeWithSelector(0xa9059cbb, to, amount)), "SafeToken: transfer failed");
+    }
+
+    function safeTransferFrom(address token, address from, address to, uint256 amount) internal {
+        require(_call(token, abi.encodeWithSelector(0x23b872dd, from, to, amount)), "SafeToken: transferFrom failed");
+    }
+
+    function safeApprove(address token, address spender, uint256 amount) internal {
+        bytes memory approveCall = abi.encodeWithSelector(0x095ea7b3, spender, amount);
+        if (!_call(token, approveCall)) {
+            require(_call(token, abi.encodeWithSelector(0x095ea7b3, spender, 0)), "SafeToken: approve failed");
+            require(_call(token, approveCall), "SafeToken: approve failed");
+        }
+    }
+
+    function _call(address token, bytes memory data) private returns (bool) {
+        (bool ok, bytes memory result) = token.call(data);
+        return ok && (result.length == 0 || abi.decode(result, (bool))) && token.code.length > 0;
+    }
+}
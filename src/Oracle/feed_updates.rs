@@ -0,0 +1,109 @@
+// src/oracle/feed_updates.rs
+use ethers::{
+    providers::{Middleware, Provider, Ws},
+    types::{Address, Filter, Log, H256},
+    utils::keccak256,
+};
+use futures::{future, Stream, StreamExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::info;
+use anyhow::Result;
+
+use super::price_oracle::{ChainlinkFeed, PriceOracle};
+
+// Every aggregator emits AnswerUpdated; OCR aggregators emit NewTransmission in the same transaction
+const UPDATE_EVENTS: [&str; 3] = [
+    "AnswerUpdated(int256,uint256,uint256)",
+    "NewTransmission(uint32,int192,address,int192[],bytes,bytes32)",
+    "NewTransmission(uint32,int192,address,uint32,int192[],bytes,int192,bytes32,uint40)",
+];
+
+/// A new answer posted to the feed pricing `token`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeedUpdate {
+    pub token: Address,
+    pub aggregator: Address,
+    pub block_number: u64,
+}
+
+/// Watches the aggregators behind `PriceOracle`'s Chainlink feeds for new answers.
+///
+/// Feeds are proxies and the events come from the aggregator they point at, which is resolved
+/// once on connect; reconnect after a feed is moved to a new aggregator.
+pub struct FeedUpdateWatcher {
+    provider: Arc<Provider<Ws>>,
+    /// Aggregator → token it prices.
+    aggregators: HashMap<Address, Address>,
+}
+
+impl FeedUpdateWatcher {
+    pub async fn connect(oracle: &PriceOracle, provider: Arc<Provider<Ws>>) -> Result<Self> {
+        let mut aggregators = HashMap::new();
+        for (&token, &feed) in oracle.feeds() {
+            let aggregator = ChainlinkFeed::new(feed, provider.clone()).aggregator().call().await?;
+            aggregators.insert(aggregator, token);
+        }
+        info!("Watching {} Chainlink aggregators for new answers", aggregators.len());
+
+        Ok(Self { provider, aggregators })
+    }
+
+    /// New answers as their logs arrive, one per transmission.
+    pub async fn subscribe(&self) -> Result<impl Stream<Item = FeedUpdate> + '_> {
+        let topics: Vec<H256> = UPDATE_EVENTS.iter().map(|event| H256::from(keccak256(event))).collect();
+        let filter = Filter::new()
+            .address(self.aggregators.keys().copied().collect::<Vec<_>>())
+            .topic0(topics);
+
+        let mut last_seen = None;
+        let logs = self.provider.subscribe_logs(&filter).await?;
+        Ok(logs.filter_map(move |log| future::ready(feed_update(&self.aggregators, &log, &mut last_seen))))
+    }
+}
+
+/// The update `log` reports, unless it was reorged out or is the second event of a transmission
+/// already reported.
+fn feed_update(aggregators: &HashMap<Address, Address>, log: &Log, last_seen: &mut Option<(H256, Address)>) -> Option<FeedUpdate> {
+    if log.removed == Some(true) {
+        return None;
+    }
+    let token = *aggregators.get(&log.address)?;
+    let seen = (log.transaction_hash?, log.address);
+    if *last_seen == Some(seen) {
+        return None;
+    }
+    *last_seen = Some(seen);
+
+    Some(FeedUpdate {
+        token,
+        aggregator: log.address,
+        block_number: log.block_number?.as_u64(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_one_update_per_transmission() {
+        let (aggregator, token) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let aggregators = HashMap::from([(aggregator, token)]);
+        let log = |tx: u64, removed: bool| Log {
+            address: aggregator,
+            transaction_hash: Some(H256::from_low_u64_be(tx)),
+            block_number: Some(100u64.into()),
+            removed: Some(removed),
+            ..Default::default()
+        };
+
+        let mut last_seen = None;
+        let update = FeedUpdate { token, aggregator, block_number: 100 };
+        // NewTransmission then AnswerUpdated from the same transmit
+        assert_eq!(feed_update(&aggregators, &log(7, false), &mut last_seen), Some(update));
+        assert_eq!(feed_update(&aggregators, &log(7, false), &mut last_seen), None);
+        assert_eq!(feed_update(&aggregators, &log(8, true), &mut last_seen), None);
+        assert_eq!(feed_update(&aggregators, &log(8, false), &mut last_seen), Some(update));
+    }
+}
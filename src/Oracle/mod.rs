@@ -1 +1,6 @@
+// src/oracle/mod.rs
+pub mod feed_updates;
+pub mod price_oracle;
 
+pub use feed_updates::{FeedUpdate, FeedUpdateWatcher};
+pub use price_oracle::{PriceOracle, PriceOracleConfig};
@@ -2,17 +2,44 @@
 use ethers::{
     prelude::*,
     providers::{Provider, Ws},
-    types::{Address, U256},
+    types::Address,
 };
+use serde::Deserialize;
 use std::sync::Arc;
 use std::collections::HashMap;
 use tokio::sync::RwLock;
 use anyhow::Result;
 
+use crate::math::Wad;
+
+abigen!(
+    ChainlinkFeed,
+    r#"[
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)
+        function decimals() external view returns (uint8)
+        function aggregator() external view returns (address)
+    ]"#
+);
+
+/// `config/price_oracle.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PriceOracleConfig {
+    /// Token → Chainlink USD feed proxy.
+    pub chainlink_feeds: HashMap<Address, Address>,
+    /// Seconds a price is reused before the feed is read again.
+    pub cache_duration: u64,
+}
+
+impl PriceOracleConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
 pub struct PriceOracle {
     provider: Arc<Provider<Ws>>,
     chainlink_feeds: HashMap<Address, Address>,
-    cache: Arc<RwLock<HashMap<Address, (U256, u64)>>>,
+    cache: Arc<RwLock<HashMap<Address, (Wad, u64)>>>,
     cache_duration: u64,
 }
 
@@ -30,7 +57,17 @@ impl PriceOracle {
         }
     }
 
-    pub async fn get_price(&self, token: Address) -> Result<U256> {
+    pub fn from_config(provider: Arc<Provider<Ws>>, config: &PriceOracleConfig) -> Self {
+        Self::new(provider, config.chainlink_feeds.clone(), config.cache_duration)
+    }
+
+    /// Token → Chainlink feed proxy it is priced by.
+    pub fn feeds(&self) -> &HashMap<Address, Address> {
+        &self.chainlink_feeds
+    }
+
+    /// USD price of one whole token.
+    pub async fn get_price(&self, token: Address) -> Result<Wad> {
         // Check cache first
         if let Some((price, timestamp)) = self.cache.read().await.get(&token) {
             if self.is_cache_valid(*timestamp) {
@@ -46,24 +83,29 @@ impl PriceOracle {
         Ok(price)
     }
 
-    async fn fetch_chainlink_price(&self, token: Address) -> Result<U256> {
+    async fn fetch_chainlink_price(&self, token: Address) -> Result<Wad> {
         let feed_address = self.chainlink_feeds.get(&token)
             .ok_or_else(|| anyhow::anyhow!("No price feed for token"))?;
 
         let feed = ChainlinkFeed::new(*feed_address, self.provider.clone());
-        let (_, price, _, updated_at, _) = feed.latest_round_data().await?;
+        let (_, answer, _, updated_at, _) = feed.latest_round_data().call().await?;
+        let decimals = feed.decimals().call().await?;
 
         // Check for stale prices
-        if self.current_timestamp()? - updated_at > 3600 {
+        if self.current_timestamp()?.saturating_sub(updated_at.low_u64()) > 3600 {
             return Err(anyhow::anyhow!("Price data is stale"));
         }
 
-        Ok(price)
+        if answer <= I256::zero() {
+            return Err(anyhow::anyhow!("Non-positive price from feed {:?}", feed_address));
+        }
+
+        Ok(Wad::from_scaled(answer.into_raw(), decimals)?)
     }
 
     fn is_cache_valid(&self, timestamp: u64) -> bool {
         match self.current_timestamp() {
-            Ok(current_time) => current_time - timestamp <= self.cache_duration,
+            Ok(current_time) => current_time.saturating_sub(timestamp) <= self.cache_duration,
             Err(_) => false,
         }
     }
@@ -74,81 +116,3 @@ impl PriceOracle {
             .as_secs())
     }
 }
-
-// src/lending/lending_pool.rs
-#[async_trait]
-pub trait LendingPool {
-    async fn get_user_account_data(&self, user: Address) -> Result<UserAccountData>;
-    async fn liquidate_position(&self, params: LiquidationParams) -> Result<()>;
-}
-
-#[derive(Debug)]
-pub struct UserAccountData {
-    pub collateral_value: U256,
-    pub debt_value: U256,
-    pub health_factor: U256,
-    pub liquidation_threshold: U256,
-}
-
-#[derive(Debug)]
-pub struct LiquidationParams {
-    pub user: Address,
-    pub collateral_token: Address,
-    pub debt_token: Address,
-    pub debt_to_cover: U256,
-    pub receive_underlying: bool,
-}
-
-pub struct AaveLendingPool {
-    contract: Contract<Provider<Ws>>,
-    provider: Arc<Provider<Ws>>,
-}
-
-impl AaveLendingPool {
-    pub fn new(address: Address, provider: Arc<Provider<Ws>>) -> Self {
-        let contract = Contract::new(
-            address,
-            include_bytes!("../abi/AaveLendingPool.json"),
-            provider.clone(),
-        );
-        
-        Self { contract, provider }
-    }
-}
-
-#[async_trait]
-impl LendingPool for AaveLendingPool {
-    async fn get_user_account_data(&self, user: Address) -> Result<UserAccountData> {
-        let result = self.contract
-            .method("getUserAccountData", user)?
-            .call()
-            .await?;
-
-        Ok(UserAccountData {
-            collateral_value: result.0,
-            debt_value: result.1,
-            health_factor: result.2,
-            liquidation_threshold: result.3,
-        })
-    }
-
-    async fn liquidate_position(&self, params: LiquidationParams) -> Result<()> {
-        let tx = self.contract
-            .method(
-                "liquidationCall",
-                (
-                    params.collateral_token,
-                    params.debt_token,
-                    params.user,
-                    params.debt_to_cover,
-                    params.receive_underlying,
-                )
-            )?
-            .send()
-            .await?;
-
-        tx.await?;
-        Ok(())
-    }
-}
-
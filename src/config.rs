@@ -1,19 +1,77 @@
 use anyhow::Result;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
 
+use crate::math::Wad;
+use crate::signer::SignerConfig;
+
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub node_url: String,
-    pub private_key: String,
+    pub signer: SignerConfig,
     pub gas_limit: u64,
     pub gas_price: u64,
-    pub min_profit: f64,
+    /// Priority fee per gas paid to the block builder, in wei.
+    pub priority_fee: u64,
+    /// Minimum net profit per opportunity, in ETH.
+    pub min_profit: Wad,
     pub max_slippage: f64,
+    pub slippage_overrides: HashMap<String, f64>,
+    pub swap_deadline_seconds: Option<u64>,
+    pub swap_deadline_slots: Option<u64>,
+    /// Most blocks a bundle is offered for, further capped by the swap deadline.
+    pub bundle_max_blocks: u64,
+    /// `eth_sendBundle` endpoint; without one, bundle submissions are broadcast publicly.
+    pub bundle_relay_url: Option<String>,
     pub dex_contracts: DexContracts,
     pub tokens: Tokens,
 }
 
+/// `config/global_config.json`, which allows `//` comments.
+#[derive(Debug, Deserialize)]
+pub struct GlobalConfig {
+    pub network: String,
+    pub default_gas_limit: u64,
+    pub log_level: String,
+    pub bot_mode: String,
+    pub monitoring_enabled: bool,
+    #[serde(default)]
+    pub dry_run: DryRunConfig,
+    pub strategies: HashMap<String, StrategyEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StrategyEntry {
+    pub enabled: bool,
+    pub config_path: String,
+}
+
+/// Paper trading: strategies run end to end but transactions are journaled instead of broadcast.
+#[derive(Debug, Deserialize)]
+pub struct DryRunConfig {
+    pub enabled: bool,
+    pub journal_path: String,
+}
+
+impl Default for DryRunConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            journal_path: "Logs/dry_run.jsonl".to_string(),
+        }
+    }
+}
+
+impl GlobalConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        let settings = ::config::Config::builder()
+            .add_source(::config::File::new(path, ::config::FileFormat::Json5))
+            .build()?;
+        Ok(settings.try_deserialize()?)
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct DexContracts {
     pub uniswap_v2_router: String,
@@ -33,11 +91,17 @@ impl Config {
     pub fn new() -> Result<Self> {
         Ok(Self {
             node_url: env::var("NODE_URL")?,
-            private_key: env::var("PRIVATE_KEY")?,
+            signer: SignerConfig::from_env()?,
             gas_limit: env::var("GAS_LIMIT")?.parse()?,
             gas_price: env::var("GAS_PRICE")?.parse()?,
+            priority_fee: env::var("PRIORITY_FEE").map(|v| v.parse()).unwrap_or(Ok(1_000_000_000))?,
             min_profit: env::var("MIN_PROFIT")?.parse()?,
             max_slippage: env::var("MAX_SLIPPAGE")?.parse()?,
+            slippage_overrides: parse_slippage_overrides(&env::var("SLIPPAGE_OVERRIDES").unwrap_or_default())?,
+            swap_deadline_seconds: env::var("SWAP_DEADLINE_SECONDS").ok().map(|v| v.parse()).transpose()?,
+            swap_deadline_slots: env::var("SWAP_DEADLINE_SLOTS").ok().map(|v| v.parse()).transpose()?,
+            bundle_max_blocks: env::var("BUNDLE_MAX_BLOCKS").map(|v| v.parse()).unwrap_or(Ok(1))?,
+            bundle_relay_url: env::var("BUNDLE_RELAY_URL").ok(),
             dex_contracts: DexContracts {
                 uniswap_v2_router: env::var("UNISWAP_V2_ROUTER")?,
                 uniswap_v3_router: env::var("UNISWAP_V3_ROUTER")?,
@@ -53,3 +117,14 @@ impl Config {
     }
 }
 
+// Parses `token=percent` pairs, e.g. "0xabc...=1.5,0xdef...=3"
+fn parse_slippage_overrides(raw: &str) -> Result<HashMap<String, f64>> {
+    let mut overrides = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (token, percent) = entry
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid slippage override: {}", entry))?;
+        overrides.insert(token.trim().to_string(), percent.trim().parse()?);
+    }
+    Ok(overrides)
+}
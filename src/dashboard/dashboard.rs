@@ -1,19 +1,20 @@
+use ethers::types::Address;
 use std::collections::HashMap;
 
 pub struct Dashboard {
   pub dexes: HashMap<String, String>,
   pub token: String,
   pub gas_price: u64,
-  pub private_key: String,
+  pub from: Address,
 }
 
 impl Dashboard {
-  pub fn new(dexes: HashMap<String, String>, token: String, gas_price: u64, private_key: String) -> Self {
+  pub fn new(dexes: HashMap<String, String>, token: String, gas_price: u64, from: Address) -> Self {
     Dashboard {
       dexes,
       token,
       gas_price,
-      private_key,
+      from,
     }
   }
 
@@ -0,0 +1,529 @@
+// src/execution/actions.rs
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    abi::{self, ParamType, Token},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256},
+    utils::id,
+};
+use serde::Deserialize;
+
+use super::approvals::ApprovalsConfig;
+use super::flash_loans::{FlashLoanQuote, FlashLoanSource};
+use crate::lending::LiquidationVenue;
+use crate::signer::PolicyConfig;
+
+const SWAP_UNISWAP_V2: u8 = 0x01;
+const SWAP_UNISWAP_V3: u8 = 0x02;
+const SWAP_CURVE: u8 = 0x03;
+const FLASH_BORROW: u8 = 0x04;
+const REPAY: u8 = 0x05;
+const LIQUIDATE: u8 = 0x06;
+const TRANSFER: u8 = 0x07;
+const CHECK_MIN_BALANCE: u8 = 0x08;
+const APPROVE: u8 = 0x09;
+const CALL: u8 = 0x0a;
+const PULL: u8 = 0x0b;
+const REDEEM: u8 = 0x0c;
+const WRAP_NATIVE: u8 = 0x0d;
+
+const FLAG: u8 = 0x01;
+// Length byte standing for the executor's whole balance
+const BALANCE_MARKER: u8 = 0xff;
+
+const EXECUTE_SIGNATURE: &str = "execute(bytes)";
+// execute(bytes)
+const EXECUTE_SELECTOR: &str = "09c5eabe";
+/// Strategies that run programs on the executor.
+const EXECUTOR_STRATEGIES: [&str; 2] = ["arbitrage", "liquidation"];
+/// The strategy whose programs `Pull` capital from the wallet.
+const PULL_STRATEGY: &str = "arbitrage";
+
+/// Amount meaning "everything the executor holds of the token" when the action runs, for
+/// chaining swaps whose input is the previous one's output.
+pub const BALANCE: U256 = U256::MAX;
+
+/// `config/executor.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecutorConfig {
+    /// Deployed `contracts/MultiActionExecutor.sol`.
+    pub executor: Address,
+}
+
+impl ExecutorConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Lets the strategies run programs on the executor, and lets arbitrage approve it for the
+    /// capital its programs pull. Fails while `executor` is still the zero address.
+    pub fn register(&self, approvals: &mut ApprovalsConfig, policy: &mut PolicyConfig) -> Result<()> {
+        for strategy in EXECUTOR_STRATEGIES {
+            policy.allow(strategy, self.executor, &[EXECUTE_SELECTOR])?;
+        }
+        approvals.add_spender(PULL_STRATEGY, self.executor);
+
+        Ok(())
+    }
+}
+
+/// One step of a program for `contracts/MultiActionExecutor.sol`.
+///
+/// Swaps pay from and receive into the executor. A `FlashBorrow` runs its own program inside
+/// the loan, which must include its `Repay`; the actions after it run once the loan is repaid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Swap directly against a pair, priced from its reserves at the 0.3% fee.
+    SwapUniswapV2 { pair: Address, zero_for_one: bool, amount_in: U256, amount_out_min: U256 },
+    SwapUniswapV3 { pool: Address, zero_for_one: bool, amount_in: U256, amount_out_min: U256 },
+    /// `exchange(i, j, ...)` on a Curve pool, which pulls `token_in` from the executor.
+    SwapCurve { pool: Address, token_in: Address, i: u8, j: u8, amount_in: U256, amount_out_min: U256 },
+    FlashBorrow { source: FlashLoanSource, lender: Address, token: Address, amount: U256, program: Vec<Action> },
+    /// Hands the lender what it is owed: approved for Aave, transferred for the others.
+    Repay { source: FlashLoanSource, lender: Address, token: Address, amount: U256 },
+    /// Repays `debt_to_cover` of `user`'s `debt` on `market`. `collateral` is the Aave reserve
+    /// seized, or the collateral cToken for Compound.
+    Liquidate {
+        venue: LiquidationVenue,
+        market: Address,
+        collateral: Address,
+        debt: Address,
+        user: Address,
+        debt_to_cover: U256,
+        /// The debt is repaid in native ETH unwrapped from the executor's WETH.
+        debt_is_native: bool,
+    },
+    Transfer { token: Address, to: Address, amount: U256 },
+    /// Reverts the whole program unless the executor holds at least `minimum` of `token`.
+    CheckMinBalance { token: Address, minimum: U256 },
+    Approve { token: Address, spender: Address, amount: U256 },
+    /// Calls `target` with `data` from the executor, e.g. a router swap or a protocol purchase.
+    Call { target: Address, data: Bytes },
+    /// Takes `amount` of `token` from the operator running the program, which must have
+    /// approved the executor.
+    Pull { token: Address, amount: U256 },
+    /// Redeems Compound cTokens for their underlying.
+    Redeem { c_token: Address, amount: U256 },
+    /// Wraps the executor's whole ETH balance into WETH.
+    WrapNative,
+}
+
+impl Action {
+    /// Takes the quoted loan, runs `program` inside it and repays the loan with its fee.
+    pub fn flash_borrow(quote: &FlashLoanQuote, mut program: Vec<Action>) -> Self {
+        program.push(Action::repay(quote));
+        Action::FlashBorrow {
+            source: quote.source,
+            lender: quote.lender,
+            token: quote.token,
+            amount: quote.amount,
+            program,
+        }
+    }
+
+    /// Repays the quoted loan with its fee.
+    pub fn repay(quote: &FlashLoanQuote) -> Self {
+        Action::Repay {
+            source: quote.source,
+            lender: quote.lender,
+            token: quote.token,
+            amount: quote.amount + quote.fee,
+        }
+    }
+}
+
+/// The executor at `address`.
+pub struct MultiActionExecutor {
+    address: Address,
+}
+
+impl MultiActionExecutor {
+    pub fn new(address: Address) -> Self {
+        Self { address }
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    pub fn execute_tx(&self, actions: &[Action]) -> Result<TypedTransaction> {
+        Ok(TransactionRequest::new().to(self.address).data(encode_execute(actions)?).into())
+    }
+}
+
+/// `execute(program)` calldata for the executor.
+pub fn encode_execute(actions: &[Action]) -> Result<Bytes> {
+    let mut data = id(EXECUTE_SIGNATURE).to_vec();
+    data.extend_from_slice(&abi::encode(&[Token::Bytes(encode_actions(actions)?.to_vec())]));
+    Ok(data.into())
+}
+
+/// The actions in `execute` calldata, e.g. a submitted transaction's input.
+pub fn decode_execute(calldata: &[u8]) -> Result<Vec<Action>> {
+    if calldata.len() < 4 || calldata[..4] != id(EXECUTE_SIGNATURE) {
+        bail!("Calldata is not an executor `execute` call");
+    }
+    match abi::decode(&[ParamType::Bytes], &calldata[4..])?.pop() {
+        Some(Token::Bytes(program)) => decode_actions(&program),
+        _ => Err(anyhow!("Malformed `execute` arguments")),
+    }
+}
+
+/// Packs `actions` the way the executor reads them, failing on a nested program or call data
+/// longer than its two-byte length allows.
+pub fn encode_actions(actions: &[Action]) -> Result<Bytes> {
+    let mut out = Vec::new();
+    for action in actions {
+        match action {
+            Action::SwapUniswapV2 { pair, zero_for_one, amount_in, amount_out_min } => {
+                out.push(SWAP_UNISWAP_V2);
+                put_address(&mut out, *pair);
+                put_flag(&mut out, *zero_for_one);
+                put_amount(&mut out, *amount_in);
+                put_amount(&mut out, *amount_out_min);
+            }
+            Action::SwapUniswapV3 { pool, zero_for_one, amount_in, amount_out_min } => {
+                out.push(SWAP_UNISWAP_V3);
+                put_address(&mut out, *pool);
+                put_flag(&mut out, *zero_for_one);
+                put_amount(&mut out, *amount_in);
+                put_amount(&mut out, *amount_out_min);
+            }
+            Action::SwapCurve { pool, token_in, i, j, amount_in, amount_out_min } => {
+                out.push(SWAP_CURVE);
+                put_address(&mut out, *pool);
+                put_address(&mut out, *token_in);
+                out.push(*i);
+                out.push(*j);
+                put_amount(&mut out, *amount_in);
+                put_amount(&mut out, *amount_out_min);
+            }
+            Action::FlashBorrow { source, lender, token, amount, program } => {
+                out.push(FLASH_BORROW);
+                out.push(*source as u8);
+                put_address(&mut out, *lender);
+                put_address(&mut out, *token);
+                put_amount(&mut out, *amount);
+                put_bytes(&mut out, &encode_actions(program)?)?;
+            }
+            Action::Repay { source, lender, token, amount } => {
+                out.push(REPAY);
+                out.push(*source as u8);
+                put_address(&mut out, *lender);
+                put_address(&mut out, *token);
+                put_amount(&mut out, *amount);
+            }
+            Action::Liquidate { venue, market, collateral, debt, user, debt_to_cover, debt_is_native } => {
+                out.push(LIQUIDATE);
+                out.push(*venue as u8);
+                put_flag(&mut out, *debt_is_native);
+                put_address(&mut out, *market);
+                put_address(&mut out, *collateral);
+                put_address(&mut out, *debt);
+                put_address(&mut out, *user);
+                put_amount(&mut out, *debt_to_cover);
+            }
+            Action::Transfer { token, to, amount } => {
+                out.push(TRANSFER);
+                put_address(&mut out, *token);
+                put_address(&mut out, *to);
+                put_amount(&mut out, *amount);
+            }
+            Action::CheckMinBalance { token, minimum } => {
+                out.push(CHECK_MIN_BALANCE);
+                put_address(&mut out, *token);
+                put_amount(&mut out, *minimum);
+            }
+            Action::Approve { token, spender, amount } => {
+                out.push(APPROVE);
+                put_address(&mut out, *token);
+                put_address(&mut out, *spender);
+                put_amount(&mut out, *amount);
+            }
+            Action::Call { target, data } => {
+                out.push(CALL);
+                put_address(&mut out, *target);
+                put_bytes(&mut out, data)?;
+            }
+            Action::Pull { token, amount } => {
+                if *amount == BALANCE {
+                    bail!("Pull needs an explicit amount of {:?}", token);
+                }
+                out.push(PULL);
+                put_address(&mut out, *token);
+                put_amount(&mut out, *amount);
+            }
+            Action::Redeem { c_token, amount } => {
+                out.push(REDEEM);
+                put_address(&mut out, *c_token);
+                put_amount(&mut out, *amount);
+            }
+            Action::WrapNative => out.push(WRAP_NATIVE),
+        }
+    }
+
+    Ok(out.into())
+}
+
+/// Unpacks a program, failing on anything the executor would revert on while reading it.
+pub fn decode_actions(program: &[u8]) -> Result<Vec<Action>> {
+    let mut reader = Reader { data: program, offset: 0 };
+    let mut actions = Vec::new();
+
+    while !reader.is_empty() {
+        let op = reader.byte()?;
+        let action = match op {
+            SWAP_UNISWAP_V2 => Action::SwapUniswapV2 {
+                pair: reader.address()?,
+                zero_for_one: reader.flag()?,
+                amount_in: reader.amount()?,
+                amount_out_min: reader.amount()?,
+            },
+            SWAP_UNISWAP_V3 => Action::SwapUniswapV3 {
+                pool: reader.address()?,
+                zero_for_one: reader.flag()?,
+                amount_in: reader.amount()?,
+                amount_out_min: reader.amount()?,
+            },
+            SWAP_CURVE => Action::SwapCurve {
+                pool: reader.address()?,
+                token_in: reader.address()?,
+                i: reader.byte()?,
+                j: reader.byte()?,
+                amount_in: reader.amount()?,
+                amount_out_min: reader.amount()?,
+            },
+            FLASH_BORROW => Action::FlashBorrow {
+                source: flash_loan_source(reader.byte()?)?,
+                lender: reader.address()?,
+                token: reader.address()?,
+                amount: reader.amount()?,
+                program: decode_actions(reader.bytes()?)?,
+            },
+            REPAY => Action::Repay {
+                source: flash_loan_source(reader.byte()?)?,
+                lender: reader.address()?,
+                token: reader.address()?,
+                amount: reader.amount()?,
+            },
+            LIQUIDATE => {
+                let venue = liquidation_venue(reader.byte()?)?;
+                let debt_is_native = reader.flag()?;
+                Action::Liquidate {
+                    venue,
+                    market: reader.address()?,
+                    collateral: reader.address()?,
+                    debt: reader.address()?,
+                    user: reader.address()?,
+                    debt_to_cover: reader.amount()?,
+                    debt_is_native,
+                }
+            }
+            TRANSFER => Action::Transfer {
+                token: reader.address()?,
+                to: reader.address()?,
+                amount: reader.amount()?,
+            },
+            CHECK_MIN_BALANCE => Action::CheckMinBalance {
+                token: reader.address()?,
+                minimum: reader.amount()?,
+            },
+            APPROVE => Action::Approve {
+                token: reader.address()?,
+                spender: reader.address()?,
+                amount: reader.amount()?,
+            },
+            CALL => Action::Call {
+                target: reader.address()?,
+                data: reader.bytes()?.to_vec().into(),
+            },
+            PULL => match (reader.address()?, reader.amount()?) {
+                (token, amount) if amount == BALANCE => bail!("Pull of {:?} without an amount", token),
+                (token, amount) => Action::Pull { token, amount },
+            },
+            REDEEM => Action::Redeem {
+                c_token: reader.address()?,
+                amount: reader.amount()?,
+            },
+            WRAP_NATIVE => Action::WrapNative,
+            op => bail!("Unknown action {:#04x} at byte {}", op, reader.offset - 1),
+        };
+        actions.push(action);
+    }
+
+    Ok(actions)
+}
+
+fn put_address(out: &mut Vec<u8>, address: Address) {
+    out.extend_from_slice(address.as_bytes());
+}
+
+fn put_flag(out: &mut Vec<u8>, flag: bool) {
+    out.push(if flag { FLAG } else { 0 });
+}
+
+/// A length byte and the amount's big-endian bytes without leading zeros.
+fn put_amount(out: &mut Vec<u8>, amount: U256) {
+    if amount == BALANCE {
+        out.push(BALANCE_MARKER);
+        return;
+    }
+    let mut word = [0u8; 32];
+    amount.to_big_endian(&mut word);
+    let length = amount.bits().div_ceil(8);
+    out.push(length as u8);
+    out.extend_from_slice(&word[32 - length..]);
+}
+
+/// A two-byte length and the bytes themselves.
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) -> Result<()> {
+    let length = u16::try_from(bytes.len()).map_err(|_| anyhow!("{} bytes exceed the 64 KiB an action can carry", bytes.len()))?;
+    out.extend_from_slice(&length.to_be_bytes());
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+fn flash_loan_source(id: u8) -> Result<FlashLoanSource> {
+    Ok(match id {
+        0 => FlashLoanSource::AaveV3,
+        1 => FlashLoanSource::Balancer,
+        2 => FlashLoanSource::UniswapV2,
+        3 => FlashLoanSource::UniswapV3,
+        _ => bail!("Unknown flash-loan source {}", id),
+    })
+}
+
+fn liquidation_venue(id: u8) -> Result<LiquidationVenue> {
+    Ok(match id {
+        0 => LiquidationVenue::AaveV3,
+        1 => LiquidationVenue::CompoundV2,
+        _ => bail!("Unknown liquidation venue {}", id),
+    })
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&[u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + length)
+            .ok_or_else(|| anyhow!("Program truncated at byte {}", self.offset))?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn flag(&mut self) -> Result<bool> {
+        Ok(self.byte()? & FLAG != 0)
+    }
+
+    fn address(&mut self) -> Result<Address> {
+        Ok(Address::from_slice(self.take(20)?))
+    }
+
+    fn bytes(&mut self) -> Result<&[u8]> {
+        let length = self.take(2)?;
+        let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+        self.take(length)
+    }
+
+    fn amount(&mut self) -> Result<U256> {
+        match self.byte()? {
+            BALANCE_MARKER => Ok(BALANCE),
+            length if length <= 32 => Ok(U256::from_big_endian(self.take(length as usize)?)),
+            length => Err(anyhow!("Amount length {} over 32 bytes", length)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(n: u64) -> Address {
+        Address::from_low_u64_be(n)
+    }
+
+    #[test]
+    fn test_every_action_round_trips_through_execute_calldata() {
+        let quote = FlashLoanQuote {
+            source: FlashLoanSource::Balancer,
+            lender: addr(1),
+            token: addr(2),
+            amount: U256::exp10(24),
+            fee: U256::zero(),
+        };
+        let actions = vec![
+            Action::Pull { token: addr(2), amount: U256::from(42) },
+            Action::flash_borrow(
+                &quote,
+                vec![
+                    Action::SwapUniswapV2 { pair: addr(3), zero_for_one: true, amount_in: quote.amount, amount_out_min: U256::from(1) },
+                    Action::SwapUniswapV3 { pool: addr(4), zero_for_one: false, amount_in: BALANCE, amount_out_min: U256::MAX - 1 },
+                    Action::SwapCurve { pool: addr(5), token_in: addr(6), i: 2, j: 0, amount_in: BALANCE, amount_out_min: U256::from(255) },
+                    Action::Liquidate {
+                        venue: LiquidationVenue::CompoundV2,
+                        market: addr(7),
+                        collateral: addr(8),
+                        debt: addr(2),
+                        user: addr(9),
+                        debt_to_cover: U256::from(1_000_000),
+                        debt_is_native: true,
+                    },
+                    Action::Redeem { c_token: addr(8), amount: BALANCE },
+                    Action::WrapNative,
+                    Action::Approve { token: addr(6), spender: addr(11), amount: U256::from(7) },
+                    Action::Call { target: addr(11), data: vec![0xab; 300].into() },
+                    Action::Approve { token: addr(6), spender: addr(11), amount: U256::zero() },
+                ],
+            ),
+            Action::CheckMinBalance { token: addr(2), minimum: U256::zero() },
+            Action::Transfer { token: addr(2), to: addr(10), amount: BALANCE },
+        ];
+
+        assert_eq!(decode_actions(&encode_actions(&actions).unwrap()).unwrap(), actions);
+        assert_eq!(decode_execute(&encode_execute(&actions).unwrap()).unwrap(), actions);
+        match &actions[1] {
+            Action::FlashBorrow { program, .. } => assert_eq!(program.last(), Some(&Action::repay(&quote))),
+            action => panic!("Expected a flash borrow, got {:?}", action),
+        }
+    }
+
+    #[test]
+    fn test_amounts_take_only_their_significant_bytes() {
+        let transfer = |amount| encode_actions(&[Action::Transfer { token: addr(1), to: addr(2), amount }]).unwrap();
+        // Opcode and two addresses come first
+        let header = 1 + 20 + 20;
+
+        assert_eq!(&transfer(U256::zero())[header..], &[0]);
+        assert_eq!(&transfer(U256::from(0x1234))[header..], &[2, 0x12, 0x34]);
+        assert_eq!(&transfer(BALANCE)[header..], &[BALANCE_MARKER]);
+        assert_eq!(transfer(U256::MAX - 1).len(), header + 33);
+    }
+
+    #[test]
+    fn test_rejects_truncated_and_unknown_programs() {
+        let program = encode_actions(&[Action::CheckMinBalance { token: addr(1), minimum: U256::from(500) }]).unwrap();
+
+        assert!(decode_actions(&program[..program.len() - 1]).is_err());
+        assert!(decode_actions(&[0x0e]).is_err());
+        // Flash borrow from source 4
+        assert!(decode_actions(&[FLASH_BORROW, 4]).is_err());
+        assert!(decode_execute(&program).is_err());
+        // Call data claiming more bytes than follow
+        let call = encode_actions(&[Action::Call { target: addr(1), data: vec![1, 2, 3].into() }]).unwrap();
+        assert!(decode_actions(&call[..call.len() - 1]).is_err());
+        assert!(encode_actions(&[Action::Call { target: addr(1), data: vec![0; 1 << 16].into() }]).is_err());
+        assert!(encode_actions(&[Action::Pull { token: addr(1), amount: BALANCE }]).is_err());
+    }
+}
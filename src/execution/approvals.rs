@@ -0,0 +1,505 @@
+// src/execution/approvals.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{self, Token},
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{
+        transaction::eip712::{EIP712Domain, Eip712, Eip712Error},
+        Address, Filter, Signature, H256, U256,
+    },
+    utils::keccak256,
+};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use super::submitter::{Submission, Submitter};
+use super::wallet_pool::WalletLease;
+
+/// Canonical Uniswap Permit2 deployment, identical on every chain.
+pub const PERMIT2_ADDRESS: &str = "0x000000000022D473030F116dDEE9F6B43aC78BA3";
+
+abigen!(
+    Erc20,
+    r#"[
+        function allowance(address owner, address spender) external view returns (uint256)
+        function approve(address spender, uint256 amount) external returns (bool)
+        event Approval(address indexed owner, address indexed spender, uint256 value)
+    ]"#
+);
+
+abigen!(
+    Permit2,
+    r#"[
+        function allowance(address owner, address token, address spender) external view returns (uint160 amount, uint48 expiration, uint48 nonce)
+        struct TokenSpenderPair { address token; address spender; }
+        function lockdown(TokenSpenderPair[] approvals) external
+        event Approval(address indexed owner, address indexed token, address indexed spender, uint160 amount, uint48 expiration)
+        event Permit(address indexed owner, address indexed token, address indexed spender, uint160 amount, uint48 expiration, uint48 nonce)
+    ]"#
+);
+
+/// Signing-policy name revocations are submitted under, whichever strategy granted the allowance.
+const REVOKE_STRATEGY: &str = "approvals";
+
+/// How much allowance to grant when the current one is insufficient.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalMode {
+    /// Approve exactly the amount about to be spent.
+    Exact,
+    /// Approve each listed token up to its cap, in the token's own units, so repeated trades reuse
+    /// one approval; no allowance ever exceeds the cap and larger spends are refused. Unlisted
+    /// tokens are approved exactly.
+    Capped(HashMap<Address, U256>),
+}
+
+impl ApprovalMode {
+    fn amount_for(&self, token: Address, required: U256) -> Result<U256> {
+        match self {
+            ApprovalMode::Exact => Ok(required),
+            ApprovalMode::Capped(caps) => match caps.get(&token) {
+                Some(cap) if required > *cap => Err(anyhow!("Spending {} of {:?} exceeds its approval cap of {}", required, token, cap)),
+                Some(cap) => Ok(*cap),
+                None => Ok(required),
+            },
+        }
+    }
+}
+
+/// `config/approvals_config.json`: spenders each strategy may need allowance for.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApprovalsConfig {
+    pub mode: ApprovalMode,
+    pub permit2: Option<Address>,
+    /// Block from which `Approval` events are scanned when listing outstanding approvals.
+    pub scan_from_block: u64,
+    /// Blocks per `eth_getLogs` request in that scan, within what public nodes accept.
+    #[serde(default = "default_block_chunk")]
+    pub block_chunk: u64,
+    pub strategies: HashMap<String, Vec<Address>>,
+}
+
+fn default_block_chunk() -> u64 {
+    2_000
+}
+
+impl ApprovalsConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn add_spender(&mut self, strategy: &str, spender: Address) {
+        let spenders = self.strategies.entry(strategy.to_string()).or_default();
+        if !spenders.contains(&spender) {
+            spenders.push(spender);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OutstandingApproval {
+    pub token: Address,
+    pub spender: Address,
+    pub amount: U256,
+    pub kind: AllowanceKind,
+}
+
+/// Where an allowance lives, which decides how it is revoked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllowanceKind {
+    /// The token's own `allowance`, revoked with `approve(spender, 0)`.
+    Erc20,
+    /// A Permit2 allowance, granted by `approve` or a signed permit and revoked with `lockdown`.
+    Permit2 { expiration: u64 },
+}
+
+/// A signed Permit2 `PermitSingle`, ready to pass to `permit(owner, permitSingle, signature)`.
+#[derive(Debug, Clone)]
+pub struct Permit2Signature {
+    pub token: Address,
+    pub spender: Address,
+    pub amount: U256,
+    pub expiration: u64,
+    pub nonce: u64,
+    pub sig_deadline: U256,
+    pub signature: Signature,
+}
+
+/// Checks, caches and issues ERC20 allowances for the spenders our strategies use.
+///
+/// Approvals leave through the submitter on the lease that will spend them, so they are journaled
+/// instead of broadcast in dry-run mode and pass the same signing policy as the trades.
+pub struct ApprovalManager {
+    provider: Arc<Provider<Ws>>,
+    submitter: Arc<Submitter>,
+    config: ApprovalsConfig,
+    cache: RwLock<HashMap<(Address, Address, Address), U256>>,
+}
+
+impl ApprovalManager {
+    pub fn new(provider: Arc<Provider<Ws>>, submitter: Arc<Submitter>, config: ApprovalsConfig) -> Self {
+        Self {
+            provider,
+            submitter,
+            config,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn spenders_for(&self, strategy: &str) -> &[Address] {
+        self.config.strategies.get(strategy).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub async fn allowance(&self, owner: Address, token: Address, spender: Address) -> Result<U256> {
+        let key = (token, owner, spender);
+        if let Some(amount) = self.cache.read().await.get(&key) {
+            return Ok(*amount);
+        }
+
+        let amount = Erc20::new(token, self.provider.clone()).allowance(owner, spender).call().await?;
+        self.cache.write().await.insert(key, amount);
+
+        Ok(amount)
+    }
+
+    /// Records that a swap consumed `spent` of the cached allowance.
+    pub async fn record_spend(&self, owner: Address, token: Address, spender: Address, spent: U256) {
+        if let Some(amount) = self.cache.write().await.get_mut(&(token, owner, spender)) {
+            *amount = amount.saturating_sub(spent);
+        }
+    }
+
+    /// Approves `spender` for at least `amount` of the lease's `token`, returning the approvals submitted.
+    pub async fn ensure_allowance(&self, strategy: &str, lease: &WalletLease, token: Address, spender: Address, amount: U256) -> Result<Vec<Submission>> {
+        if !self.spenders_for(strategy).contains(&spender) {
+            return Err(anyhow!("Spender {:?} is not configured for strategy {}", spender, strategy));
+        }
+
+        let current = self.allowance(lease.address(), token, spender).await?;
+        let mut submitted = Vec::new();
+        for approval in approvals_needed(current, amount, self.config.mode.amount_for(token, amount)?) {
+            submitted.push(self.approve(strategy, lease, token, spender, approval).await?);
+        }
+
+        Ok(submitted)
+    }
+
+    /// Sends `approve(spender, amount)` from the lease. Only a broadcast approval updates the cache:
+    /// a journaled one never changed the chain.
+    pub async fn approve(&self, strategy: &str, lease: &WalletLease, token: Address, spender: Address, amount: U256) -> Result<Submission> {
+        let tx = Erc20::new(token, self.provider.clone()).approve(spender, amount).tx;
+        let submission = self.submitter.submit(strategy, lease, tx).await?;
+
+        if let Submission::Broadcast(_) = submission {
+            self.cache.write().await.insert((token, lease.address(), spender), amount);
+            info!("Approved {:?} to spend {} of {:?} from {:?}", spender, amount, token, lease.address());
+        }
+
+        Ok(submission)
+    }
+
+    /// Signs a Permit2 `PermitSingle` so `spender` can pull `amount` of `token` through Permit2,
+    /// under `strategy`'s signing policy, which must allow typed data.
+    ///
+    /// The token itself must already have an ERC20 allowance for the Permit2 contract.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn sign_permit2(
+        &self,
+        strategy: &str,
+        lease: &WalletLease,
+        token: Address,
+        spender: Address,
+        amount: U256,
+        expiration: u64,
+        sig_deadline: U256,
+    ) -> Result<Permit2Signature> {
+        let permit2 = self.permit2_address()?;
+        let (_, _, nonce) = Permit2::new(permit2, self.provider.clone())
+            .allowance(lease.address(), token, spender)
+            .call()
+            .await?;
+        let chain_id = self.provider.get_chainid().await?;
+
+        let permit = PermitSingle::new(chain_id, permit2, token, spender, amount, expiration, nonce, sig_deadline)?;
+        let signature = self.submitter.signer(lease, strategy).sign_typed_data(&permit).await?;
+
+        Ok(Permit2Signature {
+            token,
+            spender,
+            amount,
+            expiration,
+            nonce,
+            sig_deadline,
+            signature,
+        })
+    }
+
+    /// Lists every non-zero allowance granted by `owner` since `scan_from_block`: ERC20
+    /// allowances, and unexpired Permit2 allowances.
+    pub async fn outstanding_approvals(&self, owner: Address) -> Result<Vec<OutstandingApproval>> {
+        let latest = self.provider.get_block_number().await?.as_u64();
+        let permit2 = self.permit2_address()?;
+
+        let mut erc20 = HashSet::new();
+        let mut via_permit2 = HashSet::new();
+        let mut from_block = self.config.scan_from_block;
+        while from_block <= latest {
+            let chunk_end = latest.min(from_block + self.config.block_chunk.max(1) - 1);
+            let filter = Filter::new()
+                .event("Approval(address,address,uint256)")
+                .topic1(H256::from(owner))
+                .from_block(from_block)
+                .to_block(chunk_end);
+            for log in self.provider.get_logs(&filter).await? {
+                if let Some(spender) = log.topics.get(2) {
+                    erc20.insert((log.address, Address::from(*spender)));
+                }
+            }
+
+            // Permit2 indexes the token and the spender; both events leave an allowance behind
+            let filter = Filter::new()
+                .address(permit2)
+                .events([
+                    "Approval(address,address,address,uint160,uint48)",
+                    "Permit(address,address,address,uint160,uint48,uint48)",
+                ])
+                .topic1(H256::from(owner))
+                .from_block(from_block)
+                .to_block(chunk_end);
+            for log in self.provider.get_logs(&filter).await? {
+                if let (Some(token), Some(spender)) = (log.topics.get(2), log.topics.get(3)) {
+                    via_permit2.insert((Address::from(*token), Address::from(*spender)));
+                }
+            }
+            from_block = chunk_end + 1;
+        }
+
+        let mut approvals = Vec::new();
+        for (token, spender) in erc20 {
+            // Bypass the cache: the log scan is authoritative for what we ever approved
+            let amount = Erc20::new(token, self.provider.clone()).allowance(owner, spender).call().await?;
+            self.cache.write().await.insert((token, owner, spender), amount);
+            if !amount.is_zero() {
+                approvals.push(OutstandingApproval { token, spender, amount, kind: AllowanceKind::Erc20 });
+            }
+        }
+
+        let now = self.provider
+            .get_block(latest)
+            .await?
+            .ok_or_else(|| anyhow!("Node returned no block {}", latest))?
+            .timestamp
+            .as_u64();
+        let permit2_contract = Permit2::new(permit2, self.provider.clone());
+        for (token, spender) in via_permit2 {
+            let (amount, expiration, _) = permit2_contract.allowance(owner, token, spender).call().await?;
+            if !amount.is_zero() && expiration >= now {
+                approvals.push(OutstandingApproval { token, spender, amount, kind: AllowanceKind::Permit2 { expiration } });
+            }
+        }
+
+        Ok(approvals)
+    }
+
+    /// Zeroes every outstanding allowance of the leased wallet under the `approvals` signing
+    /// policy: one `approve` per ERC20 allowance and a single Permit2 `lockdown` for the rest.
+    pub async fn revoke_all(&self, lease: &WalletLease) -> Result<Vec<Submission>> {
+        let mut revoked = Vec::new();
+        let mut permit2_pairs = Vec::new();
+        for approval in self.outstanding_approvals(lease.address()).await? {
+            match approval.kind {
+                AllowanceKind::Erc20 => {
+                    revoked.push(self.approve(REVOKE_STRATEGY, lease, approval.token, approval.spender, U256::zero()).await?);
+                }
+                AllowanceKind::Permit2 { .. } => permit2_pairs.push(TokenSpenderPair {
+                    token: approval.token,
+                    spender: approval.spender,
+                }),
+            }
+        }
+
+        if !permit2_pairs.is_empty() {
+            let tx = Permit2::new(self.permit2_address()?, self.provider.clone()).lockdown(permit2_pairs).tx;
+            revoked.push(self.submitter.submit(REVOKE_STRATEGY, lease, tx).await?);
+        }
+
+        Ok(revoked)
+    }
+
+    fn permit2_address(&self) -> Result<Address> {
+        match self.config.permit2 {
+            Some(address) => Ok(address),
+            None => Ok(PERMIT2_ADDRESS.parse()?),
+        }
+    }
+}
+
+/// Allowances to set, in order, for `current` to cover `required` with a `grant` of at least it.
+fn approvals_needed(current: U256, required: U256, grant: U256) -> Vec<U256> {
+    if current >= required {
+        return Vec::new();
+    }
+
+    // Tokens such as USDT revert when changing a non-zero allowance to another non-zero value
+    let reset = (!current.is_zero()).then(U256::zero);
+    reset.into_iter().chain(Some(grant)).collect()
+}
+
+/// Permit2's `PermitSingle` for one token, as signed under EIP-712.
+#[derive(Debug, Clone)]
+struct PermitSingle {
+    chain_id: U256,
+    permit2: Address,
+    token: Address,
+    spender: Address,
+    amount: U256,
+    expiration: u64,
+    nonce: u64,
+    sig_deadline: U256,
+}
+
+impl PermitSingle {
+    /// Fails for amounts that do not fit `PermitDetails.amount`, a `uint160`: Permit2 would hash a
+    /// different value and reject the signature.
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        chain_id: U256,
+        permit2: Address,
+        token: Address,
+        spender: Address,
+        amount: U256,
+        expiration: u64,
+        nonce: u64,
+        sig_deadline: U256,
+    ) -> Result<Self> {
+        if amount > U256::MAX >> 96 {
+            return Err(anyhow!("Permit2 amount {} does not fit in a uint160", amount));
+        }
+
+        Ok(Self {
+            chain_id,
+            permit2,
+            token,
+            spender,
+            amount,
+            expiration,
+            nonce,
+            sig_deadline,
+        })
+    }
+}
+
+impl Eip712 for PermitSingle {
+    type Error = Eip712Error;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(EIP712Domain {
+            name: Some("Permit2".to_string()),
+            chain_id: Some(self.chain_id),
+            verifying_contract: Some(self.permit2),
+            ..Default::default()
+        })
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(
+            "PermitSingle(PermitDetails details,address spender,uint256 sigDeadline)PermitDetails(address token,uint160 amount,uint48 expiration,uint48 nonce)",
+        ))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let details_hash = keccak256(abi::encode(&[
+            Token::FixedBytes(keccak256("PermitDetails(address token,uint160 amount,uint48 expiration,uint48 nonce)").to_vec()),
+            Token::Address(self.token),
+            Token::Uint(self.amount),
+            Token::Uint(self.expiration.into()),
+            Token::Uint(self.nonce.into()),
+        ]));
+
+        Ok(keccak256(abi::encode(&[
+            Token::FixedBytes(Self::type_hash()?.to_vec()),
+            Token::FixedBytes(details_hash.to_vec()),
+            Token::Address(self.spender),
+            Token::Uint(self.sig_deadline),
+        ])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::transaction::eip712::TypedData;
+
+    #[test]
+    fn test_permit_single_digest_matches_eip712_encoding() {
+        let (token, spender) = (Address::from_low_u64_be(0xa), Address::from_low_u64_be(0xb));
+        let permit2: Address = PERMIT2_ADDRESS.parse().unwrap();
+        let typed_data: TypedData = serde_json::from_value(serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" }
+                ],
+                "PermitSingle": [
+                    { "name": "details", "type": "PermitDetails" },
+                    { "name": "spender", "type": "address" },
+                    { "name": "sigDeadline", "type": "uint256" }
+                ],
+                "PermitDetails": [
+                    { "name": "token", "type": "address" },
+                    { "name": "amount", "type": "uint160" },
+                    { "name": "expiration", "type": "uint48" },
+                    { "name": "nonce", "type": "uint48" }
+                ]
+            },
+            "primaryType": "PermitSingle",
+            "domain": { "name": "Permit2", "chainId": 1, "verifyingContract": permit2 },
+            "message": {
+                "details": { "token": token, "amount": "1000000", "expiration": 1_700_000_000u64, "nonce": 3 },
+                "spender": spender,
+                "sigDeadline": 1_690_000_000u64
+            }
+        }))
+        .unwrap();
+
+        let permit = PermitSingle::new(1.into(), permit2, token, spender, 1_000_000.into(), 1_700_000_000, 3, 1_690_000_000u64.into()).unwrap();
+        assert_eq!(permit.encode_eip712().unwrap(), typed_data.encode_eip712().unwrap());
+        assert!(PermitSingle::new(1.into(), permit2, token, spender, (U256::MAX >> 96) + 1, 0, 0, U256::zero()).is_err());
+    }
+
+    #[test]
+    fn test_approval_amounts() {
+        let (usdc, dai) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let capped = ApprovalMode::Capped(HashMap::from([(usdc, U256::from(1_000))]));
+        assert_eq!(ApprovalMode::Exact.amount_for(usdc, 10.into()).unwrap(), U256::from(10));
+        assert_eq!(capped.amount_for(usdc, 10.into()).unwrap(), U256::from(1_000));
+        assert_eq!(capped.amount_for(usdc, 1_000.into()).unwrap(), U256::from(1_000));
+        // Never approve past the cap; uncapped tokens are approved exactly
+        assert!(capped.amount_for(usdc, 1_001.into()).is_err());
+        assert_eq!(capped.amount_for(dai, 5_000.into()).unwrap(), U256::from(5_000));
+
+        assert!(approvals_needed(10.into(), 10.into(), 10.into()).is_empty());
+        assert_eq!(approvals_needed(U256::zero(), 10.into(), 1_000.into()), vec![U256::from(1_000)]);
+        // A USDT-style token has to be reset to zero before the new allowance
+        assert_eq!(approvals_needed(5.into(), 10.into(), 10.into()), vec![U256::zero(), U256::from(10)]);
+    }
+
+    #[test]
+    fn test_capped_mode_reads_per_token_caps() {
+        let config: ApprovalsConfig = serde_json::from_value(serde_json::json!({
+            "mode": { "capped": { "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48": "0x2540be400" } },
+            "permit2": null,
+            "scan_from_block": 0,
+            "strategies": {}
+        }))
+        .unwrap();
+        let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap();
+
+        assert_eq!(config.mode.amount_for(usdc, 1.into()).unwrap(), U256::from(10_000_000_000u64));
+    }
+}
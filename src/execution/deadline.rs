@@ -0,0 +1,154 @@
+// src/execution/deadline.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{BlockNumber, U256, U64},
+};
+use std::sync::Arc;
+
+use crate::config::Config;
+
+pub const SLOT_SECONDS: u64 = 12;
+
+/// How long a swap stays valid after the latest block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineWindow {
+    Seconds(u64),
+    Slots(u64),
+}
+
+impl DeadlineWindow {
+    pub fn from_config(config: &Config) -> Self {
+        match (config.swap_deadline_slots, config.swap_deadline_seconds) {
+            (Some(slots), _) => DeadlineWindow::Slots(slots),
+            (None, Some(seconds)) => DeadlineWindow::Seconds(seconds),
+            (None, None) => DeadlineWindow::Slots(2),
+        }
+    }
+
+    pub fn as_seconds(&self) -> u64 {
+        match self {
+            DeadlineWindow::Seconds(seconds) => *seconds,
+            DeadlineWindow::Slots(slots) => slots * SLOT_SECONDS,
+        }
+    }
+
+    /// Whole blocks that fit in the window, rounded down so none of them can land past it.
+    pub fn as_blocks(&self) -> u64 {
+        match self {
+            DeadlineWindow::Seconds(seconds) => seconds / SLOT_SECONDS,
+            DeadlineWindow::Slots(slots) => *slots,
+        }
+    }
+}
+
+/// Validity bounds for a bundle: it must land by `max_block_number` and before `max_timestamp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundleWindow {
+    /// Swap deadline for calldata inside the bundle.
+    pub deadline: U256,
+    pub target_block: U64,
+    pub max_block_number: U64,
+    pub max_timestamp: u64,
+}
+
+/// Computes swap deadlines from the chain's clock rather than the local one.
+pub struct DeadlineCalculator {
+    provider: Arc<Provider<Ws>>,
+    window: DeadlineWindow,
+    bundle_max_blocks: u64,
+}
+
+impl DeadlineCalculator {
+    pub fn new(provider: Arc<Provider<Ws>>, window: DeadlineWindow, bundle_max_blocks: u64) -> Self {
+        Self {
+            provider,
+            window,
+            bundle_max_blocks,
+        }
+    }
+
+    pub fn from_config(provider: Arc<Provider<Ws>>, config: &Config) -> Self {
+        Self::new(provider, DeadlineWindow::from_config(config), config.bundle_max_blocks)
+    }
+
+    /// `deadline` argument for a router call: latest block timestamp plus the window.
+    pub async fn swap_deadline(&self) -> Result<U256> {
+        let (_, timestamp) = self.latest_block().await?;
+        Ok(timestamp + U256::from(self.window.as_seconds()))
+    }
+
+    /// Swap deadline and block bounds for a bundle built on the latest block.
+    pub async fn bundle_window(&self) -> Result<BundleWindow> {
+        let (number, timestamp) = self.latest_block().await?;
+        Ok(bundle_window_after(number, timestamp, self.window, self.bundle_max_blocks))
+    }
+
+    async fn latest_block(&self) -> Result<(U64, U256)> {
+        let block = self.provider
+            .get_block(BlockNumber::Latest)
+            .await?
+            .ok_or_else(|| anyhow!("Node returned no latest block"))?;
+        let number = block.number.ok_or_else(|| anyhow!("Latest block has no number"))?;
+
+        Ok((number, block.timestamp))
+    }
+}
+
+fn bundle_window_after(number: U64, timestamp: U256, window: DeadlineWindow, bundle_max_blocks: u64) -> BundleWindow {
+    // A bundle can never outlive the swap deadline encoded inside it, but always gets the next block
+    let blocks = bundle_max_blocks.max(1).min(window.as_blocks().max(1));
+    let deadline = timestamp + U256::from(window.as_seconds());
+
+    BundleWindow {
+        deadline,
+        target_block: number + 1,
+        max_block_number: number + blocks,
+        max_timestamp: (timestamp + U256::from(blocks * SLOT_SECONDS)).min(deadline).as_u64(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_in_seconds() {
+        assert_eq!(DeadlineWindow::Seconds(25).as_seconds(), 25);
+        assert_eq!(DeadlineWindow::Slots(3).as_seconds(), 36);
+    }
+
+    #[test]
+    fn test_bundle_window_is_capped_by_deadline() {
+        let window = bundle_window_after(U64::from(100), U256::from(1_000), DeadlineWindow::Seconds(24), 5);
+
+        assert_eq!(window.deadline, U256::from(1_024));
+        assert_eq!(window.target_block, U64::from(101));
+        assert_eq!(window.max_block_number, U64::from(102));
+        assert_eq!(window.max_timestamp, 1_024);
+    }
+
+    #[test]
+    fn test_partial_slot_rounds_down() {
+        // 25 seconds fits two whole slots; a third block would land after the deadline
+        let window = bundle_window_after(U64::from(100), U256::from(1_000), DeadlineWindow::Seconds(25), 5);
+        assert_eq!(DeadlineWindow::Seconds(25).as_blocks(), 2);
+        assert_eq!(window.max_block_number, U64::from(102));
+        assert_eq!(window.max_timestamp, 1_024);
+
+        // Shorter than a slot: still the next block, but only until the deadline
+        let window = bundle_window_after(U64::from(100), U256::from(1_000), DeadlineWindow::Seconds(5), 5);
+        assert_eq!(window.max_block_number, U64::from(101));
+        assert_eq!(window.max_timestamp, 1_005);
+    }
+
+    #[test]
+    fn test_single_block_bundle() {
+        let window = bundle_window_after(U64::from(100), U256::from(1_000), DeadlineWindow::Slots(3), 1);
+
+        assert_eq!(window.deadline, U256::from(1_036));
+        assert_eq!(window.max_block_number, U64::from(101));
+        assert_eq!(window.max_timestamp, 1_012);
+    }
+}
@@ -0,0 +1,481 @@
+// src/execution/flash_loans.rs
+use async_trait::async_trait;
+use ethers::{
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{Address, U256},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::debug;
+use anyhow::Result;
+
+use crate::lending::flash_liquidation::{flash_premium, AaveV3FlashPool};
+
+// Aave reserve configuration bits a flash loan is checked against
+const ACTIVE_BIT: usize = 56;
+const PAUSED_BIT: usize = 60;
+const FLASHLOAN_ENABLED_BIT: usize = 63;
+// Balancer's fee percentage is an 18-decimal fraction
+const BALANCER_FEE_ONE: u64 = 1_000_000_000_000_000_000;
+// Uniswap V3 fee tiers are in hundredths of a basis point
+const UNISWAP_V3_FEE_ONE: u32 = 1_000_000;
+const DEFAULT_UNISWAP_V3_FEE_TIERS: [u32; 4] = [100, 500, 3_000, 10_000];
+
+abigen!(
+    AaveV3LendingPool,
+    r#"[
+        struct ReserveConfigurationMap { uint256 data; }
+        struct ReserveData { ReserveConfigurationMap configuration; uint128 liquidityIndex; uint128 currentLiquidityRate; uint128 variableBorrowIndex; uint128 currentVariableBorrowRate; uint128 currentStableBorrowRate; uint40 lastUpdateTimestamp; uint16 id; address aTokenAddress; address stableDebtTokenAddress; address variableDebtTokenAddress; address interestRateStrategyAddress; uint128 accruedToTreasury; uint128 unbacked; uint128 isolationModeTotalDebt; }
+        function getReserveData(address asset) external view returns (ReserveData)
+    ]"#
+);
+
+abigen!(
+    BalancerVault,
+    r#"[
+        function getProtocolFeesCollector() external view returns (address)
+    ]"#
+);
+
+abigen!(
+    BalancerProtocolFeesCollector,
+    r#"[
+        function getFlashLoanFeePercentage() external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    UniswapV2Factory,
+    r#"[
+        function getPair(address tokenA, address tokenB) external view returns (address)
+    ]"#
+);
+
+abigen!(
+    UniswapV2Pair,
+    r#"[
+        function token0() external view returns (address)
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)
+    ]"#
+);
+
+abigen!(
+    UniswapV3Factory,
+    r#"[
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address)
+    ]"#
+);
+
+abigen!(
+    FlashLoanToken,
+    r#"[
+        function balanceOf(address account) external view returns (uint256)
+    ]"#
+);
+
+/// `config/flash_loan_providers.json`; a source is used only when its entry is present.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FlashLoanProvidersConfig {
+    pub aave_v3: Option<AaveV3FlashConfig>,
+    pub balancer: Option<BalancerFlashConfig>,
+    pub uniswap_v2: Option<UniswapV2FlashConfig>,
+    pub uniswap_v3: Option<UniswapV3FlashConfig>,
+}
+
+impl FlashLoanProvidersConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AaveV3FlashConfig {
+    pub pool: Address,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BalancerFlashConfig {
+    pub vault: Address,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UniswapV2FlashConfig {
+    pub factory: Address,
+    /// Tokens a borrowed token is paired with when looking for the deepest pair, e.g. WETH and USDC.
+    pub quote_tokens: Vec<Address>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UniswapV3FlashConfig {
+    pub factory: Address,
+    pub quote_tokens: Vec<Address>,
+    #[serde(default = "default_fee_tiers")]
+    pub fee_tiers: Vec<u32>,
+}
+
+fn default_fee_tiers() -> Vec<u32> {
+    DEFAULT_UNISWAP_V3_FEE_TIERS.to_vec()
+}
+
+/// How a loan is requested and repaid, by the id the executor expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashLoanSource {
+    /// `flashLoanSimple` for one asset, `flashLoan` for several.
+    AaveV3 = 0,
+    Balancer = 1,
+    /// A flash swap out of the pair, repaid in the same token.
+    UniswapV2 = 2,
+    UniswapV3 = 3,
+}
+
+/// One source's offer to lend `amount` of `token`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlashLoanQuote {
+    pub source: FlashLoanSource,
+    /// Contract the loan is taken from: the Aave pool, the Balancer vault, or the Uniswap pair or pool.
+    pub lender: Address,
+    pub token: Address,
+    pub amount: U256,
+    /// Owed on top of `amount`, in token units.
+    pub fee: U256,
+}
+
+/// Somewhere to flash-borrow from.
+#[async_trait]
+pub trait FlashLoanProvider: Send + Sync {
+    fn name(&self) -> &str;
+    /// Most of `token` a single loan can take right now.
+    async fn available_liquidity(&self, token: Address) -> Result<U256>;
+    /// The cheapest loan of `amount` of `token` this source can make, or `None` if it cannot cover it.
+    async fn quote(&self, token: Address, amount: U256) -> Result<Option<FlashLoanQuote>>;
+}
+
+/// Aave V3's pool, at its `FLASHLOAN_PREMIUM_TOTAL`.
+pub struct AaveV3FlashLoans {
+    pool: AaveV3LendingPool<Provider<Ws>>,
+    premium: AaveV3FlashPool<Provider<Ws>>,
+    provider: Arc<Provider<Ws>>,
+}
+
+impl AaveV3FlashLoans {
+    pub fn new(config: &AaveV3FlashConfig, provider: Arc<Provider<Ws>>) -> Self {
+        Self {
+            pool: AaveV3LendingPool::new(config.pool, provider.clone()),
+            premium: AaveV3FlashPool::new(config.pool, provider.clone()),
+            provider,
+        }
+    }
+}
+
+#[async_trait]
+impl FlashLoanProvider for AaveV3FlashLoans {
+    fn name(&self) -> &str {
+        "aave-v3"
+    }
+
+    /// The reserve's idle balance, held by its aToken, if the reserve allows flash loans.
+    async fn available_liquidity(&self, token: Address) -> Result<U256> {
+        let reserve = self.pool.get_reserve_data(token).call().await?;
+        let (config, a_token) = (reserve.0, reserve.8);
+        if a_token.is_zero() || !config.bit(ACTIVE_BIT) || config.bit(PAUSED_BIT) || !config.bit(FLASHLOAN_ENABLED_BIT) {
+            return Ok(U256::zero());
+        }
+
+        Ok(FlashLoanToken::new(token, self.provider.clone()).balance_of(a_token).call().await?)
+    }
+
+    async fn quote(&self, token: Address, amount: U256) -> Result<Option<FlashLoanQuote>> {
+        if self.available_liquidity(token).await? < amount {
+            return Ok(None);
+        }
+        let premium_bps = self.premium.flashloan_premium_total().call().await?;
+
+        Ok(Some(FlashLoanQuote {
+            source: FlashLoanSource::AaveV3,
+            lender: self.pool.address(),
+            token,
+            amount,
+            fee: flash_premium(amount, premium_bps)?,
+        }))
+    }
+}
+
+/// Balancer's vault, which lends everything it holds at the protocol's flash-loan fee (currently zero).
+pub struct BalancerFlashLoans {
+    vault: BalancerVault<Provider<Ws>>,
+    provider: Arc<Provider<Ws>>,
+}
+
+impl BalancerFlashLoans {
+    pub fn new(config: &BalancerFlashConfig, provider: Arc<Provider<Ws>>) -> Self {
+        Self {
+            vault: BalancerVault::new(config.vault, provider.clone()),
+            provider,
+        }
+    }
+}
+
+#[async_trait]
+impl FlashLoanProvider for BalancerFlashLoans {
+    fn name(&self) -> &str {
+        "balancer"
+    }
+
+    async fn available_liquidity(&self, token: Address) -> Result<U256> {
+        Ok(FlashLoanToken::new(token, self.provider.clone()).balance_of(self.vault.address()).call().await?)
+    }
+
+    async fn quote(&self, token: Address, amount: U256) -> Result<Option<FlashLoanQuote>> {
+        if self.available_liquidity(token).await? < amount {
+            return Ok(None);
+        }
+        let collector = BalancerProtocolFeesCollector::new(self.vault.get_protocol_fees_collector().call().await?, self.provider.clone());
+        let fee_percentage = collector.get_flash_loan_fee_percentage().call().await?;
+
+        Ok(Some(FlashLoanQuote {
+            source: FlashLoanSource::Balancer,
+            lender: self.vault.address(),
+            token,
+            amount,
+            fee: balancer_fee(amount, fee_percentage),
+        }))
+    }
+}
+
+/// Uniswap V2 flash swaps out of the deepest pair of the token with any of the quote tokens.
+pub struct UniswapV2FlashLoans {
+    factory: UniswapV2Factory<Provider<Ws>>,
+    quote_tokens: Vec<Address>,
+    provider: Arc<Provider<Ws>>,
+}
+
+impl UniswapV2FlashLoans {
+    pub fn new(config: &UniswapV2FlashConfig, provider: Arc<Provider<Ws>>) -> Self {
+        Self {
+            factory: UniswapV2Factory::new(config.factory, provider.clone()),
+            quote_tokens: config.quote_tokens.clone(),
+            provider,
+        }
+    }
+
+    /// The pair holding the most `token`, with how much of it can be taken out.
+    async fn deepest_pair(&self, token: Address) -> Result<Option<(Address, U256)>> {
+        let mut deepest: Option<(Address, U256)> = None;
+        for &quote_token in self.quote_tokens.iter().filter(|quote_token| **quote_token != token) {
+            let pair_address = self.factory.get_pair(token, quote_token).call().await?;
+            if pair_address.is_zero() {
+                continue;
+            }
+
+            let pair = UniswapV2Pair::new(pair_address, self.provider.clone());
+            let (reserve0, reserve1, _) = pair.get_reserves().call().await?;
+            let reserve = if pair.token_0().call().await? == token { reserve0 } else { reserve1 };
+            // The pair rejects a swap that empties a reserve
+            let available = U256::from(reserve).saturating_sub(U256::one());
+            if deepest.is_none_or(|(_, best)| available > best) {
+                deepest = Some((pair_address, available));
+            }
+        }
+
+        Ok(deepest)
+    }
+}
+
+#[async_trait]
+impl FlashLoanProvider for UniswapV2FlashLoans {
+    fn name(&self) -> &str {
+        "uniswap-v2"
+    }
+
+    async fn available_liquidity(&self, token: Address) -> Result<U256> {
+        Ok(self.deepest_pair(token).await?.map(|(_, available)| available).unwrap_or_default())
+    }
+
+    async fn quote(&self, token: Address, amount: U256) -> Result<Option<FlashLoanQuote>> {
+        Ok(match self.deepest_pair(token).await? {
+            Some((pair, available)) if available >= amount => Some(FlashLoanQuote {
+                source: FlashLoanSource::UniswapV2,
+                lender: pair,
+                token,
+                amount,
+                fee: uniswap_v2_flash_fee(amount),
+            }),
+            _ => None,
+        })
+    }
+}
+
+/// Uniswap V3 `flash`, from the lowest fee tier pool that holds enough of the token.
+pub struct UniswapV3FlashLoans {
+    factory: UniswapV3Factory<Provider<Ws>>,
+    quote_tokens: Vec<Address>,
+    fee_tiers: Vec<u32>,
+    provider: Arc<Provider<Ws>>,
+}
+
+impl UniswapV3FlashLoans {
+    pub fn new(config: &UniswapV3FlashConfig, provider: Arc<Provider<Ws>>) -> Self {
+        let mut fee_tiers = config.fee_tiers.clone();
+        fee_tiers.sort_unstable();
+
+        Self {
+            factory: UniswapV3Factory::new(config.factory, provider.clone()),
+            quote_tokens: config.quote_tokens.clone(),
+            fee_tiers,
+            provider,
+        }
+    }
+
+    /// Every pool of `token` with a quote token, cheapest tier first, with its balance of `token`.
+    async fn pools(&self, token: Address) -> Result<Vec<(Address, u32, U256)>> {
+        let balance_of = FlashLoanToken::new(token, self.provider.clone());
+        let mut pools = Vec::new();
+        for &fee in &self.fee_tiers {
+            for &quote_token in self.quote_tokens.iter().filter(|quote_token| **quote_token != token) {
+                let pool = self.factory.get_pool(token, quote_token, fee).call().await?;
+                if !pool.is_zero() {
+                    pools.push((pool, fee, balance_of.balance_of(pool).call().await?));
+                }
+            }
+        }
+
+        Ok(pools)
+    }
+}
+
+#[async_trait]
+impl FlashLoanProvider for UniswapV3FlashLoans {
+    fn name(&self) -> &str {
+        "uniswap-v3"
+    }
+
+    async fn available_liquidity(&self, token: Address) -> Result<U256> {
+        Ok(self.pools(token).await?.into_iter().map(|(_, _, balance)| balance).max().unwrap_or_default())
+    }
+
+    async fn quote(&self, token: Address, amount: U256) -> Result<Option<FlashLoanQuote>> {
+        Ok(self
+            .pools(token)
+            .await?
+            .into_iter()
+            .find(|(_, _, balance)| *balance >= amount)
+            .map(|(pool, fee, _)| FlashLoanQuote {
+                source: FlashLoanSource::UniswapV3,
+                lender: pool,
+                token,
+                amount,
+                fee: uniswap_v3_flash_fee(amount, fee),
+            }))
+    }
+}
+
+/// Every configured flash-loan source, asked together for the cheapest loan.
+pub struct FlashLoanRouter {
+    providers: Vec<Arc<dyn FlashLoanProvider>>,
+}
+
+impl FlashLoanRouter {
+    pub fn new(providers: Vec<Arc<dyn FlashLoanProvider>>) -> Self {
+        Self { providers }
+    }
+
+    /// One provider per configured source, in the order fee ties are broken.
+    pub fn from_config(config: &FlashLoanProvidersConfig, provider: Arc<Provider<Ws>>) -> Self {
+        let mut providers: Vec<Arc<dyn FlashLoanProvider>> = Vec::new();
+        if let Some(balancer) = &config.balancer {
+            providers.push(Arc::new(BalancerFlashLoans::new(balancer, provider.clone())));
+        }
+        if let Some(aave) = &config.aave_v3 {
+            providers.push(Arc::new(AaveV3FlashLoans::new(aave, provider.clone())));
+        }
+        if let Some(uniswap) = &config.uniswap_v3 {
+            providers.push(Arc::new(UniswapV3FlashLoans::new(uniswap, provider.clone())));
+        }
+        if let Some(uniswap) = &config.uniswap_v2 {
+            providers.push(Arc::new(UniswapV2FlashLoans::new(uniswap, provider)));
+        }
+
+        Self::new(providers)
+    }
+
+    /// The lowest-fee loan of `amount` of `token` any source can cover, or `None` if none can.
+    pub async fn cheapest(&self, token: Address, amount: U256) -> Option<FlashLoanQuote> {
+        let mut quotes = Vec::new();
+        for provider in &self.providers {
+            match provider.quote(token, amount).await {
+                Ok(Some(quote)) => quotes.push(quote),
+                Ok(None) => debug!("{} cannot lend {} of {:?}", provider.name(), amount, token),
+                Err(e) => debug!("Failed to quote a {} flash loan of {:?}: {}", provider.name(), token, e),
+            }
+        }
+
+        cheapest_quote(quotes)
+    }
+}
+
+/// Lowest fee wins; the earliest quote wins a tie.
+fn cheapest_quote(quotes: Vec<FlashLoanQuote>) -> Option<FlashLoanQuote> {
+    quotes.into_iter().reduce(|best, quote| if quote.fee < best.fee { quote } else { best })
+}
+
+/// Balancer rounds its fee up.
+fn balancer_fee(amount: U256, fee_percentage: U256) -> U256 {
+    let scaled = amount * fee_percentage;
+    let one = U256::from(BALANCER_FEE_ONE);
+    if scaled.is_zero() {
+        U256::zero()
+    } else {
+        (scaled - 1) / one + 1
+    }
+}
+
+/// Repaying a flash swap in the borrowed token must cover the pair's 0.3% fee on the repayment
+/// itself, so `amount · 3 / 997`, plus one for the pair's rounding.
+fn uniswap_v2_flash_fee(amount: U256) -> U256 {
+    amount * 3 / 997 + 1
+}
+
+/// Uniswap V3 charges the pool fee on the amount borrowed, rounded up.
+fn uniswap_v3_flash_fee(amount: U256, fee: u32) -> U256 {
+    let scaled = amount * fee;
+    let one = U256::from(UNISWAP_V3_FEE_ONE);
+    (scaled + one - 1) / one
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(source: FlashLoanSource, fee: u64) -> FlashLoanQuote {
+        FlashLoanQuote {
+            source,
+            lender: Address::zero(),
+            token: Address::zero(),
+            amount: U256::exp10(24),
+            fee: U256::from(fee),
+        }
+    }
+
+    #[test]
+    fn test_source_fees() {
+        let million = U256::exp10(24);
+        assert_eq!(balancer_fee(million, U256::zero()), U256::zero());
+        assert_eq!(balancer_fee(U256::from(3u64), U256::exp10(15)), U256::one());
+        assert_eq!(uniswap_v2_flash_fee(U256::from(997_000u64)), U256::from(3_001u64));
+        // 0.05% tier on 1,000,001 wei rounds up
+        assert_eq!(uniswap_v3_flash_fee(U256::from(1_000_001u64), 500), U256::from(501u64));
+    }
+
+    #[test]
+    fn test_picks_cheapest_quote_and_keeps_order_on_ties() {
+        let quotes = vec![
+            quote(FlashLoanSource::AaveV3, 500),
+            quote(FlashLoanSource::Balancer, 0),
+            quote(FlashLoanSource::UniswapV2, 0),
+        ];
+        assert_eq!(cheapest_quote(quotes).unwrap().source, FlashLoanSource::Balancer);
+        assert_eq!(cheapest_quote(Vec::new()), None);
+    }
+}
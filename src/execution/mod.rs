@@ -0,0 +1,21 @@
+// src/execution/mod.rs
+pub mod actions;
+pub mod approvals;
+pub mod deadline;
+pub mod flash_loans;
+pub mod slippage;
+pub mod submitter;
+pub mod swap;
+pub mod wallet_pool;
+
+pub use actions::{decode_actions, decode_execute, encode_actions, encode_execute, Action, ExecutorConfig, MultiActionExecutor};
+pub use approvals::{ApprovalManager, ApprovalsConfig};
+pub use deadline::{BundleWindow, DeadlineCalculator, DeadlineWindow};
+pub use flash_loans::{
+    AaveV3FlashLoans, BalancerFlashLoans, FlashLoanProvider, FlashLoanProvidersConfig, FlashLoanQuote, FlashLoanRouter,
+    FlashLoanSource, UniswapV2FlashLoans, UniswapV3FlashLoans,
+};
+pub use slippage::SlippageGuard;
+pub use submitter::{BundleRelay, Journal, JournalEntry, Submission, Submitter};
+pub use swap::{RouteFinder, SwapCalldataBuilder, SwapQuoter, SwapRoute};
+pub use wallet_pool::{WalletLease, WalletPool, WalletPoolConfig};
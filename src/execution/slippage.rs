@@ -0,0 +1,138 @@
+// src/execution/slippage.rs
+use anyhow::{anyhow, Result};
+use ethers::types::{Address, U256};
+use std::collections::HashMap;
+
+use crate::config::Config;
+
+const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Bounds swap amounts by a slippage tolerance applied to a fresh quote.
+///
+/// Tolerances are held in basis points. Volatile tokens can carry their own
+/// tolerance, which replaces the default for swaps in or out of that token; when
+/// both sides of a swap have one, the stricter applies.
+#[derive(Debug, Clone)]
+pub struct SlippageGuard {
+    default_bps: u64,
+    overrides: HashMap<Address, u64>,
+}
+
+impl SlippageGuard {
+    pub fn new(default_bps: u64, overrides: HashMap<Address, u64>) -> Result<Self> {
+        if default_bps >= BPS_DENOMINATOR {
+            return Err(anyhow!("Slippage tolerance of {} bps leaves no minimum output", default_bps));
+        }
+        if let Some((token, bps)) = overrides.iter().find(|(_, bps)| **bps >= BPS_DENOMINATOR) {
+            return Err(anyhow!("Slippage override of {} bps for {:?} leaves no minimum output", bps, token));
+        }
+
+        Ok(Self {
+            default_bps,
+            overrides,
+        })
+    }
+
+    /// Builds the guard from `max_slippage` and `slippage_overrides`, both percentages.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let mut overrides = HashMap::new();
+        for (token, percent) in &config.slippage_overrides {
+            overrides.insert(token.parse::<Address>()?, percent_to_bps(*percent)?);
+        }
+
+        Self::new(percent_to_bps(config.max_slippage)?, overrides)
+    }
+
+    /// Tolerance for a swap from `token_in` to `token_out`.
+    pub fn tolerance_bps(&self, token_in: Address, token_out: Address) -> u64 {
+        match (self.overrides.get(&token_in), self.overrides.get(&token_out)) {
+            (Some(bps_in), Some(bps_out)) => *bps_in.min(bps_out),
+            (Some(bps), None) | (None, Some(bps)) => *bps,
+            (None, None) => self.default_bps,
+        }
+    }
+
+    /// Minimum output for an exact-input swap, rounded down.
+    pub fn min_amount_out(&self, token_in: Address, token_out: Address, quoted_out: U256) -> Result<U256> {
+        let bps = self.tolerance_bps(token_in, token_out);
+        let min_out = quoted_out
+            .checked_mul(U256::from(BPS_DENOMINATOR - bps))
+            .ok_or_else(|| anyhow!("Quoted output overflows slippage calculation"))?
+            / U256::from(BPS_DENOMINATOR);
+
+        if min_out.is_zero() {
+            return Err(anyhow!("Refusing swap with zero minimum output for {:?}", token_out));
+        }
+
+        Ok(min_out)
+    }
+
+    /// Maximum input for an exact-output swap, rounded up.
+    pub fn max_amount_in(&self, token_in: Address, token_out: Address, quoted_in: U256) -> Result<U256> {
+        if quoted_in.is_zero() {
+            return Err(anyhow!("Refusing swap with zero quoted input for {:?}", token_in));
+        }
+
+        let bps = self.tolerance_bps(token_in, token_out);
+        let scaled = quoted_in
+            .checked_mul(U256::from(BPS_DENOMINATOR + bps))
+            .ok_or_else(|| anyhow!("Quoted input overflows slippage calculation"))?;
+        let denominator = U256::from(BPS_DENOMINATOR);
+
+        Ok((scaled + denominator - 1) / denominator)
+    }
+}
+
+fn percent_to_bps(percent: f64) -> Result<u64> {
+    if !percent.is_finite() || percent < 0.0 {
+        return Err(anyhow!("Invalid slippage percentage: {}", percent));
+    }
+
+    Ok((percent * 100.0).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_amount_out_uses_override() {
+        let volatile = Address::from_low_u64_be(1);
+        let guard = SlippageGuard::new(50, HashMap::from([(volatile, 300)])).unwrap();
+        let stable = Address::zero();
+
+        assert_eq!(guard.min_amount_out(stable, stable, U256::from(10_000)).unwrap(), U256::from(9_950));
+        // Either side's override applies, in both directions
+        assert_eq!(guard.min_amount_out(stable, volatile, U256::from(10_000)).unwrap(), U256::from(9_700));
+        assert_eq!(guard.min_amount_out(volatile, stable, U256::from(10_000)).unwrap(), U256::from(9_700));
+        assert_eq!(guard.max_amount_in(volatile, stable, U256::from(10_000)).unwrap(), U256::from(10_300));
+        assert_eq!(guard.max_amount_in(stable, volatile, U256::from(10_000)).unwrap(), U256::from(10_300));
+    }
+
+    #[test]
+    fn test_stricter_override_wins() {
+        let (loose, tight) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let guard = SlippageGuard::new(50, HashMap::from([(loose, 300), (tight, 20)])).unwrap();
+
+        assert_eq!(guard.tolerance_bps(loose, tight), 20);
+        assert_eq!(guard.tolerance_bps(tight, loose), 20);
+        assert_eq!(guard.min_amount_out(loose, tight, U256::from(10_000)).unwrap(), U256::from(9_980));
+        assert_eq!(guard.max_amount_in(tight, loose, U256::from(10_000)).unwrap(), U256::from(10_020));
+    }
+
+    #[test]
+    fn test_zero_minimum_is_refused() {
+        let guard = SlippageGuard::new(50, HashMap::new()).unwrap();
+
+        assert!(guard.min_amount_out(Address::zero(), Address::zero(), U256::from(1)).is_err());
+        assert!(guard.min_amount_out(Address::zero(), Address::zero(), U256::zero()).is_err());
+    }
+
+    #[test]
+    fn test_max_amount_in_rounds_up() {
+        let guard = SlippageGuard::new(50, HashMap::new()).unwrap();
+
+        assert_eq!(guard.max_amount_in(Address::zero(), Address::zero(), U256::from(10_000)).unwrap(), U256::from(10_050));
+        assert_eq!(guard.max_amount_in(Address::zero(), Address::zero(), U256::from(1)).unwrap(), U256::from(2));
+    }
+}
@@ -0,0 +1,312 @@
+// src/execution/submitter.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    core::rand::thread_rng,
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, H256, U256, U64},
+    utils::keccak256,
+};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use super::deadline::BundleWindow;
+use super::wallet_pool::WalletLease;
+use crate::config::DryRunConfig;
+use crate::signer::{PolicyEngine, PolicySigner};
+use crate::simulation::EvmSimulator;
+
+/// One would-be transaction and what the node says it would have done.
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    pub timestamp: u64,
+    pub block_number: U64,
+    pub strategy: String,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub data: Bytes,
+    pub gas_estimate: Option<U256>,
+    pub success: bool,
+    pub output: Option<Bytes>,
+    pub error: Option<String>,
+}
+
+/// Append-only JSON-lines record of dry-run submissions.
+pub struct Journal {
+    path: String,
+    lock: Mutex<()>,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub async fn record(&self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let _guard = self.lock.lock().await;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        file.write_all(&line).await?;
+        // Tokio finishes writes on a background thread; wait for this one before the file is dropped
+        file.flush().await?;
+
+        Ok(())
+    }
+}
+
+/// A Flashbots-style relay taking `eth_sendBundle`, authenticated with a throwaway key.
+pub struct BundleRelay {
+    url: String,
+    client: reqwest::Client,
+    /// Identifies us to the relay only; it never signs transactions or holds funds.
+    auth: LocalWallet,
+}
+
+impl BundleRelay {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+            auth: LocalWallet::new(&mut thread_rng()),
+        }
+    }
+
+    /// Offers `raw_tx` for every block of `window`; the relay drops it once `max_timestamp` passes.
+    async fn send(&self, raw_tx: &Bytes, window: &BundleWindow) -> Result<()> {
+        for block in window.target_block.as_u64()..=window.max_block_number.as_u64() {
+            let body = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_sendBundle",
+                "params": [{
+                    "txs": [raw_tx],
+                    "blockNumber": U64::from(block),
+                    "maxTimestamp": window.max_timestamp,
+                }],
+            })
+            .to_string();
+            let signature = self.auth.sign_message(format!("{:?}", H256::from(keccak256(&body)))).await?;
+
+            let response: serde_json::Value = self.client
+                .post(&self.url)
+                .header("Content-Type", "application/json")
+                .header("X-Flashbots-Signature", format!("{:?}:0x{}", self.auth.address(), signature))
+                .body(body)
+                .send()
+                .await?
+                .json()
+                .await?;
+            if let Some(error) = response.get("error") {
+                return Err(anyhow!("Relay rejected bundle for block {}: {}", block, error));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum Submission {
+    /// Sent publicly or, for bundles, to the relay; a bundle may still expire without landing.
+    Broadcast(H256),
+    Journaled(Box<JournalEntry>),
+}
+
+/// The single place transactions leave the bot: broadcast live or journaled in dry-run mode.
+pub struct Submitter {
+    provider: Arc<Provider<Ws>>,
+    policy: Arc<PolicyEngine>,
+    /// Priority fee per gas for EIP-1559 transactions, the builder tip profits are netted against.
+    priority_fee: U256,
+    journal: Option<Journal>,
+    relay: Option<BundleRelay>,
+    /// Runs dry-run transactions locally instead of through `eth_call`, once it has a block.
+    simulator: Option<Arc<EvmSimulator>>,
+}
+
+impl Submitter {
+    pub fn new(provider: Arc<Provider<Ws>>, policy: Arc<PolicyEngine>, priority_fee: U256, dry_run: &DryRunConfig) -> Self {
+        Self {
+            provider,
+            policy,
+            priority_fee,
+            journal: dry_run.enabled.then(|| Journal::new(dry_run.journal_path.clone())),
+            relay: None,
+            simulator: None,
+        }
+    }
+
+    /// Simulates dry-run submissions in `simulator`, which the caller keeps on the latest block.
+    pub fn with_simulator(mut self, simulator: Arc<EvmSimulator>) -> Self {
+        self.simulator = Some(simulator);
+        self
+    }
+
+    /// Sends bundle submissions to `relay` instead of the public mempool.
+    pub fn with_relay(mut self, relay: BundleRelay) -> Self {
+        self.relay = Some(relay);
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// The lease's signer under `strategy`'s policy, for signatures that are not transactions.
+    pub fn signer(&self, lease: &WalletLease, strategy: &str) -> PolicySigner {
+        lease.signer(self.policy.clone(), strategy)
+    }
+
+    pub async fn submit(&self, strategy: &str, lease: &WalletLease, tx: TypedTransaction) -> Result<Submission> {
+        self.send(strategy, lease, tx, None).await
+    }
+
+    /// Like `submit`, but through the relay as a bundle that cannot land outside `window`. Without
+    /// a relay the transaction is broadcast publicly and only its own swap deadline bounds it.
+    pub async fn submit_bundle(&self, strategy: &str, lease: &WalletLease, tx: TypedTransaction, window: &BundleWindow) -> Result<Submission> {
+        self.send(strategy, lease, tx, Some(window)).await
+    }
+
+    async fn send(&self, strategy: &str, lease: &WalletLease, mut tx: TypedTransaction, window: Option<&BundleWindow>) -> Result<Submission> {
+        tx.set_from(lease.address());
+        if let TypedTransaction::Eip1559(inner) = &mut tx {
+            inner.max_priority_fee_per_gas.get_or_insert(self.priority_fee);
+        }
+        let signer = self.signer(lease, strategy);
+
+        let journal = match &self.journal {
+            Some(journal) => journal,
+            None => {
+                tx.set_nonce(lease.next_nonce().await?);
+                let value = tx.value().copied().unwrap_or_default();
+                let client = SignerMiddleware::new(self.provider.clone(), signer);
+                let sent = match (window, &self.relay) {
+                    (Some(window), Some(relay)) => Self::send_bundle(&client, relay, tx, window).await,
+                    _ => client.send_transaction(tx, None).await.map(|pending| pending.tx_hash()).map_err(Into::into),
+                };
+                return match sent {
+                    Ok(tx_hash) => {
+                        if window.is_some() && self.relay.is_some() {
+                            // The bundle may expire unmined; let the next submission re-read the node's nonce
+                            lease.resync_nonce().await;
+                        }
+                        lease.debit(value).await;
+                        Ok(Submission::Broadcast(tx_hash))
+                    }
+                    Err(e) => {
+                        lease.resync_nonce().await;
+                        Err(anyhow!("Failed to broadcast {} transaction: {}", strategy, e))
+                    }
+                };
+            }
+        };
+
+        // Run the transaction past the policy as a live submission would, without spending today's limits
+        self.policy.check(strategy, &tx)?;
+
+        let entry = self.simulate(strategy, &tx).await?;
+        journal.record(&entry).await?;
+        info!("Dry run: journaled {} transaction to {:?} (success: {})", strategy, entry.to, entry.success);
+
+        Ok(Submission::Journaled(Box::new(entry)))
+    }
+
+    async fn send_bundle(
+        client: &SignerMiddleware<Arc<Provider<Ws>>, PolicySigner>,
+        relay: &BundleRelay,
+        mut tx: TypedTransaction,
+        window: &BundleWindow,
+    ) -> Result<H256> {
+        client.fill_transaction(&mut tx, None).await?;
+        let signature = client.signer().sign_transaction(&tx).await?;
+        let raw_tx = tx.rlp_signed(&signature);
+        relay.send(&raw_tx, window).await?;
+
+        Ok(H256::from(keccak256(&raw_tx)))
+    }
+
+    /// What `tx` would do on the latest block: run in the embedded EVM, or through `eth_call` and
+    /// `eth_estimateGas` until the simulator has seen a block.
+    async fn simulate(&self, strategy: &str, tx: &TypedTransaction) -> Result<JournalEntry> {
+        let (block_number, gas_estimate, success, output, error) = match self.simulator.as_ref().filter(|simulator| simulator.block_number().is_some()) {
+            Some(simulator) => {
+                let simulation = simulator.simulate(tx)?;
+                let error = (!simulation.success).then(|| simulation.halt_reason.unwrap_or_else(|| "execution reverted".to_string()));
+                (U64::from(simulation.block_number), Some(U256::from(simulation.gas_used)), simulation.success, Some(simulation.output), error)
+            }
+            None => {
+                let block_number = self.provider.get_block_number().await?;
+                let gas_estimate = self.provider.estimate_gas(tx, None).await.ok();
+                match self.provider.call(tx, None).await {
+                    Ok(output) => (block_number, gas_estimate, true, Some(output), None),
+                    Err(e) => (block_number, gas_estimate, false, None, Some(e.to_string())),
+                }
+            }
+        };
+
+        Ok(JournalEntry {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs(),
+            block_number,
+            strategy: strategy.to_string(),
+            from: tx.from().copied().unwrap_or_default(),
+            to: tx.to().and_then(|to| to.as_address()).copied(),
+            value: tx.value().copied().unwrap_or_default(),
+            data: tx.data().cloned().unwrap_or_default(),
+            gas_estimate,
+            success,
+            output,
+            error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(strategy: &str, success: bool) -> JournalEntry {
+        JournalEntry {
+            timestamp: 1_700_000_000,
+            block_number: U64::from(18_000_000),
+            strategy: strategy.to_string(),
+            from: Address::from_low_u64_be(1),
+            to: Some(Address::from_low_u64_be(2)),
+            value: U256::zero(),
+            data: Bytes::from(vec![0xa9, 0x05, 0x9c, 0xbb]),
+            gas_estimate: success.then(|| U256::from(21_000)),
+            success,
+            output: None,
+            error: (!success).then(|| "execution reverted".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_journal_appends_one_line_per_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dry_run.jsonl");
+        let journal = Journal::new(path.to_string_lossy());
+
+        journal.record(&entry("liquidation", true)).await.unwrap();
+        journal.record(&entry("arbitrage", false)).await.unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["strategy"], "liquidation");
+        assert_eq!(lines[1]["success"], false);
+        assert_eq!(lines[1]["error"], "execution reverted");
+    }
+}
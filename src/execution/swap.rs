@@ -0,0 +1,261 @@
+// src/execution/swap.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{self, Token},
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{Address, Bytes, U256},
+    utils::id,
+};
+use std::sync::Arc;
+use tracing::debug;
+
+use super::slippage::SlippageGuard;
+use crate::config::Config;
+
+abigen!(
+    IUniswapV2Router,
+    r#"[
+        function getAmountsOut(uint256 amountIn, address[] path) external view returns (uint256[] amounts)
+        function getAmountsIn(uint256 amountOut, address[] path) external view returns (uint256[] amounts)
+    ]"#
+);
+
+/// Quotes swaps against the router's current on-chain reserves.
+#[derive(Clone)]
+pub struct SwapQuoter {
+    provider: Arc<Provider<Ws>>,
+}
+
+impl SwapQuoter {
+    pub fn new(provider: Arc<Provider<Ws>>) -> Self {
+        Self { provider }
+    }
+
+    pub async fn quote_exact_in(&self, router: Address, amount_in: U256, path: &[Address]) -> Result<U256> {
+        let router = IUniswapV2Router::new(router, self.provider.clone());
+        let amounts = router.get_amounts_out(amount_in, path.to_vec()).call().await?;
+
+        amounts.last().copied().ok_or_else(|| anyhow!("Router returned no amounts"))
+    }
+
+    pub async fn quote_exact_out(&self, router: Address, amount_out: U256, path: &[Address]) -> Result<U256> {
+        let router = IUniswapV2Router::new(router, self.provider.clone());
+        let amounts = router.get_amounts_in(amount_out, path.to_vec()).call().await?;
+
+        amounts.first().copied().ok_or_else(|| anyhow!("Router returned no amounts"))
+    }
+}
+
+/// A quoted swap through one router.
+#[derive(Debug, Clone)]
+pub struct SwapRoute {
+    pub router: Address,
+    pub path: Vec<Address>,
+    pub amount_in: U256,
+    pub amount_out: U256,
+}
+
+/// Picks the best direct or WETH-hop route across a set of Uniswap V2 style routers.
+pub struct RouteFinder {
+    quoter: Arc<SwapQuoter>,
+    routers: Vec<Address>,
+    weth: Address,
+}
+
+impl RouteFinder {
+    pub fn new(quoter: Arc<SwapQuoter>, routers: Vec<Address>, weth: Address) -> Self {
+        Self { quoter, routers, weth }
+    }
+
+    /// Routes through the Uniswap V2 and Sushiswap routers of the global config.
+    pub fn from_config(quoter: Arc<SwapQuoter>, config: &Config) -> Result<Self> {
+        let routers = vec![
+            config.dex_contracts.uniswap_v2_router.parse::<Address>()?,
+            config.dex_contracts.sushiswap_router.parse::<Address>()?,
+        ];
+
+        Ok(Self::new(quoter, routers, config.tokens.weth.parse()?))
+    }
+
+    /// The route that pays the most `token_out` for `amount_in`, or `None` when no router trades the pair.
+    pub async fn best_exact_in(&self, token_in: Address, token_out: Address, amount_in: U256) -> Option<SwapRoute> {
+        let mut best: Option<SwapRoute> = None;
+        for &router in &self.routers {
+            for path in candidate_paths(token_in, token_out, self.weth) {
+                match self.quoter.quote_exact_in(router, amount_in, &path).await {
+                    Ok(amount_out) if best.as_ref().is_none_or(|route| amount_out > route.amount_out) => {
+                        best = Some(SwapRoute { router, path, amount_in, amount_out });
+                    }
+                    Ok(_) => {}
+                    Err(e) => debug!("No route {:?} on router {:?}: {}", path, router, e),
+                }
+            }
+        }
+
+        best
+    }
+
+    /// The route that takes the least `token_in` for `amount_out`, or `None` when no router trades the pair.
+    pub async fn best_exact_out(&self, token_in: Address, token_out: Address, amount_out: U256) -> Option<SwapRoute> {
+        let mut best: Option<SwapRoute> = None;
+        for &router in &self.routers {
+            for path in candidate_paths(token_in, token_out, self.weth) {
+                match self.quoter.quote_exact_out(router, amount_out, &path).await {
+                    Ok(amount_in) if best.as_ref().is_none_or(|route| amount_in < route.amount_in) => {
+                        best = Some(SwapRoute { router, path, amount_in, amount_out });
+                    }
+                    Ok(_) => {}
+                    Err(e) => debug!("No route {:?} on router {:?}: {}", path, router, e),
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// The direct pair, plus a hop through WETH when neither side is WETH.
+fn candidate_paths(token_in: Address, token_out: Address, weth: Address) -> Vec<Vec<Address>> {
+    let mut paths = vec![vec![token_in, token_out]];
+    if token_in != weth && token_out != weth {
+        paths.push(vec![token_in, weth, token_out]);
+    }
+    paths
+}
+
+/// Builds Uniswap V2 style router calldata with slippage bounds taken from a fresh quote.
+pub struct SwapCalldataBuilder {
+    quoter: SwapQuoter,
+    guard: SlippageGuard,
+    recipient: Address,
+}
+
+impl SwapCalldataBuilder {
+    pub fn new(quoter: SwapQuoter, guard: SlippageGuard, recipient: Address) -> Self {
+        Self {
+            quoter,
+            guard,
+            recipient,
+        }
+    }
+
+    /// The same quotes and tolerances, paying out to `recipient` instead.
+    pub fn with_recipient(&self, recipient: Address) -> Self {
+        Self::new(self.quoter.clone(), self.guard.clone(), recipient)
+    }
+
+    pub async fn exact_tokens_for_tokens(&self, router: Address, amount_in: U256, path: Vec<Address>, deadline: U256) -> Result<Bytes> {
+        let amount_out_min = self.min_amount_out(router, amount_in, &path).await?;
+        encode_swap_exact_tokens_for_tokens(amount_in, amount_out_min, path, self.recipient, deadline)
+    }
+
+    pub async fn exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(&self, router: Address, amount_in: U256, path: Vec<Address>, deadline: U256) -> Result<Bytes> {
+        let amount_out_min = self.min_amount_out(router, amount_in, &path).await?;
+        encode_swap_exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(amount_in, amount_out_min, path, self.recipient, deadline)
+    }
+
+    /// Calldata for `swapExactETHForTokens`; the caller sends `amount_in` as the transaction value.
+    pub async fn exact_eth_for_tokens(&self, router: Address, amount_in: U256, path: Vec<Address>, deadline: U256) -> Result<Bytes> {
+        let amount_out_min = self.min_amount_out(router, amount_in, &path).await?;
+        encode_swap_exact_eth_for_tokens(amount_out_min, path, self.recipient, deadline)
+    }
+
+    pub async fn exact_tokens_for_eth(&self, router: Address, amount_in: U256, path: Vec<Address>, deadline: U256) -> Result<Bytes> {
+        let amount_out_min = self.min_amount_out(router, amount_in, &path).await?;
+        encode_swap_exact_tokens_for_eth(amount_in, amount_out_min, path, self.recipient, deadline)
+    }
+
+    pub async fn tokens_for_exact_tokens(&self, router: Address, amount_out: U256, path: Vec<Address>, deadline: U256) -> Result<Bytes> {
+        let (token_in, token_out) = path_ends(&path)?;
+        let quoted_in = self.quoter.quote_exact_out(router, amount_out, &path).await?;
+        let amount_in_max = self.guard.max_amount_in(token_in, token_out, quoted_in)?;
+
+        encode_swap_tokens_for_exact_tokens(amount_out, amount_in_max, path, self.recipient, deadline)
+    }
+
+    /// The least a swap of `amount_in` along `path` may return: a fresh quote less the tolerance.
+    pub async fn min_amount_out(&self, router: Address, amount_in: U256, path: &[Address]) -> Result<U256> {
+        let (token_in, token_out) = path_ends(path)?;
+        let quoted_out = self.quoter.quote_exact_in(router, amount_in, path).await?;
+
+        self.guard.min_amount_out(token_in, token_out, quoted_out)
+    }
+}
+
+fn path_ends(path: &[Address]) -> Result<(Address, Address)> {
+    match (path.first(), path.last()) {
+        (Some(token_in), Some(token_out)) => Ok((*token_in, *token_out)),
+        _ => Err(anyhow!("Empty swap path")),
+    }
+}
+
+pub fn encode_swap_exact_tokens_for_tokens(amount_in: U256, amount_out_min: U256, path: Vec<Address>, to: Address, deadline: U256) -> Result<Bytes> {
+    ensure_nonzero_minimum(amount_out_min)?;
+    Ok(encode_call(
+        "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)",
+        vec![Token::Uint(amount_in), Token::Uint(amount_out_min), path_token(path), Token::Address(to), Token::Uint(deadline)],
+    ))
+}
+
+pub fn encode_swap_exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(amount_in: U256, amount_out_min: U256, path: Vec<Address>, to: Address, deadline: U256) -> Result<Bytes> {
+    ensure_nonzero_minimum(amount_out_min)?;
+    Ok(encode_call(
+        "swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+        vec![Token::Uint(amount_in), Token::Uint(amount_out_min), path_token(path), Token::Address(to), Token::Uint(deadline)],
+    ))
+}
+
+pub fn encode_swap_exact_eth_for_tokens(amount_out_min: U256, path: Vec<Address>, to: Address, deadline: U256) -> Result<Bytes> {
+    ensure_nonzero_minimum(amount_out_min)?;
+    Ok(encode_call(
+        "swapExactETHForTokens(uint256,address[],address,uint256)",
+        vec![Token::Uint(amount_out_min), path_token(path), Token::Address(to), Token::Uint(deadline)],
+    ))
+}
+
+pub fn encode_swap_exact_tokens_for_eth(amount_in: U256, amount_out_min: U256, path: Vec<Address>, to: Address, deadline: U256) -> Result<Bytes> {
+    ensure_nonzero_minimum(amount_out_min)?;
+    Ok(encode_call(
+        "swapExactTokensForETH(uint256,uint256,address[],address,uint256)",
+        vec![Token::Uint(amount_in), Token::Uint(amount_out_min), path_token(path), Token::Address(to), Token::Uint(deadline)],
+    ))
+}
+
+pub fn encode_swap_tokens_for_exact_tokens(amount_out: U256, amount_in_max: U256, path: Vec<Address>, to: Address, deadline: U256) -> Result<Bytes> {
+    ensure_nonzero_minimum(amount_out)?;
+    Ok(encode_call(
+        "swapTokensForExactTokens(uint256,uint256,address[],address,uint256)",
+        vec![Token::Uint(amount_out), Token::Uint(amount_in_max), path_token(path), Token::Address(to), Token::Uint(deadline)],
+    ))
+}
+
+fn ensure_nonzero_minimum(amount: U256) -> Result<()> {
+    if amount.is_zero() {
+        return Err(anyhow!("Refusing to encode swap with zero minimum output"));
+    }
+    Ok(())
+}
+
+fn path_token(path: Vec<Address>) -> Token {
+    Token::Array(path.into_iter().map(Token::Address).collect())
+}
+
+fn encode_call(signature: &str, args: Vec<Token>) -> Bytes {
+    let mut data = id(signature).to_vec();
+    data.extend_from_slice(&abi::encode(&args));
+    data.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_paths_hop_through_weth() {
+        let (usdc, dai, weth) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2), Address::from_low_u64_be(3));
+
+        assert_eq!(candidate_paths(usdc, dai, weth), vec![vec![usdc, dai], vec![usdc, weth, dai]]);
+        assert_eq!(candidate_paths(weth, dai, weth), vec![vec![weth, dai]]);
+    }
+}
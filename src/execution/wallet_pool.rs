@@ -0,0 +1,347 @@
+// src/execution/wallet_pool.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{Address, BlockNumber, TransactionRequest, H256, U256},
+};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::config::DryRunConfig;
+use crate::signer::{BotSigner, PolicyEngine, PolicySigner, SignerConfig};
+
+/// `config/wallet_pool.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WalletPoolConfig {
+    pub treasury: SignerConfig,
+    pub wallets: Vec<SignerConfig>,
+    /// Hot wallets below this balance are skipped and topped up.
+    pub min_gas_balance: U256,
+    /// Balance a hot wallet is refilled to from the treasury.
+    pub target_gas_balance: U256,
+}
+
+impl WalletPoolConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// A hot trading wallet with its own nonce stream.
+pub struct PooledWallet {
+    signer: BotSigner,
+    busy: AtomicBool,
+    next_nonce: Mutex<Option<U256>>,
+    balance: Mutex<U256>,
+}
+
+impl PooledWallet {
+    fn new(signer: BotSigner) -> Self {
+        Self {
+            signer,
+            busy: AtomicBool::new(false),
+            next_nonce: Mutex::new(None),
+            balance: Mutex::new(U256::zero()),
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+}
+
+/// Exclusive use of one hot wallet for one opportunity; the wallet is freed on drop.
+pub struct WalletLease<M = Provider<Ws>> {
+    wallet: Arc<PooledWallet>,
+    provider: Arc<M>,
+}
+
+impl<M: Middleware + 'static> WalletLease<M> {
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    /// The wallet's key behind `policy`; a lease never hands out the raw signer, so every
+    /// signature it makes passes the strategy's policy first.
+    pub fn signer(&self, policy: Arc<PolicyEngine>, strategy: &str) -> PolicySigner {
+        PolicySigner::new(self.wallet.signer.clone(), policy, strategy)
+    }
+
+    /// Hands out the next nonce, seeding from the node's pending count the first time.
+    pub async fn next_nonce(&self) -> Result<U256> {
+        let mut next = self.wallet.next_nonce.lock().await;
+        let nonce = match *next {
+            Some(nonce) => nonce,
+            None => {
+                self.provider
+                    .get_transaction_count(self.address(), Some(BlockNumber::Pending.into()))
+                    .await?
+            }
+        };
+        *next = Some(nonce + 1);
+
+        Ok(nonce)
+    }
+
+    /// Takes `amount` off the cached balance `acquire` ranks wallets by, so a wallet that just sent
+    /// value is not picked ahead of others before the next refresh.
+    pub async fn debit(&self, amount: U256) {
+        let mut balance = self.wallet.balance.lock().await;
+        *balance = balance.saturating_sub(amount);
+    }
+
+    /// Forgets the local nonce after a failed or dropped submission so the next one re-reads the node.
+    pub async fn resync_nonce(&self) {
+        *self.wallet.next_nonce.lock().await = None;
+    }
+}
+
+impl<M> Drop for WalletLease<M> {
+    fn drop(&mut self) {
+        self.wallet.busy.store(false, Ordering::Release);
+    }
+}
+
+/// The treasury only ever funds hot wallets; it is never handed out for trading.
+struct Treasury<M> {
+    client: SignerMiddleware<Arc<M>, BotSigner>,
+}
+
+impl<M: Middleware + 'static> Treasury<M> {
+    async fn fund(&self, to: Address, amount: U256) -> Result<H256> {
+        let tx = TransactionRequest::new().to(to).value(amount);
+        let pending = self.client.send_transaction(tx, None).await?;
+        let tx_hash = pending.tx_hash();
+        pending.await?.ok_or_else(|| anyhow!("Top-up transaction {:?} was dropped", tx_hash))?;
+
+        Ok(tx_hash)
+    }
+}
+
+/// Hot wallets and the treasury that keeps them in gas.
+///
+/// Balances, which `acquire` picks wallets by, are only re-read by `top_up` and in between are
+/// debited by the value each broadcast sends; run `watch_blocks` next to the strategies so both
+/// the re-read and the top-up happen every block.
+pub struct WalletPool<M = Provider<Ws>> {
+    provider: Arc<M>,
+    wallets: Vec<Arc<PooledWallet>>,
+    treasury: Treasury<M>,
+    min_gas_balance: U256,
+    target_gas_balance: U256,
+    /// Top-ups are logged instead of sent; the treasury never holds a lease, so they bypass the submitter.
+    dry_run: bool,
+}
+
+impl<M: Middleware + 'static> WalletPool<M> {
+    pub async fn from_config(provider: Arc<M>, config: &WalletPoolConfig, dry_run: &DryRunConfig) -> Result<Self> {
+        let chain_id = provider.get_chainid().await?.as_u64();
+        let treasury = BotSigner::from_config(&config.treasury, chain_id).await?;
+
+        let mut wallets = Vec::new();
+        for wallet_config in &config.wallets {
+            let signer = BotSigner::from_config(wallet_config, chain_id).await?;
+            if signer.address() == treasury.address() {
+                return Err(anyhow!("Treasury {:?} cannot also be a trading wallet", treasury.address()));
+            }
+            wallets.push(Arc::new(PooledWallet::new(signer)));
+        }
+        if wallets.is_empty() {
+            return Err(anyhow!("Wallet pool has no trading wallets"));
+        }
+
+        let pool = Self {
+            treasury: Treasury {
+                client: SignerMiddleware::new(provider.clone(), treasury),
+            },
+            provider,
+            wallets,
+            min_gas_balance: config.min_gas_balance,
+            target_gas_balance: config.target_gas_balance,
+            dry_run: dry_run.enabled,
+        };
+        pool.refresh_balances().await?;
+
+        Ok(pool)
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.wallets.iter().map(|wallet| wallet.address()).collect()
+    }
+
+    /// Leases the free wallet with the most gas money, or `None` if every wallet is busy or underfunded.
+    pub async fn acquire(&self) -> Option<WalletLease<M>> {
+        let mut candidates = Vec::new();
+        for wallet in &self.wallets {
+            let balance = *wallet.balance.lock().await;
+            if balance >= self.min_gas_balance {
+                candidates.push((balance, wallet));
+            }
+        }
+        candidates.sort_by_key(|(balance, _)| std::cmp::Reverse(*balance));
+
+        candidates.into_iter().find_map(|(_, wallet)| {
+            wallet
+                .busy
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .ok()
+                .map(|_| WalletLease {
+                    wallet: wallet.clone(),
+                    provider: self.provider.clone(),
+                })
+        })
+    }
+
+    pub async fn refresh_balances(&self) -> Result<()> {
+        for wallet in &self.wallets {
+            let balance = self.provider.get_balance(wallet.address(), None).await?;
+            *wallet.balance.lock().await = balance;
+        }
+        Ok(())
+    }
+
+    /// Refills every hot wallet below `min_gas_balance` up to `target_gas_balance` from the treasury.
+    pub async fn top_up(&self) -> Result<Vec<H256>> {
+        self.refresh_balances().await?;
+
+        let mut funded = Vec::new();
+        for wallet in &self.wallets {
+            let balance = *wallet.balance.lock().await;
+            if balance >= self.min_gas_balance {
+                continue;
+            }
+
+            let amount = self.target_gas_balance.saturating_sub(balance);
+            if self.dry_run {
+                info!("Dry run: would top up {:?} with {} wei", wallet.address(), amount);
+                continue;
+            }
+            match self.treasury.fund(wallet.address(), amount).await {
+                Ok(tx_hash) => {
+                    info!("Topped up {:?} with {} wei", wallet.address(), amount);
+                    *wallet.balance.lock().await = balance + amount;
+                    funded.push(tx_hash);
+                }
+                Err(e) => warn!("Failed to top up {:?}: {}", wallet.address(), e),
+            }
+        }
+
+        Ok(funded)
+    }
+}
+
+impl WalletPool {
+    /// Tops wallets up on every new block. Runs until the subscription ends.
+    pub async fn watch_blocks(&self) -> Result<()> {
+        let mut blocks = self.provider.subscribe_blocks().await?;
+        while let Some(block) = blocks.next().await {
+            if let Err(e) = self.top_up().await {
+                warn!("Failed to top up hot wallets at block {:?}: {}", block.number, e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::core::rand::thread_rng;
+    use ethers::providers::{MockError, MockProvider};
+
+    fn random_signer() -> BotSigner {
+        BotSigner::Local(LocalWallet::new(&mut thread_rng()))
+    }
+
+    async fn funded_pool(wallets: usize) -> (WalletPool<Provider<MockProvider>>, MockProvider) {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+        let pool = WalletPool {
+            treasury: Treasury {
+                client: SignerMiddleware::new(provider.clone(), random_signer()),
+            },
+            provider,
+            wallets: (0..wallets).map(|_| Arc::new(PooledWallet::new(random_signer()))).collect(),
+            min_gas_balance: U256::from(100),
+            target_gas_balance: U256::from(1_000),
+            dry_run: false,
+        };
+        for wallet in &pool.wallets {
+            *wallet.balance.lock().await = U256::from(500);
+        }
+
+        (pool, mock)
+    }
+
+    #[tokio::test]
+    async fn test_lease_is_exclusive_until_dropped() {
+        let (pool, _) = funded_pool(2).await;
+
+        let first = pool.acquire().await.unwrap();
+        let second = pool.acquire().await.unwrap();
+        assert_ne!(first.address(), second.address());
+        assert!(pool.acquire().await.is_none());
+
+        let freed = first.address();
+        drop(first);
+        assert_eq!(pool.acquire().await.unwrap().address(), freed);
+    }
+
+    #[tokio::test]
+    async fn test_underfunded_wallets_are_not_leased() {
+        let (pool, _) = funded_pool(1).await;
+        *pool.wallets[0].balance.lock().await = U256::from(99);
+
+        assert!(pool.acquire().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_debit_lowers_cached_balance() {
+        let (pool, _) = funded_pool(2).await;
+        *pool.wallets[0].balance.lock().await = U256::from(600);
+
+        let lease = pool.acquire().await.unwrap();
+        assert_eq!(lease.address(), pool.wallets[0].address());
+        lease.debit(U256::from(550)).await;
+        drop(lease);
+
+        // Now below the minimum, so only the other wallet is leased
+        let other = pool.acquire().await.unwrap();
+        assert_eq!(other.address(), pool.wallets[1].address());
+        assert!(pool.acquire().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_top_up_only_reads_balances() {
+        let (mut pool, mock) = funded_pool(1).await;
+        pool.dry_run = true;
+        let wallet = pool.wallets[0].address();
+
+        mock.push(U256::from(50)).unwrap();
+        assert!(pool.top_up().await.unwrap().is_empty());
+
+        mock.assert_request("eth_getBalance", (wallet, "latest")).unwrap();
+        // Nothing else reached the node
+        assert!(matches!(mock.assert_request("eth_sendRawTransaction", ()), Err(MockError::EmptyRequests)));
+    }
+
+    #[tokio::test]
+    async fn test_nonce_increments_and_resyncs_from_node() {
+        let (pool, mock) = funded_pool(1).await;
+        let lease = pool.acquire().await.unwrap();
+
+        mock.push(U256::from(7)).unwrap();
+        assert_eq!(lease.next_nonce().await.unwrap(), U256::from(7));
+        // Served locally: no response is queued for a second request
+        assert_eq!(lease.next_nonce().await.unwrap(), U256::from(8));
+
+        lease.resync_nonce().await;
+        mock.push(U256::from(3)).unwrap();
+        assert_eq!(lease.next_nonce().await.unwrap(), U256::from(3));
+    }
+}
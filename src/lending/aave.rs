@@ -0,0 +1,721 @@
+// src/lending/aave.rs
+use async_trait::async_trait;
+use ethers::{
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::debug;
+use anyhow::{anyhow, Result};
+
+use super::indexer::{BorrowerIndexConfig, IndexedMarket, Protocol};
+use super::lending_pool::{
+    FlashLiquidationLeg, LendingPool, LiquidationCandidate, LiquidationParams, LiquidationVenue, ReservePosition, UserAccountData,
+    DEFAULT_DUST_DEBT_VALUE,
+};
+use crate::execution::ApprovalsConfig;
+use crate::math::{mul_div, unit_decimals, Wad};
+use crate::signer::PolicyConfig;
+
+const BPS: u64 = 10_000;
+// Aave lets half of a debt position be repaid; V3 allows all of it once HF is at or below 0.95
+const DEFAULT_CLOSE_FACTOR_BPS: u64 = 5_000;
+const MAX_CLOSE_FACTOR_BPS: u64 = 10_000;
+const CLOSE_FACTOR_HF_THRESHOLD_BPS: u64 = 9_500;
+// The user configuration bitmap has two bits per reserve
+const MAX_RESERVES: u16 = 128;
+/// Signing policy and approvals our liquidations of these markets run under.
+const LIQUIDATION_STRATEGY: &str = "liquidation";
+// liquidationCall(address,address,address,uint256,bool)
+const LIQUIDATION_CALL_SELECTOR: &str = "00a718a9";
+
+abigen!(
+    AavePool,
+    r#"[
+        function getReservesList() external view returns (address[])
+        function getUserAccountData(address user) external view returns (uint256 totalCollateralBase, uint256 totalDebtBase, uint256 availableBorrowsBase, uint256 currentLiquidationThreshold, uint256 ltv, uint256 healthFactor)
+        function getReserveAddressById(uint16 id) external view returns (address)
+        function getConfiguration(address asset) external view returns (uint256)
+        function getUserConfiguration(address user) external view returns (uint256)
+        function getUserEMode(address user) external view returns (uint256)
+        function getEModeCategoryData(uint8 id) external view returns ((uint16,uint16,uint16,address,string))
+        function liquidationCall(address collateralAsset, address debtAsset, address user, uint256 debtToCover, bool receiveAToken)
+    ]"#
+);
+
+abigen!(
+    AaveProtocolDataProvider,
+    r#"[
+        function getUserReserveData(address asset, address user) external view returns (uint256 currentATokenBalance, uint256 currentStableDebt, uint256 currentVariableDebt, uint256 principalStableDebt, uint256 scaledVariableDebt, uint256 stableBorrowRate, uint256 liquidityRate, uint40 stableRateLastUpdated, bool usageAsCollateralEnabled)
+        function getReserveTokensAddresses(address asset) external view returns (address aTokenAddress, address stableDebtTokenAddress, address variableDebtTokenAddress)
+    ]"#
+);
+
+abigen!(
+    AaveOracle,
+    r#"[
+        function getAssetsPrices(address[] assets) external view returns (uint256[])
+        function BASE_CURRENCY_UNIT() external view returns (uint256)
+    ]"#
+);
+
+/// `config/aave_markets.json`: Aave deployments and forks sharing its interface.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AaveMarketsConfig {
+    pub markets: Vec<AaveMarketConfig>,
+}
+
+impl AaveMarketsConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Indexes each market's borrowers and lets the liquidation strategy approve and call its
+    /// pool, so adding a fork takes only its entry here.
+    pub fn register(&self, index: &mut BorrowerIndexConfig, approvals: &mut ApprovalsConfig, policy: &mut PolicyConfig) -> Result<()> {
+        for market in &self.markets {
+            index.add_market(IndexedMarket {
+                name: market.name.clone(),
+                protocol: match market.version {
+                    AaveVersion::V2 => Protocol::AaveV2,
+                    AaveVersion::V3 => Protocol::AaveV3,
+                },
+                contracts: vec![market.pool],
+                deployment_block: market.deployment_block,
+            });
+            approvals.add_spender(LIQUIDATION_STRATEGY, market.pool);
+            policy.allow(LIQUIDATION_STRATEGY, market.pool, &[LIQUIDATION_CALL_SELECTOR])?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AaveMarketConfig {
+    /// Matches the market's name in the borrower index.
+    pub name: String,
+    pub version: AaveVersion,
+    pub pool: Address,
+    pub data_provider: Address,
+    pub oracle: Address,
+    /// Block the pool was deployed at, where indexing its borrowers starts.
+    pub deployment_block: u64,
+    #[serde(default)]
+    pub quirks: AaveQuirks,
+}
+
+/// Which `Pool` generation a market runs; V2 has no e-mode, isolation mode or full close factor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AaveVersion {
+    V2,
+    V3,
+}
+
+/// Where a fork departs from the upstream version it is based on.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AaveQuirks {
+    /// Decimals of oracle prices, for oracles without `BASE_CURRENCY_UNIT` (ETH-denominated markets use 18).
+    pub base_currency_decimals: Option<u8>,
+    /// Share of a debt position one liquidation may repay, where the fork changed it from 50%.
+    pub close_factor_bps: Option<u64>,
+    /// Reserve ids are positions in `getReservesList`, for V3 forks without `getReserveAddressById`.
+    pub reserves_from_list: bool,
+    /// Smallest debt worth liquidating, as a decimal in the base currency, for markets not priced in USD.
+    pub dust_debt_value: Option<String>,
+}
+
+/// How much of a debt position one liquidation may repay.
+#[derive(Debug, Clone, Copy)]
+struct CloseFactor {
+    default_bps: u64,
+    /// Health factor at or below which the whole position may be repaid.
+    full_at_or_below: Option<Wad>,
+}
+
+impl CloseFactor {
+    fn for_market(version: AaveVersion, quirks: &AaveQuirks) -> Self {
+        Self {
+            default_bps: quirks.close_factor_bps.unwrap_or(DEFAULT_CLOSE_FACTOR_BPS),
+            full_at_or_below: match version {
+                AaveVersion::V2 => None,
+                AaveVersion::V3 => Some(Wad::from_bps(CLOSE_FACTOR_HF_THRESHOLD_BPS)),
+            },
+        }
+    }
+
+    fn bps(&self, health_factor: Wad) -> u64 {
+        // V3's LiquidationLogic keeps the default only while `healthFactor > CLOSE_FACTOR_HF_THRESHOLD`
+        match self.full_at_or_below {
+            Some(threshold) if health_factor <= threshold => MAX_CLOSE_FACTOR_BPS,
+            _ => self.default_bps,
+        }
+    }
+}
+
+/// The fields of Aave's packed `ReserveConfigurationMap` that matter for liquidations; the V3-only
+/// fields decode as zero on V2.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ReserveConfig {
+    liquidation_threshold_bps: u64,
+    liquidation_bonus_bps: u64,
+    decimals: u8,
+    active: bool,
+    paused: bool,
+    protocol_fee_bps: u64,
+    emode_category: u8,
+    debt_ceiling: u64,
+}
+
+impl ReserveConfig {
+    fn decode(data: U256) -> Self {
+        let bits = |offset: usize, width: usize| ((data >> offset) & ((U256::one() << width) - 1)).as_u64();
+
+        Self {
+            liquidation_threshold_bps: bits(16, 16),
+            liquidation_bonus_bps: bits(32, 16),
+            decimals: bits(48, 8) as u8,
+            active: bits(56, 1) == 1,
+            paused: bits(60, 1) == 1,
+            protocol_fee_bps: bits(152, 16),
+            emode_category: bits(168, 8) as u8,
+            debt_ceiling: bits(212, 40),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EModeCategory {
+    liquidation_threshold_bps: u64,
+    liquidation_bonus_bps: u64,
+    /// Asset whose oracle price replaces every category member's price, if set.
+    price_source: Address,
+}
+
+/// An Aave V2 or V3 market, or any fork of either, described entirely by its config entry.
+pub struct AaveLendingPool {
+    name: String,
+    version: AaveVersion,
+    reserves_from_list: bool,
+    close_factor: CloseFactor,
+    pool: AavePool<Provider<Ws>>,
+    data_provider: AaveProtocolDataProvider<Provider<Ws>>,
+    oracle: AaveOracle<Provider<Ws>>,
+    base_currency_decimals: u8,
+    dust_debt_value: Wad,
+    reserves: RwLock<HashMap<u16, Address>>,
+    configs: RwLock<HashMap<Address, ReserveConfig>>,
+    emode_categories: RwLock<HashMap<u8, EModeCategory>>,
+}
+
+impl AaveLendingPool {
+    pub async fn connect(config: &AaveMarketConfig, provider: Arc<Provider<Ws>>) -> Result<Self> {
+        let oracle = AaveOracle::new(config.oracle, provider.clone());
+
+        // 1e8 for USD-denominated markets, 1e18 for the original ETH-denominated ones
+        let base_currency_decimals = match config.quirks.base_currency_decimals {
+            Some(decimals) => decimals,
+            None => unit_decimals(oracle.base_currency_unit().call().await?)?,
+        };
+        let dust_debt_value = match &config.quirks.dust_debt_value {
+            Some(value) => value.parse()?,
+            None => Wad::from_integer(DEFAULT_DUST_DEBT_VALUE),
+        };
+
+        Ok(Self {
+            name: config.name.clone(),
+            version: config.version,
+            reserves_from_list: config.version == AaveVersion::V2 || config.quirks.reserves_from_list,
+            close_factor: CloseFactor::for_market(config.version, &config.quirks),
+            pool: AavePool::new(config.pool, provider.clone()),
+            data_provider: AaveProtocolDataProvider::new(config.data_provider, provider),
+            oracle,
+            base_currency_decimals,
+            dust_debt_value,
+            reserves: RwLock::new(HashMap::new()),
+            configs: RwLock::new(HashMap::new()),
+            emode_categories: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Drops cached reserve and e-mode parameters so governance changes are picked up.
+    pub async fn refresh_reserves(&self) {
+        if self.reserves_from_list {
+            self.reserves.write().await.clear();
+        }
+        self.configs.write().await.clear();
+        self.emode_categories.write().await.clear();
+    }
+
+    async fn reserve_address(&self, id: u16) -> Result<Address> {
+        if let Some(asset) = self.reserves.read().await.get(&id) {
+            return Ok(*asset);
+        }
+
+        if self.reserves_from_list {
+            let list = self.pool.get_reserves_list().call().await?;
+            let mut reserves = self.reserves.write().await;
+            reserves.extend(list.into_iter().enumerate().map(|(index, asset)| (index as u16, asset)));
+            return reserves.get(&id).copied().ok_or_else(|| anyhow!("{} has no reserve {}", self.name, id));
+        }
+
+        let asset = self.pool.get_reserve_address_by_id(id).call().await?;
+        self.reserves.write().await.insert(id, asset);
+        Ok(asset)
+    }
+
+    async fn reserve_config(&self, asset: Address) -> Result<ReserveConfig> {
+        if let Some(config) = self.configs.read().await.get(&asset) {
+            return Ok(*config);
+        }
+
+        let config = ReserveConfig::decode(self.pool.get_configuration(asset).call().await?);
+        self.configs.write().await.insert(asset, config);
+        Ok(config)
+    }
+
+    async fn emode_category(&self, id: u8) -> Result<EModeCategory> {
+        if let Some(category) = self.emode_categories.read().await.get(&id) {
+            return Ok(*category);
+        }
+
+        let (_ltv, liquidation_threshold, liquidation_bonus, price_source, _label) =
+            self.pool.get_e_mode_category_data(id).call().await?;
+        let category = EModeCategory {
+            liquidation_threshold_bps: liquidation_threshold as u64,
+            liquidation_bonus_bps: liquidation_bonus as u64,
+            price_source,
+        };
+        self.emode_categories.write().await.insert(id, category);
+        Ok(category)
+    }
+}
+
+#[async_trait]
+impl LendingPool for AaveLendingPool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dust_debt_value(&self) -> Wad {
+        self.dust_debt_value
+    }
+
+    async fn get_user_account_data(&self, user: Address) -> Result<UserAccountData> {
+        let result = self.pool.get_user_account_data(user).call().await?;
+
+        Ok(UserAccountData {
+            collateral_value: result.0,
+            debt_value: result.1,
+            health_factor: Wad::from_raw(result.5),
+            liquidation_threshold: result.3,
+        })
+    }
+
+    async fn get_user_positions(&self, user: Address) -> Result<Vec<ReservePosition>> {
+        let user_config = self.pool.get_user_configuration(user).call().await?;
+        let emode_id = match self.version {
+            AaveVersion::V2 => 0,
+            AaveVersion::V3 => self.pool.get_user_e_mode(user).call().await?.low_u64() as u8,
+        };
+        let emode = match emode_id {
+            0 => None,
+            id => Some(self.emode_category(id).await?),
+        };
+
+        // (asset, config, used as collateral, e-mode parameters if the asset is in the user's category)
+        let mut reserves = Vec::new();
+        for id in 0..MAX_RESERVES {
+            let remaining = user_config >> (2 * id as usize);
+            if remaining.is_zero() {
+                break;
+            }
+            let flags = remaining.low_u64() & 0b11;
+            if flags == 0 {
+                continue;
+            }
+
+            let asset = self.reserve_address(id).await?;
+            let config = self.reserve_config(asset).await?;
+            let category = emode.filter(|_| config.emode_category == emode_id);
+            reserves.push((asset, config, flags & 0b10 != 0, category));
+        }
+
+        // In isolation mode the isolated asset is the only collateral backing the debt
+        let collaterals: Vec<_> = reserves.iter().filter(|(_, _, used, _)| *used).collect();
+        let isolated = match collaterals.as_slice() {
+            [(asset, config, _, _)] if config.debt_ceiling != 0 => Some(*asset),
+            _ => None,
+        };
+        if let Some(asset) = isolated {
+            debug!("{:?} is in isolation mode on {:?}", user, asset);
+        }
+
+        let price_assets: Vec<Address> = reserves
+            .iter()
+            .map(|(asset, _, _, category)| match category {
+                Some(category) if !category.price_source.is_zero() => category.price_source,
+                _ => *asset,
+            })
+            .collect();
+        let prices = self.oracle.get_assets_prices(price_assets.clone()).call().await?;
+
+        let mut positions = Vec::new();
+        for (((asset, config, used_as_collateral, category), price_source), price) in
+            reserves.into_iter().zip(price_assets).zip(prices)
+        {
+            let data = self.data_provider.get_user_reserve_data(asset, user).call().await?;
+            let (liquidation_threshold_bps, liquidation_bonus_bps) = match category {
+                Some(category) => (category.liquidation_threshold_bps, category.liquidation_bonus_bps),
+                None => (config.liquidation_threshold_bps, config.liquidation_bonus_bps),
+            };
+
+            positions.push(ReservePosition {
+                asset,
+                decimals: config.decimals,
+                price: Wad::from_scaled(price, self.base_currency_decimals)?,
+                price_source,
+                collateral: data.0,
+                debt: data.1 + data.2,
+                collateral_enabled: used_as_collateral
+                    && liquidation_threshold_bps != 0
+                    && config.active
+                    && isolated.is_none_or(|isolated| isolated == asset),
+                liquidation_threshold_bps,
+                liquidation_bonus_bps,
+                protocol_fee_bps: config.protocol_fee_bps,
+            });
+        }
+
+        Ok(positions)
+    }
+
+    async fn asset_prices(&self, assets: &[Address]) -> Result<Vec<Wad>> {
+        let prices = self.oracle.get_assets_prices(assets.to_vec()).call().await?;
+        prices
+            .into_iter()
+            .map(|price| Ok(Wad::from_scaled(price, self.base_currency_decimals)?))
+            .collect()
+    }
+
+    async fn find_liquidation(&self, user: Address) -> Result<Option<LiquidationCandidate>> {
+        let account = self.get_user_account_data(user).await?;
+        if account.health_factor >= Wad::ONE {
+            return Ok(None);
+        }
+
+        // Paused reserves still count towards the health factor but cannot be liquidated
+        let mut positions = self.get_user_positions(user).await?;
+        for position in positions.iter_mut() {
+            if self.reserve_config(position.asset).await?.paused {
+                position.collateral_enabled = false;
+                position.debt = U256::zero();
+            }
+        }
+        best_liquidation(user, &positions, account.health_factor, self.close_factor)
+    }
+
+    fn health_factor(&self, positions: &[ReservePosition]) -> Result<Wad> {
+        aave_health_factor(positions, self.base_currency_decimals)
+    }
+
+    fn liquidation_tx(&self, params: &LiquidationParams) -> TypedTransaction {
+        self.pool
+            .liquidation_call(
+                params.collateral_token,
+                params.debt_token,
+                params.user,
+                params.debt_to_cover,
+                !params.receive_underlying,
+            )
+            .tx
+    }
+
+    async fn collateral_share_token(&self, asset: Address) -> Result<Address> {
+        Ok(self.data_provider.get_reserve_tokens_addresses(asset).call().await?.0)
+    }
+
+    fn flash_liquidation_leg(&self, params: &LiquidationParams) -> Option<FlashLiquidationLeg> {
+        Some(FlashLiquidationLeg {
+            venue: LiquidationVenue::AaveV3,
+            market: self.pool.address(),
+            debt_underlying: params.debt_token,
+            collateral_underlying: params.collateral_token,
+            debt_is_native: false,
+            collateral_is_native: false,
+        })
+    }
+}
+
+/// Health factor with the integer rounding of Aave's `GenericLogic.calculateUserAccountData`:
+/// values truncated to base currency units, the threshold averaged with truncation, then
+/// `percentMul` and `wadDiv`, both rounding half up.
+fn aave_health_factor(positions: &[ReservePosition], base_currency_decimals: u8) -> Result<Wad> {
+    let overflow = || anyhow!("Health factor overflows");
+    let mut total_collateral = U256::zero();
+    let mut weighted_threshold = U256::zero();
+    let mut total_debt = U256::zero();
+
+    for position in positions {
+        let price = position.price.to_scaled(base_currency_decimals)?;
+        let unit = U256::exp10(position.decimals as usize);
+
+        if position.collateral_enabled {
+            let value = position.collateral.checked_mul(price).ok_or_else(overflow)? / unit;
+            total_collateral = total_collateral.checked_add(value).ok_or_else(overflow)?;
+            weighted_threshold = value
+                .checked_mul(U256::from(position.liquidation_threshold_bps))
+                .and_then(|weighted| weighted.checked_add(weighted_threshold))
+                .ok_or_else(overflow)?;
+        }
+        total_debt = (position.debt.checked_mul(price).ok_or_else(overflow)? / unit)
+            .checked_add(total_debt)
+            .ok_or_else(overflow)?;
+    }
+
+    if total_debt.is_zero() {
+        return Ok(Wad::from_raw(U256::MAX));
+    }
+    let average_threshold = if total_collateral.is_zero() { U256::zero() } else { weighted_threshold / total_collateral };
+    let adjusted_collateral = (total_collateral * average_threshold + U256::from(BPS / 2)) / BPS;
+
+    Ok(Wad::from_raw(
+        adjusted_collateral
+            .checked_mul(Wad::ONE.raw())
+            .map(|scaled| (scaled + total_debt / 2) / total_debt)
+            .ok_or_else(overflow)?,
+    ))
+}
+
+/// Sizes a liquidation of `debt` against `collateral` the way Aave's `LiquidationLogic` does.
+///
+/// Returns the debt actually repaid, the collateral seized in total and the part of it the
+/// protocol keeps as its fee.
+fn size_liquidation(
+    collateral: &ReservePosition,
+    debt: &ReservePosition,
+    debt_to_cover: U256,
+) -> Result<(U256, U256, U256)> {
+    let bonus = U256::from(collateral.liquidation_bonus_bps);
+    let bps = U256::from(BPS);
+    let collateral_unit = U256::exp10(collateral.decimals as usize);
+    let debt_unit = U256::exp10(debt.decimals as usize);
+
+    let base_collateral = mul_div(
+        debt_to_cover,
+        debt.price.raw() * collateral_unit,
+        collateral.price.raw() * debt_unit,
+    )?;
+    let max_collateral = mul_div(base_collateral, bonus, bps)?;
+
+    let (seized, repaid) = if max_collateral > collateral.collateral {
+        let collateral_in_debt = mul_div(
+            collateral.collateral,
+            collateral.price.raw() * debt_unit,
+            debt.price.raw() * collateral_unit,
+        )?;
+        (collateral.collateral, mul_div(collateral_in_debt, bps, bonus)?)
+    } else {
+        (max_collateral, debt_to_cover)
+    };
+
+    let bonus_collateral = seized - mul_div(seized, bps, bonus)?;
+    let protocol_fee = mul_div(bonus_collateral, U256::from(collateral.protocol_fee_bps), bps)?;
+
+    Ok((repaid, seized, protocol_fee))
+}
+
+/// Picks the collateral/debt pair that leaves the liquidator the most value after repaying the debt.
+fn best_liquidation(
+    user: Address,
+    positions: &[ReservePosition],
+    health_factor: Wad,
+    close_factor: CloseFactor,
+) -> Result<Option<LiquidationCandidate>> {
+    let close_factor = U256::from(close_factor.bps(health_factor));
+    let mut best: Option<(Wad, LiquidationCandidate)> = None;
+
+    for debt in positions.iter().filter(|position| !position.debt.is_zero() && !position.price.is_zero()) {
+        let debt_to_cover = mul_div(debt.debt, close_factor, U256::from(BPS))?;
+
+        for collateral in positions.iter().filter(|position| {
+            position.collateral_enabled
+                && !position.collateral.is_zero()
+                && !position.price.is_zero()
+                && position.liquidation_bonus_bps > BPS
+        }) {
+            let (repaid, seized, protocol_fee) = size_liquidation(collateral, debt, debt_to_cover)?;
+            if repaid.is_zero() {
+                continue;
+            }
+
+            let received = seized - protocol_fee;
+            let paid_for = mul_div(seized, U256::from(BPS), U256::from(collateral.liquidation_bonus_bps))?;
+            let gross_profit = received.saturating_sub(paid_for);
+            let value = Wad::from_scaled(gross_profit, collateral.decimals)?.checked_mul(collateral.price)?;
+
+            if best.as_ref().is_none_or(|(best_value, _)| value > *best_value) {
+                best = Some((value, LiquidationCandidate {
+                    params: LiquidationParams {
+                        user,
+                        collateral_token: collateral.asset,
+                        debt_token: debt.asset,
+                        debt_to_cover: repaid,
+                        receive_underlying: true,
+                    },
+                    health_factor,
+                    collateral_underlying: collateral.asset,
+                    collateral_decimals: collateral.decimals,
+                    debt_decimals: debt.decimals,
+                    seized_collateral: received,
+                    gross_profit,
+                }));
+            }
+        }
+    }
+
+    Ok(best.map(|(_, candidate)| candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(asset: u64, decimals: u8, price: u64, collateral: u64, debt: u64, bonus_bps: u64) -> ReservePosition {
+        ReservePosition {
+            asset: Address::from_low_u64_be(asset),
+            decimals,
+            price: Wad::from_integer(price),
+            price_source: Address::from_low_u64_be(asset),
+            collateral: U256::from(collateral) * U256::exp10(decimals as usize),
+            debt: U256::from(debt) * U256::exp10(decimals as usize),
+            collateral_enabled: collateral > 0,
+            liquidation_threshold_bps: 8_000,
+            liquidation_bonus_bps: bonus_bps,
+            protocol_fee_bps: 0,
+        }
+    }
+
+    #[test]
+    fn test_decodes_reserve_configuration() {
+        // LTV 80%, LT 82.5%, bonus 5%, 18 decimals, active, 10% protocol fee, e-mode 1
+        let data = U256::from(8_000u64)
+            | U256::from(8_250u64) << 16
+            | U256::from(10_500u64) << 32
+            | U256::from(18u64) << 48
+            | U256::one() << 56
+            | U256::from(1_000u64) << 152
+            | U256::one() << 168;
+
+        let config = ReserveConfig::decode(data);
+        assert_eq!(config.liquidation_threshold_bps, 8_250);
+        assert_eq!(config.liquidation_bonus_bps, 10_500);
+        assert_eq!(config.decimals, 18);
+        assert!(config.active && !config.paused);
+        assert_eq!(config.protocol_fee_bps, 1_000);
+        assert_eq!(config.emode_category, 1);
+        assert_eq!(config.debt_ceiling, 0);
+    }
+
+    #[test]
+    fn test_close_factor_doubles_below_threshold() {
+        let user = Address::from_low_u64_be(9);
+        let positions = [position(1, 18, 2_000, 10, 0, 10_500), position(2, 6, 1, 0, 10_000, 10_000)];
+
+        let v3 = CloseFactor::for_market(AaveVersion::V3, &AaveQuirks::default());
+
+        let half = best_liquidation(user, &positions, Wad::from_bps(9_800), v3).unwrap().unwrap();
+        assert_eq!(half.params.debt_to_cover, U256::from(5_000u64) * U256::exp10(6));
+        // 5000 USDC at $1 buys 2.5 ETH, plus the 5% bonus
+        assert_eq!(half.seized_collateral, U256::from(2_625u64) * U256::exp10(15));
+
+        let full = best_liquidation(user, &positions, Wad::from_bps(9_000), v3).unwrap().unwrap();
+        assert_eq!(full.params.debt_to_cover, U256::from(10_000u64) * U256::exp10(6));
+        // Exactly at the threshold already allows the full close
+        assert_eq!(v3.bps(Wad::from_bps(9_500)), 10_000);
+        assert_eq!(v3.bps(Wad::from_bps(9_501)), 5_000);
+
+        // V2 never goes past the close factor, which forks may change
+        let v2 = CloseFactor::for_market(AaveVersion::V2, &AaveQuirks::default());
+        let v2_fork = CloseFactor::for_market(AaveVersion::V2, &AaveQuirks { close_factor_bps: Some(7_500), ..Default::default() });
+        assert_eq!(v2.bps(Wad::from_bps(9_000)), 5_000);
+        assert_eq!(v2_fork.bps(Wad::from_bps(9_000)), 7_500);
+    }
+
+    #[test]
+    fn test_picks_highest_bonus_and_caps_at_collateral_balance() {
+        let user = Address::from_low_u64_be(9);
+        let positions = [
+            position(1, 18, 2_000, 10, 0, 10_500),
+            position(3, 8, 1_000, 1, 0, 11_000),
+            position(2, 6, 1, 0, 10_000, 10_000),
+        ];
+        let v3 = CloseFactor::for_market(AaveVersion::V3, &AaveQuirks::default());
+
+        let candidate = best_liquidation(user, &positions, Wad::from_bps(9_000), v3).unwrap().unwrap();
+        assert_eq!(candidate.params.collateral_token, Address::from_low_u64_be(1));
+
+        // Only 1 token of the 10% bonus collateral: the whole balance is seized and less debt is repaid
+        let capped = best_liquidation(user, &positions[1..], Wad::from_bps(9_000), v3).unwrap().unwrap();
+        assert_eq!(capped.seized_collateral, U256::exp10(8));
+        assert_eq!(capped.params.debt_to_cover, mul_div(U256::from(1_000u64) * U256::exp10(6), U256::from(BPS), U256::from(11_000u64)).unwrap());
+    }
+
+    #[test]
+    fn test_health_factor_truncates_values_to_base_currency_units() {
+        // 1 wei of collateral is worth nothing once truncated to 8-decimal base units
+        let mut dust = position(1, 18, 2_000, 0, 0, 10_500);
+        dust.collateral = U256::one();
+        dust.collateral_enabled = true;
+        let positions = [position(1, 18, 2_000, 1, 0, 10_500), dust, position(2, 6, 1, 0, 1_000, 10_000)];
+
+        // 2000 * 0.8 / 1000, with no trace of the dust
+        assert_eq!(aave_health_factor(&positions, 8).unwrap(), Wad::from_bps(16_000));
+        assert_eq!(aave_health_factor(&positions[..2], 8).unwrap(), Wad::from_raw(U256::MAX));
+    }
+
+    #[test]
+    fn test_register_derives_index_approval_and_policy_entries() {
+        let pool = Address::from_low_u64_be(0xa1);
+        let markets: AaveMarketsConfig = serde_json::from_value(serde_json::json!({
+            "markets": [{
+                "name": "fork",
+                "version": "v2",
+                "pool": pool,
+                "data_provider": Address::from_low_u64_be(2),
+                "oracle": Address::from_low_u64_be(3),
+                "deployment_block": 100
+            }]
+        }))
+        .unwrap();
+        let mut index: BorrowerIndexConfig = serde_json::from_value(serde_json::json!({
+            "state_path": "index.json", "block_chunk": 2000, "save_every_blocks": 50, "markets": []
+        }))
+        .unwrap();
+        let mut approvals: ApprovalsConfig = serde_json::from_value(serde_json::json!({
+            "mode": "exact", "permit2": null, "scan_from_block": 0, "strategies": {}
+        }))
+        .unwrap();
+        let mut policy: PolicyConfig = serde_json::from_value(serde_json::json!({
+            "strategies": { "liquidation": { "targets": {}, "max_value_per_tx": "0x0", "max_value_per_day": "0x0" } }
+        }))
+        .unwrap();
+
+        markets.register(&mut index, &mut approvals, &mut policy).unwrap();
+        // Registering twice changes nothing
+        markets.register(&mut index, &mut approvals, &mut policy).unwrap();
+
+        assert_eq!(index.markets.len(), 1);
+        assert_eq!(index.markets[0].protocol, Protocol::AaveV2);
+        assert_eq!(index.markets[0].contracts, vec![pool]);
+        assert_eq!(index.markets[0].deployment_block, 100);
+        assert_eq!(approvals.strategies["liquidation"], vec![pool]);
+        assert_eq!(policy.strategies["liquidation"].targets[&pool], vec![LIQUIDATION_CALL_SELECTOR.to_string()]);
+    }
+}
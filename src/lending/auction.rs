@@ -0,0 +1,41 @@
+// src/lending/auction.rs
+use async_trait::async_trait;
+use ethers::types::{transaction::eip2718::TypedTransaction, Address, U256};
+use anyhow::Result;
+
+use crate::execution::SwapRoute;
+use crate::math::Wad;
+
+/// A protocol that liquidates by auctioning seized collateral at a falling price.
+#[async_trait]
+pub trait AuctionHouse: Send + Sync {
+    fn name(&self) -> &str;
+    /// Brings the set of active auctions up to date with `to_block`.
+    async fn sync(&self, to_block: u64) -> Result<()>;
+    /// Every active auction, priced as of `timestamp`.
+    async fn lots(&self, timestamp: u64) -> Result<Vec<AuctionLot>>;
+    /// Buys all of `lot` at up to its current price, selling the collateral through `route` to pay for it.
+    fn take_tx(&self, lot: &AuctionLot, route: &SwapRoute, min_profit: U256) -> TypedTransaction;
+    /// Restarts an auction that has run too long or fallen too far.
+    fn redo_tx(&self, lot: &AuctionLot) -> TypedTransaction;
+}
+
+/// One auction and what taking all of it costs right now.
+#[derive(Debug, Clone)]
+pub struct AuctionLot {
+    pub id: U256,
+    pub collateral: Address,
+    pub collateral_decimals: u8,
+    /// Collateral a take receives; less than the whole lot when the remaining debt is worth less.
+    pub collateral_amount: U256,
+    /// Token the auction is paid in.
+    pub payment_token: Address,
+    pub payment_decimals: u8,
+    pub payment_amount: U256,
+    /// Current price of one whole collateral token, in the payment token.
+    pub price: Wad,
+    /// The auction can no longer be taken until it is reset.
+    pub needs_redo: bool,
+    /// What resetting it pays the keeper, in the payment token.
+    pub redo_incentive: U256,
+}
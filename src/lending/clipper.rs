@@ -0,0 +1,478 @@
+// src/lending/clipper.rs
+use async_trait::async_trait;
+use ethers::{
+    abi::RawLog,
+    contract::EthLogDecode,
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{transaction::eip2718::TypedTransaction, Address, Filter, U256},
+};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use anyhow::{anyhow, Result};
+
+use super::auction::{AuctionHouse, AuctionLot};
+use crate::execution::SwapRoute;
+use crate::math::{mul_div, mul_div_up, MathError, Ray, Wad};
+use crate::signer::PolicyConfig;
+
+// Maker's DAI accounting units: wad 1e18, ray 1e27, rad 1e45
+const WAD_DECIMALS: u8 = 18;
+const LIQUIDATION_STRATEGY: &str = "liquidation";
+// take((address,address,uint256,uint256,uint256,address,address[],uint256))
+const TAKE_SELECTOR: &str = "1bbc0222";
+
+abigen!(
+    Clipper,
+    r#"[
+        function sales(uint256 id) external view returns (uint256 pos, uint256 tab, uint256 lot, address usr, uint96 tic, uint256 top)
+        function list() external view returns (uint256[])
+        function calc() external view returns (address)
+        function tail() external view returns (uint256)
+        function cusp() external view returns (uint256)
+        function tip() external view returns (uint192)
+        function chip() external view returns (uint64)
+        function chost() external view returns (uint256)
+        function spotter() external view returns (address)
+        function ilk() external view returns (bytes32)
+        function redo(uint256 id, address kpr)
+        event Kick(uint256 indexed id, uint256 top, uint256 tab, uint256 lot, address indexed usr, address indexed kpr, uint256 coin)
+        event Take(uint256 indexed id, uint256 max, uint256 price, uint256 owe, uint256 tab, uint256 lot, address indexed usr)
+        event Redo(uint256 indexed id, uint256 top, uint256 tab, uint256 lot, address indexed usr, address indexed kpr, uint256 coin)
+        event Yank(uint256 id)
+    ]"#
+);
+
+abigen!(
+    Abaci,
+    r#"[
+        function tau() external view returns (uint256)
+        function cut() external view returns (uint256)
+        function step() external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    Spotter,
+    r#"[
+        function par() external view returns (uint256)
+        function ilks(bytes32 ilk) external view returns (address pip, uint256 mat)
+    ]"#
+);
+
+abigen!(
+    Pip,
+    r#"[
+        function peek() external view returns (bytes32, bool)
+    ]"#
+);
+
+abigen!(
+    GemJoin,
+    r#"[
+        function gem() external view returns (address)
+        function dec() external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    ClipperTakerContract,
+    r#"[
+        struct Take { address clipper; address gemJoin; uint256 id; uint256 amt; uint256 max; address router; address[] path; uint256 minProfit; }
+        function take(Take order) external
+    ]"#
+);
+
+/// `config/clipper_auctions.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClipperConfig {
+    /// Deployed `contracts/ClipperTaker.sol`, which is also credited with redo incentives.
+    pub taker: Address,
+    pub dai: Address,
+    pub clippers: Vec<ClipperMarket>,
+}
+
+impl ClipperConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Lets the liquidation strategy call the taker, with each take capped in DAI.
+    pub fn register(&self, policy: &mut PolicyConfig) -> Result<()> {
+        policy.allow(LIQUIDATION_STRATEGY, self.taker, &[TAKE_SELECTOR])?;
+        policy.set_market_token(LIQUIDATION_STRATEGY, self.taker, self.dai)
+    }
+}
+
+/// One collateral type's auction contract.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClipperMarket {
+    pub name: String,
+    pub clipper: Address,
+    pub gem_join: Address,
+}
+
+/// The price curve (`calc`) an auction decays along, as implemented in Maker's `abaci.sol`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Abacus {
+    /// Reaches zero after `tau` seconds.
+    Linear { tau: U256 },
+    /// Multiplies by `cut` every second.
+    Exponential { cut: U256 },
+    /// Multiplies by `cut` every `step` seconds.
+    StairstepExponential { cut: U256, step: U256 },
+}
+
+impl Abacus {
+    async fn connect(calc: Address, provider: Arc<Provider<Ws>>) -> Result<Self> {
+        let abaci = Abaci::new(calc, provider);
+        if let Ok(step) = abaci.step().call().await {
+            return Ok(Abacus::StairstepExponential { cut: abaci.cut().call().await?, step });
+        }
+        if let Ok(tau) = abaci.tau().call().await {
+            return Ok(Abacus::Linear { tau });
+        }
+
+        Ok(Abacus::Exponential { cut: abaci.cut().call().await? })
+    }
+
+    /// Price `dur` seconds after an auction started at `top`, both in rays.
+    fn price(&self, top: U256, dur: U256) -> Result<U256, MathError> {
+        match *self {
+            Abacus::Linear { tau } => {
+                if dur >= tau {
+                    return Ok(U256::zero());
+                }
+                mul_div(top, (tau - dur) * Ray::ONE.raw() / tau, Ray::ONE.raw())
+            }
+            Abacus::Exponential { cut } => mul_div(top, rpow(cut, dur)?, Ray::ONE.raw()),
+            Abacus::StairstepExponential { cut, step } => {
+                if step.is_zero() {
+                    return Err(MathError::DivisionByZero);
+                }
+                mul_div(top, rpow(cut, dur / step)?, Ray::ONE.raw())
+            }
+        }
+    }
+}
+
+/// `x^n` in rays, rounding half up at every step exactly as `abaci.sol` does.
+fn rpow(mut x: U256, mut n: U256) -> Result<U256, MathError> {
+    let base = Ray::ONE.raw();
+    let half = base / 2;
+    let round = |a: U256, b: U256| {
+        a.checked_mul(b)
+            .and_then(|product| product.checked_add(half))
+            .map(|product| product / base)
+            .ok_or(MathError::Overflow)
+    };
+
+    if n.is_zero() {
+        return Ok(base);
+    }
+    if x.is_zero() {
+        return Ok(U256::zero());
+    }
+
+    let mut z = if n.bit(0) { x } else { base };
+    n >>= 1;
+    while !n.is_zero() {
+        x = round(x, x)?;
+        if n.bit(0) {
+            z = round(z, x)?;
+        }
+        n >>= 1;
+    }
+
+    Ok(z)
+}
+
+/// Collateral (wad) and DAI owed (rad) for taking all of a lot at `price`, capped at the remaining debt.
+fn take_amounts(lot: U256, tab: U256, price: U256) -> Result<(U256, U256), MathError> {
+    if price.is_zero() {
+        return Ok((U256::zero(), U256::zero()));
+    }
+
+    let owe = lot.checked_mul(price).ok_or(MathError::Overflow)?;
+    if owe > tab {
+        Ok((tab / price, tab))
+    } else {
+        Ok((lot, owe))
+    }
+}
+
+/// Keeper incentive (rad) `redo` pays on `sale`. The clipper pays nothing when either the debt
+/// left or the collateral left, valued at `feed_price` (ray), is below `chost`.
+fn redo_incentive(params: &ClipperParams, sale: &Sale, feed_price: U256) -> Result<U256, MathError> {
+    let lot_value = sale.lot.checked_mul(feed_price).unwrap_or(U256::MAX);
+    if sale.tab < params.chost || lot_value < params.chost {
+        return Ok(U256::zero());
+    }
+
+    Ok(params.tip + mul_div(sale.tab, params.chip, Wad::ONE.raw())?)
+}
+
+/// An active auction as `sales(id)` reports it.
+#[derive(Debug, Clone, Copy)]
+struct Sale {
+    /// DAI still to raise, in rads.
+    tab: U256,
+    /// Collateral left, in wads.
+    lot: U256,
+    /// When the auction (re)started.
+    tic: u64,
+    /// Starting price, in rays.
+    top: U256,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClipperParams {
+    /// Seconds before an auction must be reset.
+    tail: U256,
+    /// Fraction of `top` (ray) below which an auction must be reset.
+    cusp: U256,
+    /// Flat keeper incentive, in rads.
+    tip: U256,
+    /// Keeper incentive as a fraction of `tab`, in wads.
+    chip: U256,
+    /// Smallest `tab` that earns an incentive and that a partial take may leave, in rads.
+    chost: U256,
+}
+
+/// Auctions of one Maker collateral type, tracked from the clipper's events.
+pub struct ClipperAuctions {
+    name: String,
+    provider: Arc<Provider<Ws>>,
+    clipper: Clipper<Provider<Ws>>,
+    spotter: Spotter<Provider<Ws>>,
+    /// The collateral's OSM, which `redo` reads the price from.
+    pip: Pip<Provider<Ws>>,
+    taker: ClipperTakerContract<Provider<Ws>>,
+    gem_join: Address,
+    gem: Address,
+    gem_decimals: u8,
+    dai: Address,
+    abacus: Abacus,
+    params: ClipperParams,
+    /// What `redo` values collateral at, in rays, as of the last sync.
+    feed_price: RwLock<U256>,
+    sales: RwLock<HashMap<U256, Sale>>,
+    synced_to: RwLock<Option<u64>>,
+}
+
+impl ClipperAuctions {
+    /// Reads the clipper's price curve and incentives once; governance changes need a reconnect.
+    pub async fn connect(market: &ClipperMarket, taker: Address, dai: Address, provider: Arc<Provider<Ws>>) -> Result<Self> {
+        let clipper = Clipper::new(market.clipper, provider.clone());
+        let gem_join = GemJoin::new(market.gem_join, provider.clone());
+        let spotter = Spotter::new(clipper.spotter().call().await?, provider.clone());
+        let (pip, _mat) = spotter.ilks(clipper.ilk().call().await?).call().await?;
+
+        Ok(Self {
+            name: market.name.clone(),
+            abacus: Abacus::connect(clipper.calc().call().await?, provider.clone()).await?,
+            params: ClipperParams {
+                tail: clipper.tail().call().await?,
+                cusp: clipper.cusp().call().await?,
+                tip: clipper.tip().call().await?,
+                chip: U256::from(clipper.chip().call().await?),
+                chost: clipper.chost().call().await?,
+            },
+            taker: ClipperTakerContract::new(taker, provider.clone()),
+            gem: gem_join.gem().call().await?,
+            gem_decimals: gem_join.dec().call().await?.as_u32() as u8,
+            gem_join: market.gem_join,
+            dai,
+            pip: Pip::new(pip, provider.clone()),
+            spotter,
+            clipper,
+            provider,
+            feed_price: RwLock::new(U256::zero()),
+            sales: RwLock::new(HashMap::new()),
+            synced_to: RwLock::new(None),
+        })
+    }
+
+    async fn refresh(&self, id: U256) -> Result<()> {
+        let (_pos, tab, lot, usr, tic, top) = self.clipper.sales(id).call().await?;
+
+        let mut sales = self.sales.write().await;
+        if usr.is_zero() {
+            sales.remove(&id);
+        } else {
+            sales.insert(id, Sale { tab, lot, tic: tic as u64, top });
+        }
+        Ok(())
+    }
+
+    /// The clipper's `getFeedPrice`: the OSM's current value over the target price `par`, in rays.
+    async fn read_feed_price(&self) -> Result<U256> {
+        // The OSM only answers whitelisted readers, so ask as the clipper
+        let (value, has) = self.pip.peek().from(self.clipper.address()).call().await?;
+        if !has {
+            return Err(anyhow!("{} price feed has no valid value", self.name));
+        }
+        let par = self.spotter.par().call().await?;
+
+        Ok(mul_div(U256::from_big_endian(&value), U256::exp10(9) * Ray::ONE.raw(), par)?)
+    }
+
+    fn lot(&self, id: U256, sale: &Sale, feed_price: U256, timestamp: u64) -> Result<AuctionLot> {
+        let elapsed = U256::from(timestamp.saturating_sub(sale.tic));
+        let price = self.abacus.price(sale.top, elapsed)?;
+        let needs_redo = elapsed > self.params.tail
+            || sale.top.is_zero()
+            || mul_div(price, Ray::ONE.raw(), sale.top)? < self.params.cusp;
+
+        let (slice, owe) = take_amounts(sale.lot, sale.tab, price)?;
+        let redo_incentive = redo_incentive(&self.params, sale, feed_price)? / Ray::ONE.raw();
+
+        Ok(AuctionLot {
+            id,
+            collateral: self.gem,
+            collateral_decimals: self.gem_decimals,
+            collateral_amount: slice / U256::exp10((WAD_DECIMALS - self.gem_decimals) as usize),
+            payment_token: self.dai,
+            payment_decimals: WAD_DECIMALS,
+            payment_amount: mul_div_up(owe, U256::one(), Ray::ONE.raw())?,
+            price: Ray::from_raw(price).to_wad(),
+            needs_redo,
+            redo_incentive,
+        })
+    }
+}
+
+#[async_trait]
+impl AuctionHouse for ClipperAuctions {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Starts from the clipper's active list, then re-reads only auctions named in new events.
+    async fn sync(&self, to_block: u64) -> Result<()> {
+        let synced_to = *self.synced_to.read().await;
+        let ids: BTreeSet<U256> = match synced_to {
+            None => {
+                let ids = self.clipper.list().call().await?;
+                info!("{} has {} active auctions", self.name, ids.len());
+                ids.into_iter().collect()
+            }
+            Some(synced_to) if synced_to >= to_block => return Ok(()),
+            Some(synced_to) => {
+                let filter = Filter::new()
+                    .address(self.clipper.address())
+                    .from_block(synced_to + 1)
+                    .to_block(to_block);
+                self.provider
+                    .get_logs(&filter)
+                    .await?
+                    .into_iter()
+                    .filter_map(|log| match ClipperEvents::decode_log(&RawLog::from(log)).ok()? {
+                        ClipperEvents::KickFilter(event) => Some(event.id),
+                        ClipperEvents::TakeFilter(event) => Some(event.id),
+                        ClipperEvents::RedoFilter(event) => Some(event.id),
+                        ClipperEvents::YankFilter(event) => Some(event.id),
+                    })
+                    .collect()
+            }
+        };
+
+        for id in ids {
+            self.refresh(id).await?;
+        }
+        *self.feed_price.write().await = self.read_feed_price().await?;
+        *self.synced_to.write().await = Some(to_block);
+
+        Ok(())
+    }
+
+    async fn lots(&self, timestamp: u64) -> Result<Vec<AuctionLot>> {
+        let feed_price = *self.feed_price.read().await;
+        let sales = self.sales.read().await;
+
+        let mut lots = Vec::with_capacity(sales.len());
+        for (id, sale) in sales.iter() {
+            match self.lot(*id, sale, feed_price, timestamp) {
+                Ok(lot) => lots.push(lot),
+                Err(e) => warn!("Skipping {} auction {}: {}", self.name, id, e),
+            }
+        }
+
+        Ok(lots)
+    }
+
+    /// Takes the whole lot (`amt` is capped by the clipper) at no more than the quoted price,
+    /// rounded up to the ray so truncation cannot make it too low.
+    fn take_tx(&self, lot: &AuctionLot, route: &SwapRoute, min_profit: U256) -> TypedTransaction {
+        let wad_to_ray = U256::exp10((Ray::DECIMALS - Wad::DECIMALS) as usize);
+
+        self.taker
+            .take(Take {
+                clipper: self.clipper.address(),
+                gem_join: self.gem_join,
+                id: lot.id,
+                amt: U256::MAX,
+                max: (lot.price.raw() + 1) * wad_to_ray,
+                router: route.router,
+                path: route.path.clone(),
+                min_profit,
+            })
+            .tx
+    }
+
+    fn redo_tx(&self, lot: &AuctionLot) -> TypedTransaction {
+        self.clipper.redo(lot.id, self.taker.address()).tx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stairstep_price_decays_per_step() {
+        // 1% cut every 90 seconds from a 2000 DAI top
+        let cut = Ray::ONE.raw() / 100 * 99;
+        let abacus = Abacus::StairstepExponential { cut, step: U256::from(90) };
+        let top = U256::from(2_000) * Ray::ONE.raw();
+
+        assert_eq!(abacus.price(top, U256::from(89)).unwrap(), top);
+        assert_eq!(abacus.price(top, U256::from(90)).unwrap(), U256::from(1_980) * Ray::ONE.raw());
+        assert_eq!(abacus.price(top, U256::from(180)).unwrap(), U256::from(19_602) * Ray::ONE.raw() / 10);
+        assert_eq!(Abacus::Linear { tau: U256::from(100) }.price(top, U256::from(75)).unwrap(), top / 4);
+    }
+
+    #[test]
+    fn test_take_is_capped_at_remaining_debt() {
+        let ray = Ray::ONE.raw();
+        let wad = Wad::ONE.raw();
+        // 10 ETH left at 1500 DAI, but only 6000 DAI still owed
+        let (slice, owe) = take_amounts(U256::from(10) * wad, U256::from(6_000) * wad * ray, U256::from(1_500) * ray).unwrap();
+
+        assert_eq!(slice, U256::from(4) * wad);
+        assert_eq!(owe, U256::from(6_000) * wad * ray);
+    }
+
+    #[test]
+    fn test_redo_incentive_needs_debt_and_collateral_above_chost() {
+        let ray = Ray::ONE.raw();
+        let wad = Wad::ONE.raw();
+        let rad = wad * ray;
+        let params = ClipperParams {
+            tail: U256::zero(),
+            cusp: U256::zero(),
+            tip: U256::from(300) * rad,
+            chip: wad / 1_000,
+            chost: U256::from(5_000) * rad,
+        };
+        // 20000 DAI owed on 2 ETH
+        let sale = Sale { tab: U256::from(20_000) * rad, lot: U256::from(2) * wad, tic: 0, top: U256::zero() };
+
+        assert_eq!(redo_incentive(&params, &sale, U256::from(3_000) * ray).unwrap(), U256::from(320) * rad);
+        // Only 4000 DAI of collateral left at 2000
+        assert_eq!(redo_incentive(&params, &sale, U256::from(2_000) * ray).unwrap(), U256::zero());
+    }
+}
@@ -0,0 +1,330 @@
+// src/lending/comet.rs
+use async_trait::async_trait;
+use ethers::{
+    abi::AbiEncode,
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+
+use super::lending_pool::{account_data, CollateralSale, LendingPool, LiquidationCandidate, LiquidationParams, ReservePosition, UserAccountData};
+use crate::math::{mul_div, unit_decimals, Wad};
+
+const BPS: u64 = 10_000;
+// Comet factors are 1e18-scaled, prices 1e8-scaled
+const FACTOR_PER_BPS: u64 = 100_000_000_000_000;
+const PRICE_DECIMALS: u8 = 8;
+
+abigen!(
+    Comet,
+    r#"[
+        function baseToken() external view returns (address)
+        function baseTokenPriceFeed() external view returns (address)
+        function decimals() external view returns (uint8)
+        function numAssets() external view returns (uint8)
+        function getAssetInfo(uint8 i) external view returns ((uint8,address,address,uint64,uint64,uint64,uint64,uint128))
+        function userCollateral(address account, address asset) external view returns (uint128 balance, uint128 reserved)
+        function borrowBalanceOf(address account) external view returns (uint256)
+        function getPrice(address priceFeed) external view returns (uint256)
+        function isLiquidatable(address account) external view returns (bool)
+        function storeFrontPriceFactor() external view returns (uint256)
+        function getReserves() external view returns (int256)
+        function targetReserves() external view returns (uint256)
+        function getCollateralReserves(address asset) external view returns (uint256)
+        function quoteCollateral(address asset, uint256 baseAmount) external view returns (uint256)
+        function absorb(address absorber, address[] accounts)
+        function buyCollateral(address asset, uint256 minAmount, uint256 baseAmount, address recipient)
+    ]"#
+);
+
+#[derive(Debug, Clone, Copy)]
+struct CometAsset {
+    asset: Address,
+    price_feed: Address,
+    decimals: u8,
+    liquidate_collateral_factor: u64,
+    liquidation_factor: u64,
+}
+
+/// One Comet market in `config/liquidation_config.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CometMarketConfig {
+    /// Matches the market's name in the borrower index.
+    pub name: String,
+    pub comet: Address,
+}
+
+/// A Compound V3 market: accounts are absorbed into the protocol, which then sells their
+/// collateral from its reserves at a discount.
+pub struct CometLendingPool {
+    name: String,
+    comet: Comet<Provider<Ws>>,
+    /// Account credited with absorb points.
+    absorber: Address,
+    base_token: Address,
+    base_price_feed: Address,
+    base_decimals: u8,
+    assets: Vec<CometAsset>,
+}
+
+impl CometLendingPool {
+    pub async fn connect(name: impl Into<String>, comet: Address, absorber: Address, provider: Arc<Provider<Ws>>) -> Result<Self> {
+        let comet = Comet::new(comet, provider);
+
+        let mut assets = Vec::new();
+        for i in 0..comet.num_assets().call().await? {
+            let (_offset, asset, price_feed, scale, _borrow_cf, liquidate_cf, liquidation_factor, _supply_cap) =
+                comet.get_asset_info(i).call().await?;
+            assets.push(CometAsset {
+                asset,
+                price_feed,
+                decimals: unit_decimals(U256::from(scale))?,
+                liquidate_collateral_factor: liquidate_cf / FACTOR_PER_BPS,
+                liquidation_factor: liquidation_factor / FACTOR_PER_BPS,
+            });
+        }
+
+        Ok(Self {
+            name: name.into(),
+            base_token: comet.base_token().call().await?,
+            base_price_feed: comet.base_token_price_feed().call().await?,
+            base_decimals: comet.decimals().call().await?,
+            comet,
+            absorber,
+            assets,
+        })
+    }
+
+    async fn price(&self, price_feed: Address) -> Result<Wad> {
+        Ok(Wad::from_scaled(self.comet.get_price(price_feed).call().await?, PRICE_DECIMALS)?)
+    }
+
+    /// Whether the protocol is selling collateral at all; it stops once reserves reach the target.
+    async fn is_selling(&self) -> Result<bool> {
+        let reserves = self.comet.get_reserves().call().await?;
+        let target = self.comet.target_reserves().call().await?;
+
+        Ok(reserves.is_negative() || reserves.into_raw() < target)
+    }
+}
+
+#[async_trait]
+impl LendingPool for CometLendingPool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn get_user_account_data(&self, user: Address) -> Result<UserAccountData> {
+        account_data(&self.get_user_positions(user).await?)
+    }
+
+    async fn get_user_positions(&self, user: Address) -> Result<Vec<ReservePosition>> {
+        let store_front_price_factor = (self.comet.store_front_price_factor().call().await? / FACTOR_PER_BPS).as_u64();
+
+        let mut positions = vec![ReservePosition {
+            asset: self.base_token,
+            decimals: self.base_decimals,
+            price: self.price(self.base_price_feed).await?,
+            price_source: self.base_token,
+            collateral: U256::zero(),
+            debt: self.comet.borrow_balance_of(user).call().await?,
+            collateral_enabled: false,
+            liquidation_threshold_bps: 0,
+            liquidation_bonus_bps: BPS,
+            protocol_fee_bps: 0,
+        }];
+
+        for asset in &self.assets {
+            let (balance, _) = self.comet.user_collateral(user, asset.asset).call().await?;
+            if balance == 0 {
+                continue;
+            }
+
+            positions.push(ReservePosition {
+                asset: asset.asset,
+                decimals: asset.decimals,
+                price: self.price(asset.price_feed).await?,
+                price_source: asset.asset,
+                collateral: U256::from(balance),
+                debt: U256::zero(),
+                collateral_enabled: true,
+                liquidation_threshold_bps: asset.liquidate_collateral_factor,
+                liquidation_bonus_bps: BPS + store_front_discount_bps(store_front_price_factor, asset.liquidation_factor),
+                protocol_fee_bps: 0,
+            });
+        }
+
+        Ok(positions)
+    }
+
+    async fn asset_prices(&self, assets: &[Address]) -> Result<Vec<Wad>> {
+        let mut prices = Vec::new();
+        for &asset in assets {
+            let price_feed = if asset == self.base_token {
+                self.base_price_feed
+            } else {
+                self.assets
+                    .iter()
+                    .find(|info| info.asset == asset)
+                    .map(|info| info.price_feed)
+                    .ok_or_else(|| anyhow!("{:?} is not a {} asset", asset, self.name))?
+            };
+            prices.push(self.price(price_feed).await?);
+        }
+
+        Ok(prices)
+    }
+
+    /// Absorbing hands the account's collateral to the protocol, which sells it from its reserves
+    /// at the store-front discount while they are below target. The absorber is paid nothing
+    /// directly, so the candidate is valued at the discount it unlocks, earned by buying the
+    /// collateral back through `collateral_for_sale`.
+    async fn find_liquidation(&self, user: Address) -> Result<Option<LiquidationCandidate>> {
+        if !self.comet.is_liquidatable(user).call().await? {
+            return Ok(None);
+        }
+
+        let positions = self.get_user_positions(user).await?;
+        absorb_candidate(user, &positions, self.is_selling().await?)
+    }
+
+    fn liquidation_tx(&self, params: &LiquidationParams) -> TypedTransaction {
+        absorb_tx(self.comet.address(), self.absorber, params.user)
+    }
+
+    async fn collateral_for_sale(&self) -> Result<Vec<CollateralSale>> {
+        if !self.is_selling().await? {
+            return Ok(Vec::new());
+        }
+
+        let base_unit = U256::exp10(self.base_decimals as usize);
+        let mut sales = Vec::new();
+        for asset in &self.assets {
+            let available = self.comet.get_collateral_reserves(asset.asset).call().await?;
+            if available.is_zero() {
+                continue;
+            }
+
+            // quoteCollateral is linear in the base amount, so price one base unit and scale to the whole reserve
+            let per_base_unit = self.comet.quote_collateral(asset.asset, base_unit).call().await?;
+            if per_base_unit.is_zero() {
+                continue;
+            }
+            let payment_amount = mul_div(available, base_unit, per_base_unit)?;
+            let collateral_amount = self.comet.quote_collateral(asset.asset, payment_amount).call().await?.min(available);
+
+            sales.push(CollateralSale {
+                asset: asset.asset,
+                decimals: asset.decimals,
+                collateral_amount,
+                payment_token: self.base_token,
+                payment_decimals: self.base_decimals,
+                payment_amount,
+            });
+        }
+
+        Ok(sales)
+    }
+
+    fn buy_collateral_tx(&self, sale: &CollateralSale, recipient: Address) -> Option<TypedTransaction> {
+        Some(
+            self.comet
+                .buy_collateral(sale.asset, sale.collateral_amount, sale.payment_amount, recipient)
+                .tx,
+        )
+    }
+}
+
+/// The absorb of `user`, whose first position is the base debt, valued at the store-front discount
+/// on its largest collateral. Worth nothing while the protocol is not `selling` its reserves.
+fn absorb_candidate(user: Address, positions: &[ReservePosition], selling: bool) -> Result<Option<LiquidationCandidate>> {
+    let health_factor = account_data(positions)?.health_factor;
+
+    let mut largest: Option<(Wad, &ReservePosition)> = None;
+    for position in positions.iter().filter(|position| position.collateral_enabled) {
+        let value = position.collateral_value()?;
+        if largest.is_none_or(|(largest_value, _)| value > largest_value) {
+            largest = Some((value, position));
+        }
+    }
+
+    let (_, collateral) = match largest {
+        Some(largest) => largest,
+        None => return Ok(None),
+    };
+    let discount_bps = if selling { collateral.liquidation_bonus_bps.saturating_sub(BPS) } else { 0 };
+
+    Ok(Some(LiquidationCandidate {
+        params: LiquidationParams {
+            user,
+            collateral_token: collateral.asset,
+            debt_token: positions[0].asset,
+            debt_to_cover: positions[0].debt,
+            receive_underlying: true,
+        },
+        health_factor,
+        collateral_underlying: collateral.asset,
+        collateral_decimals: collateral.decimals,
+        debt_decimals: positions[0].decimals,
+        // What the absorb moves into the reserves we can buy from, and the discount on it
+        seized_collateral: collateral.collateral,
+        gross_profit: mul_div(collateral.collateral, U256::from(discount_bps), U256::from(BPS))?,
+    }))
+}
+
+fn absorb_tx(comet: Address, absorber: Address, user: Address) -> TypedTransaction {
+    let call = AbsorbCall { absorber, accounts: vec![user] };
+    TransactionRequest::new().to(comet).data(call.encode()).into()
+}
+
+/// Comet sells absorbed collateral at `storeFrontPriceFactor * (1 - liquidationFactor)` below the oracle price.
+fn store_front_discount_bps(store_front_price_factor_bps: u64, liquidation_factor_bps: u64) -> u64 {
+    store_front_price_factor_bps * (BPS - liquidation_factor_bps.min(BPS)) / BPS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::AbiDecode;
+
+    #[test]
+    fn test_store_front_discount() {
+        // mainnet USDC market: 50% store-front factor, WETH liquidation factor 0.93
+        assert_eq!(store_front_discount_bps(5_000, 9_300), 350);
+        assert_eq!(store_front_discount_bps(5_000, 10_000), 0);
+    }
+
+    #[test]
+    fn test_liquidatable_account_is_absorbed_for_the_store_front_discount() {
+        let position = |asset: u64, collateral: u64, debt: u64, bonus_bps: u64| ReservePosition {
+            asset: Address::from_low_u64_be(asset),
+            decimals: 18,
+            price: Wad::ONE,
+            price_source: Address::from_low_u64_be(asset),
+            collateral: U256::from(collateral) * U256::exp10(18),
+            debt: U256::from(debt) * U256::exp10(18),
+            collateral_enabled: collateral > 0,
+            liquidation_threshold_bps: 8_000,
+            liquidation_bonus_bps: bonus_bps,
+            protocol_fee_bps: 0,
+        };
+        let user = Address::from_low_u64_be(9);
+        // 100 of debt against 110 of collateral at an 80% threshold, 3.5% store-front discount
+        let positions = [position(1, 0, 100, BPS), position(2, 110, 0, 10_350), position(3, 10, 0, 10_350)];
+
+        let candidate = absorb_candidate(user, &positions, true).unwrap().unwrap();
+        assert!(candidate.health_factor < Wad::ONE);
+        assert_eq!(candidate.params.collateral_token, Address::from_low_u64_be(2));
+        assert_eq!(candidate.gross_profit, U256::from(385) * U256::exp10(16));
+        assert!(absorb_candidate(user, &positions, false).unwrap().unwrap().gross_profit.is_zero());
+
+        let (comet, absorber) = (Address::from_low_u64_be(4), Address::from_low_u64_be(5));
+        let tx = absorb_tx(comet, absorber, candidate.params.user);
+        assert_eq!(tx.to_addr(), Some(&comet));
+        let absorb = AbsorbCall::decode(tx.data().unwrap()).unwrap();
+        assert_eq!((absorb.absorber, absorb.accounts), (absorber, vec![user]));
+    }
+}
@@ -0,0 +1,322 @@
+// src/lending/compound.rs
+use async_trait::async_trait;
+use ethers::{
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+use anyhow::{anyhow, Result};
+
+use super::lending_pool::{
+    account_data, FlashLiquidationLeg, LendingPool, LiquidationCandidate, LiquidationParams, LiquidationVenue, ReservePosition,
+    UserAccountData,
+};
+use crate::math::{mul_div, Wad};
+
+// Compound mantissas are 1e18-scaled; basis points are 1e4-scaled
+const MANTISSA_PER_BPS: u64 = 100_000_000_000_000;
+
+abigen!(Comptroller, "src/strategies/abi/compound_comptroller_abi.json");
+abigen!(CToken, "src/strategies/abi/ctoken_abi.json");
+
+abigen!(
+    CEther,
+    r#"[
+        function liquidateBorrow(address borrower, address cTokenCollateral) external payable
+    ]"#
+);
+
+abigen!(
+    CompoundPriceOracle,
+    r#"[
+        function getUnderlyingPrice(address cToken) external view returns (uint256)
+    ]"#
+);
+
+abigen!(
+    UnderlyingToken,
+    r#"[
+        function decimals() external view returns (uint8)
+    ]"#
+);
+
+#[derive(Debug, Clone, Copy)]
+struct Market {
+    /// `None` for cEther, which holds native ETH.
+    underlying: Option<Address>,
+    decimals: u8,
+}
+
+/// A position as Compound reports it, before converting cTokens to underlying.
+struct Snapshot {
+    position: ReservePosition,
+    c_token_balance: U256,
+    exchange_rate: U256,
+}
+
+/// One Compound V2 style deployment in `config/liquidation_config.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompoundMarketConfig {
+    /// Matches the market's name in the borrower index.
+    pub name: String,
+    pub comptroller: Address,
+}
+
+/// Compound V2 and its forks, liquidated through `liquidateBorrow` on the borrowed market.
+pub struct CompoundLendingPool {
+    name: String,
+    provider: Arc<Provider<Ws>>,
+    comptroller: Comptroller<Provider<Ws>>,
+    oracle: CompoundPriceOracle<Provider<Ws>>,
+    markets: HashMap<Address, Market>,
+    weth: Address,
+}
+
+impl CompoundLendingPool {
+    /// Loads every listed market of `comptroller`; seized cEther is accounted for as `weth`.
+    pub async fn connect(
+        name: impl Into<String>,
+        comptroller: Address,
+        weth: Address,
+        provider: Arc<Provider<Ws>>,
+    ) -> Result<Self> {
+        let comptroller = Comptroller::new(comptroller, provider.clone());
+        let oracle = CompoundPriceOracle::new(comptroller.oracle().call().await?, provider.clone());
+
+        let mut markets = HashMap::new();
+        for c_token in comptroller.get_all_markets().call().await? {
+            // cEther is the only market without `underlying()`
+            let market = match CToken::new(c_token, provider.clone()).underlying().call().await {
+                Ok(underlying) => Market {
+                    underlying: Some(underlying),
+                    decimals: UnderlyingToken::new(underlying, provider.clone()).decimals().call().await?,
+                },
+                Err(_) => Market { underlying: None, decimals: 18 },
+            };
+            markets.insert(c_token, market);
+        }
+
+        Ok(Self {
+            name: name.into(),
+            provider,
+            comptroller,
+            oracle,
+            markets,
+            weth,
+        })
+    }
+
+    fn market(&self, c_token: Address) -> Result<Market> {
+        self.markets
+            .get(&c_token)
+            .copied()
+            .ok_or_else(|| anyhow!("{:?} is not a {} market", c_token, self.name))
+    }
+
+    async fn snapshots(&self, user: Address) -> Result<Vec<Snapshot>> {
+        let incentive = self.comptroller.liquidation_incentive_mantissa().call().await?;
+
+        let mut snapshots = Vec::new();
+        for c_token in self.comptroller.get_assets_in(user).call().await? {
+            let market = self.market(c_token)?;
+            let (error, c_token_balance, borrow_balance, exchange_rate) =
+                CToken::new(c_token, self.provider.clone()).get_account_snapshot(user).call().await?;
+            if !error.is_zero() {
+                return Err(anyhow!("getAccountSnapshot({:?}) on {:?} failed with error {}", user, c_token, error));
+            }
+            let (_, collateral_factor, _) = self.comptroller.markets(c_token).call().await?;
+            let price = self.oracle.get_underlying_price(c_token).call().await?;
+
+            snapshots.push(Snapshot {
+                position: ReservePosition {
+                    asset: c_token,
+                    decimals: market.decimals,
+                    price: underlying_price_to_wad(price, market.decimals)?,
+                    price_source: c_token,
+                    collateral: mul_div(c_token_balance, exchange_rate, U256::exp10(18))?,
+                    debt: borrow_balance,
+                    collateral_enabled: !collateral_factor.is_zero(),
+                    liquidation_threshold_bps: (collateral_factor / MANTISSA_PER_BPS).as_u64(),
+                    liquidation_bonus_bps: (incentive / MANTISSA_PER_BPS).as_u64(),
+                    protocol_fee_bps: 0,
+                },
+                c_token_balance,
+                exchange_rate,
+            });
+        }
+
+        Ok(snapshots)
+    }
+
+    async fn seize_tokens(&self, c_token_borrowed: Address, c_token_collateral: Address, repay_amount: U256) -> Result<U256> {
+        let (error, seize_tokens) = self.comptroller
+            .liquidate_calculate_seize_tokens(c_token_borrowed, c_token_collateral, repay_amount)
+            .call()
+            .await?;
+        if !error.is_zero() {
+            return Err(anyhow!("liquidateCalculateSeizeTokens failed with error {}", error));
+        }
+
+        Ok(seize_tokens)
+    }
+}
+
+#[async_trait]
+impl LendingPool for CompoundLendingPool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn get_user_account_data(&self, user: Address) -> Result<UserAccountData> {
+        account_data(&self.get_user_positions(user).await?)
+    }
+
+    async fn get_user_positions(&self, user: Address) -> Result<Vec<ReservePosition>> {
+        Ok(self.snapshots(user).await?.into_iter().map(|snapshot| snapshot.position).collect())
+    }
+
+    async fn asset_prices(&self, assets: &[Address]) -> Result<Vec<Wad>> {
+        let mut prices = Vec::new();
+        for &c_token in assets {
+            let price = self.oracle.get_underlying_price(c_token).call().await?;
+            prices.push(underlying_price_to_wad(price, self.market(c_token)?.decimals)?);
+        }
+
+        Ok(prices)
+    }
+
+    async fn find_liquidation(&self, user: Address) -> Result<Option<LiquidationCandidate>> {
+        let (error, _liquidity, shortfall) = self.comptroller.get_account_liquidity(user).call().await?;
+        if !error.is_zero() {
+            return Err(anyhow!("getAccountLiquidity({:?}) failed with error {}", user, error));
+        }
+        if shortfall.is_zero() {
+            return Ok(None);
+        }
+
+        let snapshots = self.snapshots(user).await?;
+        let positions: Vec<ReservePosition> = snapshots.iter().map(|snapshot| snapshot.position.clone()).collect();
+        let health_factor = account_data(&positions)?.health_factor;
+        let close_factor = self.comptroller.close_factor_mantissa().call().await?;
+
+        let mut best: Option<(Wad, LiquidationCandidate)> = None;
+        for debt in positions.iter().filter(|position| !position.debt.is_zero() && !position.price.is_zero()) {
+            let max_repay = mul_div(debt.debt, close_factor, U256::exp10(18))?;
+
+            for snapshot in snapshots.iter().filter(|snapshot| {
+                snapshot.position.collateral_enabled && !snapshot.c_token_balance.is_zero() && !snapshot.position.price.is_zero()
+            }) {
+                let collateral = &snapshot.position;
+
+                // Repay less when the borrower does not hold enough cTokens to cover the full seizure
+                let mut repay = max_repay;
+                let mut seize = self.seize_tokens(debt.asset, collateral.asset, repay).await?;
+                if seize > snapshot.c_token_balance {
+                    repay = mul_div(repay, snapshot.c_token_balance, seize)?;
+                    seize = self.seize_tokens(debt.asset, collateral.asset, repay).await?;
+                }
+                if repay.is_zero() {
+                    continue;
+                }
+
+                // Older forks predate the protocol's share of seized collateral
+                let protocol_share = match CToken::new(collateral.asset, self.provider.clone())
+                    .protocol_seize_share_mantissa()
+                    .call()
+                    .await
+                {
+                    Ok(share) => share,
+                    Err(_) => U256::zero(),
+                };
+                let received_c_tokens = seize - mul_div(seize, protocol_share, U256::exp10(18))?;
+                let received = mul_div(received_c_tokens, snapshot.exchange_rate, U256::exp10(18))?;
+
+                let paid_for = mul_div(
+                    repay,
+                    debt.price.raw() * U256::exp10(collateral.decimals as usize),
+                    collateral.price.raw() * U256::exp10(debt.decimals as usize),
+                )?;
+                let gross_profit = received.saturating_sub(paid_for);
+                let value = Wad::from_scaled(gross_profit, collateral.decimals)?.checked_mul(collateral.price)?;
+
+                if best.as_ref().is_none_or(|(best_value, _)| value > *best_value) {
+                    best = Some((value, LiquidationCandidate {
+                        params: LiquidationParams {
+                            user,
+                            collateral_token: collateral.asset,
+                            debt_token: debt.asset,
+                            debt_to_cover: repay,
+                            // Compound always pays the liquidator in cTokens
+                            receive_underlying: false,
+                        },
+                        health_factor,
+                        collateral_underlying: self.market(collateral.asset)?.underlying.unwrap_or(self.weth),
+                        collateral_decimals: collateral.decimals,
+                        debt_decimals: debt.decimals,
+                        seized_collateral: received,
+                        gross_profit,
+                    }));
+                }
+            }
+        }
+
+        if best.is_none() {
+            warn!("{:?} has a shortfall on {} but no liquidatable pair", user, self.name);
+        }
+        Ok(best.map(|(_, candidate)| candidate))
+    }
+
+    fn liquidation_tx(&self, params: &LiquidationParams) -> TypedTransaction {
+        let is_ether = self.markets.get(&params.debt_token).is_some_and(|market| market.underlying.is_none());
+
+        if is_ether {
+            CEther::new(params.debt_token, self.provider.clone())
+                .liquidate_borrow(params.user, params.collateral_token)
+                .value(params.debt_to_cover)
+                .tx
+        } else {
+            CToken::new(params.debt_token, self.provider.clone())
+                .liquidate_borrow(params.user, params.debt_to_cover, params.collateral_token)
+                .tx
+        }
+    }
+
+    /// cEther is repaid and redeemed in native ETH; the executor moves between it and WETH.
+    fn flash_liquidation_leg(&self, params: &LiquidationParams) -> Option<FlashLiquidationLeg> {
+        let debt = self.markets.get(&params.debt_token)?;
+        let collateral = self.markets.get(&params.collateral_token)?;
+
+        Some(FlashLiquidationLeg {
+            venue: LiquidationVenue::CompoundV2,
+            market: params.debt_token,
+            debt_underlying: debt.underlying.unwrap_or(self.weth),
+            collateral_underlying: collateral.underlying.unwrap_or(self.weth),
+            debt_is_native: debt.underlying.is_none(),
+            collateral_is_native: collateral.underlying.is_none(),
+        })
+    }
+}
+
+/// Compound's oracle quotes USD per smallest underlying unit scaled by `1e36 / 10^decimals`.
+fn underlying_price_to_wad(price: U256, decimals: u8) -> Result<Wad> {
+    Ok(Wad::from_raw(mul_div(price, U256::exp10(decimals as usize), U256::exp10(18))?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_underlying_price_scales_by_decimals() {
+        // $1 USDC (6 decimals) and $2000 ETH as the oracle reports them
+        let usdc = underlying_price_to_wad(U256::exp10(30), 6).unwrap();
+        let eth = underlying_price_to_wad(U256::from(2_000u64) * U256::exp10(18), 18).unwrap();
+
+        assert_eq!(usdc, Wad::ONE);
+        assert_eq!(eth, Wad::from_integer(2_000));
+    }
+}
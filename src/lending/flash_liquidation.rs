@@ -0,0 +1,238 @@
+// src/lending/flash_liquidation.rs
+use ethers::{
+    prelude::*,
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+};
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+
+use super::lending_pool::{FlashLiquidationLeg, LiquidationParams, LiquidationVenue};
+use crate::execution::actions::BALANCE;
+use crate::execution::swap::encode_swap_tokens_for_exact_tokens;
+use crate::execution::{Action, FlashLoanQuote, MultiActionExecutor, RouteFinder, SlippageGuard};
+
+// Aave's `percentMul` works in basis points and rounds half up
+const PERCENTAGE_FACTOR: u64 = 10_000;
+
+abigen!(
+    AaveV3FlashPool,
+    r#"[
+        function FLASHLOAN_PREMIUM_TOTAL() external view returns (uint128)
+    ]"#
+);
+
+/// A liquidation funded by a flash loan of the debt asset, with the route the seized collateral
+/// is sold through to repay it.
+#[derive(Debug, Clone)]
+pub struct FlashLiquidationPlan {
+    pub leg: FlashLiquidationLeg,
+    pub params: LiquidationParams,
+    /// The loan of `debt_to_cover` funding picked; its fee is in debt units.
+    pub loan: FlashLoanQuote,
+    pub router: Address,
+    /// Empty when the collateral is the debt asset and nothing needs selling.
+    pub path: Vec<Address>,
+    pub seized_collateral: U256,
+    /// Collateral sold to repay the loan and its fee.
+    pub collateral_sold: U256,
+}
+
+impl FlashLiquidationPlan {
+    /// Seized collateral left over once the loan and its fee are repaid.
+    pub fn profit(&self) -> U256 {
+        self.seized_collateral.saturating_sub(self.collateral_sold)
+    }
+}
+
+/// Sizes liquidations funded by a flash loan and builds them as executor programs, so the debt
+/// asset never has to be held in our wallets.
+pub struct FlashLiquidator {
+    executor: Arc<MultiActionExecutor>,
+    routes: Arc<RouteFinder>,
+    guard: SlippageGuard,
+}
+
+impl FlashLiquidator {
+    pub fn new(executor: Arc<MultiActionExecutor>, routes: Arc<RouteFinder>, guard: SlippageGuard) -> Self {
+        Self { executor, routes, guard }
+    }
+
+    /// Prices the liquidation in `params` inside `loan`, a quote for its `debt_to_cover`, through
+    /// the cheapest route, or `None` when no route sells the seized collateral for enough to repay it.
+    pub async fn plan(
+        &self,
+        leg: FlashLiquidationLeg,
+        params: &LiquidationParams,
+        seized_collateral: U256,
+        loan: FlashLoanQuote,
+    ) -> Result<Option<FlashLiquidationPlan>> {
+        if loan.token != leg.debt_underlying || loan.amount != params.debt_to_cover {
+            return Err(anyhow!("Flash loan of {} {:?} does not cover the liquidation of {:?}", loan.amount, loan.token, params.user));
+        }
+        let owed = loan.amount + loan.fee;
+
+        let (router, path, collateral_sold) = if leg.collateral_underlying == leg.debt_underlying {
+            (Address::zero(), Vec::new(), owed)
+        } else {
+            match self.routes.best_exact_out(leg.collateral_underlying, leg.debt_underlying, owed).await {
+                Some(route) => (route.router, route.path, route.amount_in),
+                None => return Ok(None),
+            }
+        };
+
+        if collateral_sold >= seized_collateral {
+            return Ok(None);
+        }
+
+        Ok(Some(FlashLiquidationPlan {
+            leg,
+            params: params.clone(),
+            loan,
+            router,
+            path,
+            seized_collateral,
+            collateral_sold,
+        }))
+    }
+
+    /// The executor program: inside the loan it liquidates, redeems Compound collateral and buys
+    /// back exactly what the loan owes with the seized collateral. It reverts unless the
+    /// collateral left afterwards is at least what the slippage-bounded sale guarantees, then
+    /// pays everything left to `recipient`.
+    pub fn liquidation_tx(&self, plan: &FlashLiquidationPlan, recipient: Address, deadline: U256) -> Result<TypedTransaction> {
+        let (collateral, debt) = (plan.leg.collateral_underlying, plan.leg.debt_underlying);
+        let max_collateral_in = if plan.path.is_empty() {
+            plan.collateral_sold
+        } else {
+            self.guard.max_amount_in(collateral, debt, plan.collateral_sold)?.min(plan.seized_collateral)
+        };
+
+        let actions = liquidation_actions(plan, self.executor.address(), recipient, max_collateral_in, deadline)?;
+        self.executor.execute_tx(&actions)
+    }
+}
+
+/// The executor program for `plan`, selling at most `max_collateral_in` of the seizure to repay
+/// the loan and paying what is left to `recipient`.
+fn liquidation_actions(
+    plan: &FlashLiquidationPlan,
+    executor: Address,
+    recipient: Address,
+    max_collateral_in: U256,
+    deadline: U256,
+) -> Result<Vec<Action>> {
+    let (collateral, debt) = (plan.leg.collateral_underlying, plan.leg.debt_underlying);
+    let owed = plan.loan.amount + plan.loan.fee;
+
+    let mut program = vec![Action::Liquidate {
+        venue: plan.leg.venue,
+        market: plan.leg.market,
+        collateral: plan.params.collateral_token,
+        debt,
+        user: plan.params.user,
+        debt_to_cover: plan.params.debt_to_cover,
+        debt_is_native: plan.leg.debt_is_native,
+    }];
+    if plan.leg.venue == LiquidationVenue::CompoundV2 {
+        program.push(Action::Redeem { c_token: plan.params.collateral_token, amount: BALANCE });
+        if plan.leg.collateral_is_native {
+            program.push(Action::WrapNative);
+        }
+    }
+    if !plan.path.is_empty() {
+        let buy_back = encode_swap_tokens_for_exact_tokens(owed, max_collateral_in, plan.path.clone(), executor, deadline)?;
+        program.extend([
+            Action::Approve { token: collateral, spender: plan.router, amount: max_collateral_in },
+            Action::Call { target: plan.router, data: buy_back },
+            // The swap rarely spends the whole allowance
+            Action::Approve { token: collateral, spender: plan.router, amount: U256::zero() },
+        ]);
+    }
+
+    let mut actions = vec![
+        Action::flash_borrow(&plan.loan, program),
+        Action::CheckMinBalance { token: collateral, minimum: plan.seized_collateral.saturating_sub(max_collateral_in) },
+        Action::Transfer { token: collateral, to: recipient, amount: BALANCE },
+    ];
+    // Aave repays less than `debt_to_cover` when the position is smaller than the close factor allows
+    if collateral != debt {
+        actions.push(Action::Transfer { token: debt, to: recipient, amount: BALANCE });
+    }
+
+    Ok(actions)
+}
+
+/// Aave V3 flash-loan premium on `amount`.
+pub fn flash_premium(amount: U256, premium_bps: u128) -> Result<U256> {
+    amount
+        .checked_mul(U256::from(premium_bps))
+        .map(|scaled| (scaled + PERCENTAGE_FACTOR / 2) / PERCENTAGE_FACTOR)
+        .ok_or_else(|| anyhow!("Flash-loan premium on {} overflows", amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::FlashLoanSource;
+
+    #[test]
+    fn test_flash_premium_rounds_half_up() {
+        // 0.05% of 1,000,000 USDC, and of an amount whose premium lands exactly on half a unit
+        assert_eq!(flash_premium(U256::from(1_000_000_000_000u64), 5).unwrap(), U256::from(500_000_000u64));
+        assert_eq!(flash_premium(U256::from(1_000u64), 5).unwrap(), U256::one());
+        assert_eq!(flash_premium(U256::from(999u64), 5).unwrap(), U256::zero());
+    }
+
+    #[test]
+    fn test_compound_liquidation_runs_inside_any_quoted_loan() {
+        let addr = Address::from_low_u64_be;
+        let (weth, usdc, c_usdc, c_eth) = (addr(1), addr(2), addr(3), addr(4));
+        let loan = FlashLoanQuote {
+            source: FlashLoanSource::Balancer,
+            lender: addr(5),
+            token: usdc,
+            amount: U256::from(1_000),
+            fee: U256::zero(),
+        };
+        let plan = FlashLiquidationPlan {
+            leg: FlashLiquidationLeg {
+                venue: LiquidationVenue::CompoundV2,
+                market: c_usdc,
+                debt_underlying: usdc,
+                collateral_underlying: weth,
+                debt_is_native: false,
+                collateral_is_native: true,
+            },
+            params: LiquidationParams {
+                user: addr(6),
+                collateral_token: c_eth,
+                debt_token: c_usdc,
+                debt_to_cover: U256::from(1_000),
+                receive_underlying: false,
+            },
+            loan: loan.clone(),
+            router: addr(7),
+            path: vec![weth, usdc],
+            seized_collateral: U256::from(900),
+            collateral_sold: U256::from(800),
+        };
+
+        let actions = liquidation_actions(&plan, addr(8), addr(9), U256::from(850), U256::from(60)).unwrap();
+        let program = match &actions[0] {
+            Action::FlashBorrow { source, token, amount, program, .. } => {
+                assert_eq!((*source, *token, *amount), (FlashLoanSource::Balancer, usdc, loan.amount));
+                program
+            }
+            action => panic!("Expected a flash borrow, got {:?}", action),
+        };
+        assert!(matches!(program[0], Action::Liquidate { market, collateral, .. } if market == c_usdc && collateral == c_eth));
+        assert_eq!(program[1], Action::Redeem { c_token: c_eth, amount: BALANCE });
+        assert_eq!(program[2], Action::WrapNative);
+        assert_eq!(program[3], Action::Approve { token: weth, spender: addr(7), amount: U256::from(850) });
+        assert_eq!(program.last(), Some(&Action::repay(&loan)));
+        // Only the slippage-bounded surplus is guaranteed, and both tokens go to the wallet
+        assert_eq!(actions[1], Action::CheckMinBalance { token: weth, minimum: U256::from(50) });
+        assert_eq!(actions[2], Action::Transfer { token: weth, to: addr(9), amount: BALANCE });
+        assert_eq!(actions[3], Action::Transfer { token: usdc, to: addr(9), amount: BALANCE });
+    }
+}
@@ -0,0 +1,461 @@
+// src/lending/indexer.rs
+use ethers::{
+    abi::RawLog,
+    contract::EthLogDecode,
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{Address, Filter, Log, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+use anyhow::Result;
+
+abigen!(
+    AaveV3PoolLogs,
+    r#"[
+        event Supply(address indexed reserve, address user, address indexed onBehalfOf, uint256 amount, uint16 indexed referralCode)
+        event Withdraw(address indexed reserve, address indexed user, address indexed to, uint256 amount)
+        event Borrow(address indexed reserve, address user, address indexed onBehalfOf, uint256 amount, uint8 interestRateMode, uint256 borrowRate, uint16 indexed referralCode)
+        event Repay(address indexed reserve, address indexed user, address indexed repayer, uint256 amount, bool useATokens)
+        event LiquidationCall(address indexed collateralAsset, address indexed debtAsset, address indexed user, uint256 debtToCover, uint256 liquidatedCollateralAmount, address liquidator, bool receiveAToken)
+    ]"#
+);
+
+abigen!(
+    AaveV2PoolLogs,
+    r#"[
+        event Deposit(address indexed reserve, address user, address indexed onBehalfOf, uint256 amount, uint16 indexed referral)
+        event Withdraw(address indexed reserve, address indexed user, address indexed to, uint256 amount)
+        event Borrow(address indexed reserve, address user, address indexed onBehalfOf, uint256 amount, uint256 borrowRateMode, uint256 borrowRate, uint16 indexed referral)
+        event Repay(address indexed reserve, address indexed user, address indexed repayer, uint256 amount)
+        event LiquidationCall(address indexed collateralAsset, address indexed debtAsset, address indexed user, uint256 debtToCover, uint256 liquidatedCollateralAmount, address liquidator, bool receiveAToken)
+    ]"#
+);
+
+abigen!(
+    CTokenLogs,
+    r#"[
+        event Mint(address minter, uint256 mintAmount, uint256 mintTokens)
+        event Redeem(address redeemer, uint256 redeemAmount, uint256 redeemTokens)
+        event Borrow(address borrower, uint256 borrowAmount, uint256 accountBorrows, uint256 totalBorrows)
+        event RepayBorrow(address payer, address borrower, uint256 repayAmount, uint256 accountBorrows, uint256 totalBorrows)
+        event LiquidateBorrow(address liquidator, address borrower, uint256 repayAmount, address cTokenCollateral, uint256 seizeTokens)
+    ]"#
+);
+
+abigen!(
+    CometLogs,
+    r#"[
+        event Supply(address indexed from, address indexed dst, uint256 amount)
+        event Withdraw(address indexed src, address indexed to, uint256 amount)
+        event SupplyCollateral(address indexed from, address indexed dst, address indexed asset, uint256 amount)
+        event WithdrawCollateral(address indexed src, address indexed to, address indexed asset, uint256 amount)
+        event AbsorbDebt(address indexed absorber, address indexed borrower, uint256 basePaidOut, uint256 usdValue)
+        event AbsorbCollateral(address indexed absorber, address indexed borrower, address indexed asset, uint256 collateralAbsorbed, uint256 usdValue)
+    ]"#
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    AaveV2,
+    AaveV3,
+    CompoundV2,
+    CompoundV3,
+}
+
+/// One lending market and the contracts whose events describe it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexedMarket {
+    /// Matches the name the market's `LendingPool` backend was connected with.
+    pub name: String,
+    pub protocol: Protocol,
+    /// The Aave pool, every Compound V2 cToken, or the Comet proxy.
+    pub contracts: Vec<Address>,
+    pub deployment_block: u64,
+}
+
+/// `config/borrower_index.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BorrowerIndexConfig {
+    /// Where the index is persisted between runs.
+    pub state_path: String,
+    /// Blocks per `eth_getLogs` request while catching up.
+    pub block_chunk: u64,
+    /// Blocks between saves of the index. A restart replays at most this many blocks of logs.
+    pub save_every_blocks: u64,
+    pub markets: Vec<IndexedMarket>,
+}
+
+impl BorrowerIndexConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Indexes `market` too, unless a market of that name is configured here already.
+    pub fn add_market(&mut self, market: IndexedMarket) {
+        if !self.markets.iter().any(|existing| existing.name == market.name) {
+            self.markets.push(market);
+        }
+    }
+}
+
+/// Balances reconstructed from events. Interest is not accrued, so they drift from the
+/// protocol's own numbers and only say which accounts are worth checking.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetBalance {
+    pub collateral: U256,
+    pub debt: U256,
+}
+
+type Accounts = HashMap<Address, HashMap<Address, AssetBalance>>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexState {
+    /// Last fully indexed block per market.
+    synced_to: HashMap<String, u64>,
+    /// Market name → borrower → asset → balances.
+    accounts: HashMap<String, Accounts>,
+}
+
+/// Every account with an open position on the configured markets, kept current from logs.
+pub struct BorrowerIndex {
+    provider: Arc<Provider<Ws>>,
+    config: BorrowerIndexConfig,
+    state: RwLock<IndexState>,
+    /// Block the index was last saved at.
+    saved_at: RwLock<Option<u64>>,
+}
+
+impl BorrowerIndex {
+    /// Resumes from the persisted index, or starts from each market's deployment block.
+    pub async fn open(provider: Arc<Provider<Ws>>, config: BorrowerIndexConfig) -> Result<Self> {
+        let state = match tokio::fs::read_to_string(&config.state_path).await {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => IndexState::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            provider,
+            config,
+            state: RwLock::new(state),
+            saved_at: RwLock::new(None),
+        })
+    }
+
+    /// Applies every market's events up to and including `to_block`, persisting the index when
+    /// `save_every_blocks` have passed since it was last saved.
+    ///
+    /// Returns the accounts each market's events touched.
+    pub async fn sync(&self, to_block: u64) -> Result<HashMap<String, HashSet<Address>>> {
+        let mut touched: HashMap<String, HashSet<Address>> = HashMap::new();
+        let mut changed = false;
+
+        for market in &self.config.markets {
+            let mut from_block = match self.state.read().await.synced_to.get(&market.name) {
+                Some(synced_to) => synced_to + 1,
+                None => market.deployment_block,
+            };
+
+            while from_block <= to_block {
+                let chunk_end = to_block.min(from_block + self.config.block_chunk.max(1) - 1);
+                let filter = Filter::new()
+                    .address(market.contracts.clone())
+                    .from_block(from_block)
+                    .to_block(chunk_end);
+                let logs = self.provider.get_logs(&filter).await?;
+
+                let mut state = self.state.write().await;
+                let accounts = state.accounts.entry(market.name.clone()).or_default();
+                let market_touched = touched.entry(market.name.clone()).or_default();
+                for log in &logs {
+                    market_touched.extend(apply_log(accounts, market.protocol, log));
+                }
+                state.synced_to.insert(market.name.clone(), chunk_end);
+                drop(state);
+
+                if to_block - from_block > self.config.block_chunk {
+                    info!("Indexed {} up to block {} ({} logs)", market.name, chunk_end, logs.len());
+                }
+                changed = true;
+                from_block = chunk_end + 1;
+            }
+        }
+
+        let save_due = self
+            .saved_at
+            .read()
+            .await
+            .is_none_or(|saved_at| to_block >= saved_at + self.config.save_every_blocks);
+        if changed && save_due {
+            self.save().await?;
+            *self.saved_at.write().await = Some(to_block);
+        }
+        Ok(touched)
+    }
+
+    /// Accounts with outstanding debt on `market`.
+    pub async fn borrowers(&self, market: &str) -> Vec<Address> {
+        self.state
+            .read()
+            .await
+            .accounts
+            .get(market)
+            .map(|accounts| {
+                accounts
+                    .iter()
+                    .filter(|(_, balances)| balances.values().any(|balance| !balance.debt.is_zero()))
+                    .map(|(user, _)| *user)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The approximate balances of `user` on `market`, by asset.
+    pub async fn balances(&self, market: &str, user: Address) -> HashMap<Address, AssetBalance> {
+        self.state
+            .read()
+            .await
+            .accounts
+            .get(market)
+            .and_then(|accounts| accounts.get(&user))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Writes a temporary file and renames it over the index, so a crash mid-write leaves the
+    /// previous save intact.
+    async fn save(&self) -> Result<()> {
+        let contents = serde_json::to_vec(&*self.state.read().await)?;
+        let temp_path = format!("{}.tmp", self.config.state_path);
+        tokio::fs::write(&temp_path, contents).await?;
+        tokio::fs::rename(&temp_path, &self.config.state_path).await?;
+
+        Ok(())
+    }
+}
+
+/// Applies one log to the index and returns the account it touched, if it was one we track.
+fn apply_log(accounts: &mut Accounts, protocol: Protocol, log: &Log) -> Option<Address> {
+    let raw = RawLog::from(log.clone());
+
+    match protocol {
+        Protocol::AaveV2 => match AaveV2PoolLogsEvents::decode_log(&raw).ok()? {
+            AaveV2PoolLogsEvents::DepositFilter(event) => {
+                add_collateral(accounts, event.on_behalf_of, event.reserve, event.amount);
+                Some(event.on_behalf_of)
+            }
+            AaveV2PoolLogsEvents::WithdrawFilter(event) => {
+                remove_collateral(accounts, event.user, event.reserve, event.amount);
+                Some(event.user)
+            }
+            AaveV2PoolLogsEvents::BorrowFilter(event) => {
+                add_debt(accounts, event.on_behalf_of, event.reserve, event.amount);
+                Some(event.on_behalf_of)
+            }
+            AaveV2PoolLogsEvents::RepayFilter(event) => {
+                remove_debt(accounts, event.user, event.reserve, event.amount);
+                Some(event.user)
+            }
+            AaveV2PoolLogsEvents::LiquidationCallFilter(event) => {
+                remove_debt(accounts, event.user, event.debt_asset, event.debt_to_cover);
+                remove_collateral(accounts, event.user, event.collateral_asset, event.liquidated_collateral_amount);
+                Some(event.user)
+            }
+        },
+        Protocol::AaveV3 => match AaveV3PoolLogsEvents::decode_log(&raw).ok()? {
+            AaveV3PoolLogsEvents::SupplyFilter(event) => {
+                add_collateral(accounts, event.on_behalf_of, event.reserve, event.amount);
+                Some(event.on_behalf_of)
+            }
+            AaveV3PoolLogsEvents::WithdrawFilter(event) => {
+                remove_collateral(accounts, event.user, event.reserve, event.amount);
+                Some(event.user)
+            }
+            AaveV3PoolLogsEvents::BorrowFilter(event) => {
+                add_debt(accounts, event.on_behalf_of, event.reserve, event.amount);
+                Some(event.on_behalf_of)
+            }
+            AaveV3PoolLogsEvents::RepayFilter(event) => {
+                remove_debt(accounts, event.user, event.reserve, event.amount);
+                Some(event.user)
+            }
+            AaveV3PoolLogsEvents::LiquidationCallFilter(event) => {
+                remove_debt(accounts, event.user, event.debt_asset, event.debt_to_cover);
+                remove_collateral(accounts, event.user, event.collateral_asset, event.liquidated_collateral_amount);
+                Some(event.user)
+            }
+        },
+        // V2 collateral is tracked in cTokens, keyed by the market's cToken
+        Protocol::CompoundV2 => {
+            let c_token = log.address;
+            match CTokenLogsEvents::decode_log(&raw).ok()? {
+                CTokenLogsEvents::MintFilter(event) => {
+                    add_collateral(accounts, event.minter, c_token, event.mint_tokens);
+                    Some(event.minter)
+                }
+                CTokenLogsEvents::RedeemFilter(event) => {
+                    remove_collateral(accounts, event.redeemer, c_token, event.redeem_tokens);
+                    Some(event.redeemer)
+                }
+                CTokenLogsEvents::BorrowFilter(event) => {
+                    set_debt(accounts, event.borrower, c_token, event.account_borrows);
+                    Some(event.borrower)
+                }
+                CTokenLogsEvents::RepayBorrowFilter(event) => {
+                    set_debt(accounts, event.borrower, c_token, event.account_borrows);
+                    Some(event.borrower)
+                }
+                // The debt side arrives as a RepayBorrow from the same transaction
+                CTokenLogsEvents::LiquidateBorrowFilter(event) => {
+                    remove_collateral(accounts, event.borrower, event.c_token_collateral, event.seize_tokens);
+                    Some(event.borrower)
+                }
+            }
+        }
+        // The base asset is keyed by the Comet proxy; supply and borrow net against each other
+        Protocol::CompoundV3 => {
+            let base = log.address;
+            match CometLogsEvents::decode_log(&raw).ok()? {
+                CometLogsEvents::SupplyFilter(event) => {
+                    let repaid = remove_debt(accounts, event.dst, base, event.amount);
+                    add_collateral(accounts, event.dst, base, event.amount - repaid);
+                    Some(event.dst)
+                }
+                CometLogsEvents::WithdrawFilter(event) => {
+                    let withdrawn = remove_collateral(accounts, event.src, base, event.amount);
+                    add_debt(accounts, event.src, base, event.amount - withdrawn);
+                    Some(event.src)
+                }
+                CometLogsEvents::SupplyCollateralFilter(event) => {
+                    add_collateral(accounts, event.dst, event.asset, event.amount);
+                    Some(event.dst)
+                }
+                CometLogsEvents::WithdrawCollateralFilter(event) => {
+                    remove_collateral(accounts, event.src, event.asset, event.amount);
+                    Some(event.src)
+                }
+                CometLogsEvents::AbsorbDebtFilter(event) => {
+                    set_debt(accounts, event.borrower, base, U256::zero());
+                    Some(event.borrower)
+                }
+                CometLogsEvents::AbsorbCollateralFilter(event) => {
+                    remove_collateral(accounts, event.borrower, event.asset, event.collateral_absorbed);
+                    Some(event.borrower)
+                }
+            }
+        }
+    }
+}
+
+fn add_collateral(accounts: &mut Accounts, user: Address, asset: Address, amount: U256) {
+    let balance = accounts.entry(user).or_default().entry(asset).or_default();
+    balance.collateral = balance.collateral.saturating_add(amount);
+}
+
+fn add_debt(accounts: &mut Accounts, user: Address, asset: Address, amount: U256) {
+    let balance = accounts.entry(user).or_default().entry(asset).or_default();
+    balance.debt = balance.debt.saturating_add(amount);
+}
+
+fn set_debt(accounts: &mut Accounts, user: Address, asset: Address, amount: U256) {
+    accounts.entry(user).or_default().entry(asset).or_default().debt = amount;
+    prune(accounts, user);
+}
+
+/// Removes up to `amount` of collateral and returns how much was actually there.
+fn remove_collateral(accounts: &mut Accounts, user: Address, asset: Address, amount: U256) -> U256 {
+    let removed = match accounts.get_mut(&user).and_then(|balances| balances.get_mut(&asset)) {
+        Some(balance) => {
+            let removed = balance.collateral.min(amount);
+            balance.collateral -= removed;
+            removed
+        }
+        None => U256::zero(),
+    };
+    prune(accounts, user);
+    removed
+}
+
+/// Removes up to `amount` of debt and returns how much was actually owed.
+fn remove_debt(accounts: &mut Accounts, user: Address, asset: Address, amount: U256) -> U256 {
+    let removed = match accounts.get_mut(&user).and_then(|balances| balances.get_mut(&asset)) {
+        Some(balance) => {
+            let removed = balance.debt.min(amount);
+            balance.debt -= removed;
+            removed
+        }
+        None => U256::zero(),
+    };
+    prune(accounts, user);
+    removed
+}
+
+/// Drops empty balances, and the account once nothing is left.
+fn prune(accounts: &mut Accounts, user: Address) {
+    if let Some(balances) = accounts.get_mut(&user) {
+        balances.retain(|_, balance| !balance.collateral.is_zero() || !balance.debt.is_zero());
+        if balances.is_empty() {
+            accounts.remove(&user);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{self, Token};
+
+    fn c_token_log(c_token: Address, signature: H256, tokens: &[Token]) -> Log {
+        Log {
+            address: c_token,
+            topics: vec![signature],
+            data: abi::encode(tokens).into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compound_borrow_and_repay_open_and_close_position() {
+        let c_token = Address::from_low_u64_be(1);
+        let borrower = Address::from_low_u64_be(2);
+        let mut accounts = Accounts::new();
+
+        let borrow = c_token_log(
+            c_token,
+            c_token_logs::BorrowFilter::signature(),
+            &[Token::Address(borrower), Token::Uint(100.into()), Token::Uint(100.into()), Token::Uint(1_000.into())],
+        );
+        apply_log(&mut accounts, Protocol::CompoundV2, &borrow);
+        assert_eq!(accounts[&borrower][&c_token].debt, U256::from(100));
+
+        let repay = c_token_log(
+            c_token,
+            c_token_logs::RepayBorrowFilter::signature(),
+            &[
+                Token::Address(borrower),
+                Token::Address(borrower),
+                Token::Uint(100.into()),
+                Token::Uint(0.into()),
+                Token::Uint(900.into()),
+            ],
+        );
+        apply_log(&mut accounts, Protocol::CompoundV2, &repay);
+        assert!(accounts.is_empty());
+    }
+
+    #[test]
+    fn test_state_round_trips_through_json() {
+        let mut state = IndexState::default();
+        state.synced_to.insert("aave-v3".to_string(), 42);
+        add_debt(state.accounts.entry("aave-v3".to_string()).or_default(), Address::from_low_u64_be(2), Address::from_low_u64_be(3), 7.into());
+
+        let restored: IndexState = serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+        assert_eq!(restored.synced_to["aave-v3"], 42);
+        assert_eq!(restored.accounts["aave-v3"][&Address::from_low_u64_be(2)][&Address::from_low_u64_be(3)].debt, U256::from(7));
+    }
+}
@@ -0,0 +1,216 @@
+// src/lending/lending_pool.rs
+use async_trait::async_trait;
+use ethers::types::{transaction::eip2718::TypedTransaction, Address, U256};
+use anyhow::Result;
+
+use crate::math::{mul_div, Wad};
+
+const BPS: u64 = 10_000;
+/// Debt below this many units of a USD base currency never pays for the gas of liquidating it.
+pub const DEFAULT_DUST_DEBT_VALUE: u64 = 100;
+
+/// A lending protocol we can liquidate on.
+#[async_trait]
+pub trait LendingPool: Send + Sync {
+    fn name(&self) -> &str;
+    async fn get_user_account_data(&self, user: Address) -> Result<UserAccountData>;
+    /// Per-asset collateral and debt of `user`.
+    async fn get_user_positions(&self, user: Address) -> Result<Vec<ReservePosition>>;
+    /// Current prices of `assets` (each a `ReservePosition::price_source`), read from the protocol's own oracle.
+    async fn asset_prices(&self, assets: &[Address]) -> Result<Vec<Wad>>;
+    /// Health factor of an account with `positions`, rounded the way the protocol rounds it.
+    fn health_factor(&self, positions: &[ReservePosition]) -> Result<Wad> {
+        Ok(account_data(positions)?.health_factor)
+    }
+    /// Smallest debt worth liquidating, in the protocol's base currency.
+    fn dust_debt_value(&self) -> Wad {
+        Wad::from_integer(DEFAULT_DUST_DEBT_VALUE)
+    }
+    /// The most profitable liquidation of `user`, or `None` if the account is healthy.
+    async fn find_liquidation(&self, user: Address) -> Result<Option<LiquidationCandidate>>;
+    fn liquidation_tx(&self, params: &LiquidationParams) -> TypedTransaction;
+
+    /// Token an account holds for its `asset` collateral, e.g. Aave's aToken; positions of
+    /// protocols that report the share token itself are keyed by it already.
+    async fn collateral_share_token(&self, asset: Address) -> Result<Address> {
+        Ok(asset)
+    }
+
+    /// How the flash-liquidation executor performs `params`, for protocols it supports.
+    fn flash_liquidation_leg(&self, _params: &LiquidationParams) -> Option<FlashLiquidationLeg> {
+        None
+    }
+
+    /// Seized collateral the protocol is currently selling, for protocols that sell it back.
+    async fn collateral_for_sale(&self) -> Result<Vec<CollateralSale>> {
+        Ok(Vec::new())
+    }
+
+    fn buy_collateral_tx(&self, _sale: &CollateralSale, _recipient: Address) -> Option<TypedTransaction> {
+        None
+    }
+}
+
+/// Account totals in the protocol's base currency, with `liquidation_threshold` a weighted average in basis points.
+#[derive(Debug, Clone)]
+pub struct UserAccountData {
+    pub collateral_value: U256,
+    pub debt_value: U256,
+    pub health_factor: Wad,
+    pub liquidation_threshold: U256,
+}
+
+/// One asset of a borrower's account, with the risk parameters that apply to that borrower.
+#[derive(Debug, Clone)]
+pub struct ReservePosition {
+    pub asset: Address,
+    pub decimals: u8,
+    /// Price of one whole token in the protocol's base currency.
+    pub price: Wad,
+    /// What `asset_prices` is asked for to reprice this position; differs from `asset` where the
+    /// protocol prices it through another asset, e.g. an Aave e-mode category's price source.
+    pub price_source: Address,
+    pub collateral: U256,
+    pub debt: U256,
+    /// Whether the protocol counts (and lets liquidators seize) this asset as collateral.
+    pub collateral_enabled: bool,
+    pub liquidation_threshold_bps: u64,
+    /// Collateral paid per unit of debt repaid, e.g. `10500` for a 5% bonus.
+    pub liquidation_bonus_bps: u64,
+    /// Share of the bonus the protocol keeps.
+    pub protocol_fee_bps: u64,
+}
+
+impl ReservePosition {
+    pub fn collateral_value(&self) -> Result<Wad> {
+        Ok(Wad::from_scaled(self.collateral, self.decimals)?.checked_mul(self.price)?)
+    }
+
+    pub fn debt_value(&self) -> Result<Wad> {
+        Ok(Wad::from_scaled(self.debt, self.decimals)?.checked_mul(self.price)?)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LiquidationParams {
+    pub user: Address,
+    pub collateral_token: Address,
+    pub debt_token: Address,
+    pub debt_to_cover: U256,
+    pub receive_underlying: bool,
+}
+
+/// A sized liquidation and what it should pay.
+#[derive(Debug, Clone)]
+pub struct LiquidationCandidate {
+    pub params: LiquidationParams,
+    pub health_factor: Wad,
+    /// Token the seized collateral redeems to; differs from `collateral_token` when the protocol pays out in a wrapper such as a cToken.
+    pub collateral_underlying: Address,
+    pub collateral_decimals: u8,
+    pub debt_decimals: u8,
+    /// Collateral we receive, after any protocol fee.
+    pub seized_collateral: U256,
+    /// Seized collateral left over once the repaid debt is paid for, in collateral units.
+    pub gross_profit: U256,
+}
+
+/// Liquidation protocols the executor's `Liquidate` action can call, by the id it expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiquidationVenue {
+    /// Aave's `liquidationCall`, unchanged from V2 through V3 and their forks.
+    AaveV3 = 0,
+    CompoundV2 = 1,
+}
+
+/// The protocol side of a flash-funded liquidation, as the executor's `Liquidate` action performs it.
+#[derive(Debug, Clone)]
+pub struct FlashLiquidationLeg {
+    pub venue: LiquidationVenue,
+    /// Contract `liquidate` is called on: the Aave pool, or the borrowed cToken.
+    pub market: Address,
+    /// Token flash-borrowed to repay the debt.
+    pub debt_underlying: Address,
+    /// Token the seized collateral is sold from.
+    pub collateral_underlying: Address,
+    /// The debt is repaid in native ETH, unwrapped from the borrowed WETH.
+    pub debt_is_native: bool,
+    /// The collateral redeems to native ETH, wrapped before selling.
+    pub collateral_is_native: bool,
+}
+
+/// A lot of seized collateral offered by the protocol, quoted at its current discount.
+#[derive(Debug, Clone)]
+pub struct CollateralSale {
+    pub asset: Address,
+    pub decimals: u8,
+    pub collateral_amount: U256,
+    /// Token the protocol wants in return.
+    pub payment_token: Address,
+    pub payment_decimals: u8,
+    pub payment_amount: U256,
+}
+
+/// Account totals and health factor from per-asset positions, for protocols that do not report them.
+pub fn account_data(positions: &[ReservePosition]) -> Result<UserAccountData> {
+    let mut collateral_value = Wad::ZERO;
+    let mut weighted_collateral = Wad::ZERO;
+    let mut debt_value = Wad::ZERO;
+
+    for position in positions {
+        if position.collateral_enabled {
+            let value = position.collateral_value()?;
+            collateral_value = collateral_value.checked_add(value)?;
+            weighted_collateral = weighted_collateral.checked_add(
+                Wad::from_bps(position.liquidation_threshold_bps).checked_mul(value)?,
+            )?;
+        }
+        debt_value = debt_value.checked_add(position.debt_value()?)?;
+    }
+
+    let health_factor = if debt_value.is_zero() {
+        Wad::from_raw(U256::MAX)
+    } else {
+        weighted_collateral.checked_div(debt_value)?
+    };
+    let liquidation_threshold = if collateral_value.is_zero() {
+        U256::zero()
+    } else {
+        mul_div(weighted_collateral.raw(), U256::from(BPS), collateral_value.raw())?
+    };
+
+    Ok(UserAccountData {
+        collateral_value: collateral_value.raw(),
+        debt_value: debt_value.raw(),
+        health_factor,
+        liquidation_threshold,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_data_weights_collateral_by_threshold() {
+        let position = |collateral: u64, debt: u64, collateral_enabled: bool| ReservePosition {
+            asset: Address::zero(),
+            decimals: 18,
+            price: Wad::ONE,
+            price_source: Address::zero(),
+            collateral: U256::from(collateral) * U256::exp10(18),
+            debt: U256::from(debt) * U256::exp10(18),
+            collateral_enabled,
+            liquidation_threshold_bps: 8_000,
+            liquidation_bonus_bps: 10_800,
+            protocol_fee_bps: 0,
+        };
+
+        // 100 enabled at 80% against 100 of debt; supplied-but-disabled collateral does not count
+        let account = account_data(&[position(100, 0, true), position(50, 100, false)]).unwrap();
+        assert_eq!(account.health_factor, Wad::from_bps(8_000));
+        assert_eq!(account.liquidation_threshold, U256::from(8_000));
+
+        assert_eq!(account_data(&[position(1, 0, true)]).unwrap().health_factor, Wad::from_raw(U256::MAX));
+    }
+}
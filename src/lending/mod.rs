@@ -0,0 +1,27 @@
+// src/lending/mod.rs
+pub mod aave;
+pub mod auction;
+pub mod clipper;
+pub mod comet;
+pub mod compound;
+pub mod flash_liquidation;
+pub mod indexer;
+pub mod lending_pool;
+pub mod protection;
+pub mod watchlist;
+
+pub use aave::{AaveLendingPool, AaveMarketConfig, AaveMarketsConfig, AaveQuirks, AaveVersion};
+pub use auction::{AuctionHouse, AuctionLot};
+pub use clipper::{ClipperAuctions, ClipperConfig, ClipperMarket};
+pub use comet::{CometLendingPool, CometMarketConfig};
+pub use compound::{CompoundLendingPool, CompoundMarketConfig};
+pub use flash_liquidation::{FlashLiquidationPlan, FlashLiquidator};
+pub use indexer::{AssetBalance, BorrowerIndex, BorrowerIndexConfig, IndexedMarket, Protocol};
+pub use lending_pool::{
+    account_data, CollateralSale, FlashLiquidationLeg, LendingPool, LiquidationCandidate, LiquidationParams, LiquidationVenue,
+    ReservePosition, UserAccountData,
+};
+pub use protection::{
+    alert_level, DeleverageAction, DeleveragePlan, DeleveragePolicy, PositionUnwinder, ProtectedAccount, ProtectionConfig,
+};
+pub use watchlist::HealthMonitor;
@@ -0,0 +1,347 @@
+// src/lending/protection.rs
+use ethers::{
+    prelude::*,
+    providers::{Provider, Ws},
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+
+use super::flash_liquidation::{flash_premium, AaveV3FlashPool};
+use super::lending_pool::{FlashLiquidationLeg, LendingPool, LiquidationParams, ReservePosition};
+use crate::execution::{RouteFinder, SlippageGuard};
+use crate::math::Wad;
+use crate::signer::PolicyConfig;
+
+const BPS: u64 = 10_000;
+const PROTECTION_STRATEGY: &str = "self_protection";
+// unwind(Unwind), topUp(uint8,address,address,address,bool,uint256,address)
+const UNWINDER_SELECTORS: [&str; 2] = ["83ca93de", "c83c21ca"];
+
+abigen!(
+    PositionUnwinderContract,
+    r#"[
+        struct Unwind { uint8 venue; address market; address account; address collateralToken; address collateralUnderlying; address debtUnderlying; uint256 repayAmount; bool debtIsNative; bool collateralIsNative; address router; address[] path; uint256 maxCollateralIn; }
+        function pool() external view returns (address)
+        function unwind(Unwind order) external
+        function topUp(uint8 venue, address market, address account, address underlying, bool isNative, uint256 amount, address reserve) external
+    ]"#
+);
+
+/// `config/self_protection.json`: our own borrowing accounts and how to defend them.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProtectionConfig {
+    /// Deployed `contracts/PositionUnwinder.sol`.
+    pub unwinder: Address,
+    pub accounts: Vec<ProtectedAccount>,
+}
+
+impl ProtectionConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Lets self-protection call the unwinder; fails while it is not deployed.
+    pub fn register(&self, policy: &mut PolicyConfig) -> Result<()> {
+        policy.allow(PROTECTION_STRATEGY, self.unwinder, &UNWINDER_SELECTORS)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProtectedAccount {
+    /// Name of the lending pool the account borrows on.
+    pub market: String,
+    pub account: Address,
+    /// Health factors, in basis points, that raise an alert when the account falls below them.
+    pub alert_bps: Vec<u64>,
+    /// Unset to only alert.
+    #[serde(default)]
+    pub deleverage: Option<DeleveragePolicy>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeleveragePolicy {
+    /// Health factor, in basis points, below which the account is deleveraged.
+    pub floor_bps: u64,
+    /// Health factor, in basis points, the deleverage restores.
+    pub target_bps: u64,
+    pub action: DeleverageAction,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeleverageAction {
+    /// Repay the largest debt with a flash loan and sell the largest collateral to pay it back.
+    Repay,
+    /// Supply more `asset` (as the account's positions name it), pulled from `reserve`, which
+    /// approves the unwinder for it.
+    AddCollateral { asset: Address, reserve: Address },
+}
+
+/// A sized deleverage of one account.
+#[derive(Debug, Clone)]
+pub enum DeleveragePlan {
+    Unwind {
+        leg: FlashLiquidationLeg,
+        /// Token the account holds for its collateral, which the unwinder pulls.
+        collateral_share: Address,
+        repay_amount: U256,
+        premium: U256,
+        router: Address,
+        path: Vec<Address>,
+        /// Collateral withdrawn; what the sale does not use is returned to the account.
+        max_collateral_in: U256,
+    },
+    TopUp {
+        leg: FlashLiquidationLeg,
+        amount: U256,
+        reserve: Address,
+    },
+}
+
+/// Sizes and submits deleverages of our own accounts through the position unwinder.
+pub struct PositionUnwinder {
+    contract: PositionUnwinderContract<Provider<Ws>>,
+    flash_pool: AaveV3FlashPool<Provider<Ws>>,
+    routes: Arc<RouteFinder>,
+    guard: SlippageGuard,
+}
+
+impl PositionUnwinder {
+    pub async fn connect(config: &ProtectionConfig, routes: Arc<RouteFinder>, guard: SlippageGuard, provider: Arc<Provider<Ws>>) -> Result<Self> {
+        let contract = PositionUnwinderContract::new(config.unwinder, provider.clone());
+        let flash_pool = AaveV3FlashPool::new(contract.pool().call().await?, provider);
+
+        Ok(Self {
+            contract,
+            flash_pool,
+            routes,
+            guard,
+        })
+    }
+
+    /// What bringing `account` back to the policy's target takes, or `None` when the protocol is
+    /// not supported by the unwinder or no route repays the flash loan.
+    pub async fn plan(
+        &self,
+        pool: &dyn LendingPool,
+        account: Address,
+        positions: &[ReservePosition],
+        policy: &DeleveragePolicy,
+    ) -> Result<Option<DeleveragePlan>> {
+        let (weighted_collateral, debt_value) = weighted_totals(positions)?;
+        let target = Wad::from_bps(policy.target_bps);
+
+        match &policy.action {
+            DeleverageAction::Repay => {
+                let debt = largest(positions, |position| position.debt_value())?
+                    .ok_or_else(|| anyhow!("{:?} has no debt on {}", account, pool.name()))?;
+                let collateral = largest(positions.iter().filter(|position| position.collateral_enabled), |position| {
+                    position.collateral_value()
+                })?
+                .ok_or_else(|| anyhow!("{:?} has no collateral on {}", account, pool.name()))?;
+
+                // Each unit repaid sells that much collateral plus the flash premium, and up to the
+                // guard's slippage more when it has to be swapped
+                let premium_bps = self.flash_pool.flashloan_premium_total().call().await?;
+                let slippage_bps = if collateral.asset == debt.asset { 0 } else { self.guard.tolerance_bps(collateral.asset, debt.asset) };
+                let sold_per_repaid = Wad::from_bps(BPS + premium_bps as u64).checked_mul(Wad::from_bps(BPS + slippage_bps))?;
+
+                let value = repay_value(weighted_collateral, debt_value, target, collateral.liquidation_threshold_bps, sold_per_repaid)?;
+                let repay_amount = value.checked_div(debt.price)?.to_scaled(debt.decimals)?.min(debt.debt);
+                let params = LiquidationParams {
+                    user: account,
+                    collateral_token: collateral.asset,
+                    debt_token: debt.asset,
+                    debt_to_cover: repay_amount,
+                    receive_underlying: true,
+                };
+                let leg = match pool.flash_liquidation_leg(&params) {
+                    Some(leg) => leg,
+                    None => return Ok(None),
+                };
+
+                let premium = flash_premium(repay_amount, premium_bps)?;
+                let owed = repay_amount + premium;
+                let (router, path, max_collateral_in) = if leg.collateral_underlying == leg.debt_underlying {
+                    (Address::zero(), Vec::new(), owed)
+                } else {
+                    match self.routes.best_exact_out(leg.collateral_underlying, leg.debt_underlying, owed).await {
+                        Some(route) => {
+                            let max_in = self.guard.max_amount_in(leg.collateral_underlying, leg.debt_underlying, route.amount_in)?;
+                            (route.router, route.path, max_in)
+                        }
+                        None => return Ok(None),
+                    }
+                };
+                if max_collateral_in > collateral.collateral {
+                    return Ok(None);
+                }
+
+                Ok(Some(DeleveragePlan::Unwind {
+                    collateral_share: pool.collateral_share_token(collateral.asset).await?,
+                    leg,
+                    repay_amount,
+                    premium,
+                    router,
+                    path,
+                    max_collateral_in,
+                }))
+            }
+            DeleverageAction::AddCollateral { asset, reserve } => {
+                let position = positions
+                    .iter()
+                    .find(|position| position.asset == *asset && position.collateral_enabled)
+                    .ok_or_else(|| anyhow!("{:?} does not use {:?} as collateral on {}", account, asset, pool.name()))?;
+
+                let value = top_up_value(weighted_collateral, debt_value, target, position.liquidation_threshold_bps)?;
+                let amount = value.checked_div(position.price)?.to_scaled(position.decimals)?;
+                // The unwinder supplies to the same market it would repay a debt of `asset` on
+                let params = LiquidationParams {
+                    user: account,
+                    collateral_token: *asset,
+                    debt_token: *asset,
+                    debt_to_cover: amount,
+                    receive_underlying: true,
+                };
+
+                Ok(pool.flash_liquidation_leg(&params).map(|leg| DeleveragePlan::TopUp {
+                    leg,
+                    amount,
+                    reserve: *reserve,
+                }))
+            }
+        }
+    }
+
+    pub fn deleverage_tx(&self, account: Address, plan: &DeleveragePlan) -> TypedTransaction {
+        match plan {
+            DeleveragePlan::Unwind {
+                leg,
+                collateral_share,
+                repay_amount,
+                router,
+                path,
+                max_collateral_in,
+                ..
+            } => {
+                let order = Unwind {
+                    venue: leg.venue as u8,
+                    market: leg.market,
+                    account,
+                    collateral_token: *collateral_share,
+                    collateral_underlying: leg.collateral_underlying,
+                    debt_underlying: leg.debt_underlying,
+                    repay_amount: *repay_amount,
+                    debt_is_native: leg.debt_is_native,
+                    collateral_is_native: leg.collateral_is_native,
+                    router: *router,
+                    path: path.clone(),
+                    max_collateral_in: *max_collateral_in,
+                };
+                self.contract.unwind(order).tx
+            }
+            DeleveragePlan::TopUp { leg, amount, reserve } => {
+                self.contract
+                    .top_up(leg.venue as u8, leg.market, account, leg.debt_underlying, leg.debt_is_native, *amount, *reserve)
+                    .tx
+            }
+        }
+    }
+}
+
+/// How many of `alert_bps` the health factor is below; alerts fire as this rises.
+pub fn alert_level(health_factor: Wad, alert_bps: &[u64]) -> usize {
+    alert_bps.iter().filter(|bps| health_factor < Wad::from_bps(**bps)).count()
+}
+
+/// Threshold-weighted collateral value and debt value, whose ratio is the health factor.
+fn weighted_totals(positions: &[ReservePosition]) -> Result<(Wad, Wad)> {
+    let mut weighted_collateral = Wad::ZERO;
+    let mut debt_value = Wad::ZERO;
+    for position in positions {
+        if position.collateral_enabled {
+            weighted_collateral = weighted_collateral
+                .checked_add(Wad::from_bps(position.liquidation_threshold_bps).checked_mul(position.collateral_value()?)?)?;
+        }
+        debt_value = debt_value.checked_add(position.debt_value()?)?;
+    }
+    Ok((weighted_collateral, debt_value))
+}
+
+fn largest<'a>(
+    positions: impl IntoIterator<Item = &'a ReservePosition>,
+    value: impl Fn(&ReservePosition) -> Result<Wad>,
+) -> Result<Option<&'a ReservePosition>> {
+    let mut best: Option<(&ReservePosition, Wad)> = None;
+    for position in positions {
+        let position_value = value(position)?;
+        if !position_value.is_zero() && best.is_none_or(|(_, best_value)| position_value > best_value) {
+            best = Some((position, position_value));
+        }
+    }
+    Ok(best.map(|(position, _)| position))
+}
+
+/// Debt value to repay for the health factor `weighted / debt` to reach `target`, when every unit
+/// repaid sells `sold_per_repaid` units of collateral at `threshold_bps`:
+/// `(target·debt − weighted) / (target − threshold·sold_per_repaid)`.
+fn repay_value(weighted_collateral: Wad, debt_value: Wad, target: Wad, threshold_bps: u64, sold_per_repaid: Wad) -> Result<Wad> {
+    let shortfall = target.checked_mul(debt_value)?.saturating_sub(weighted_collateral);
+    let per_unit = target.saturating_sub(Wad::from_bps(threshold_bps).checked_mul(sold_per_repaid)?);
+    if per_unit.is_zero() {
+        return Err(anyhow!("Selling collateral cannot raise the health factor to {}", target));
+    }
+    Ok(shortfall.checked_div(per_unit)?.min(debt_value))
+}
+
+/// Collateral value at `threshold_bps` to add for the health factor to reach `target`.
+fn top_up_value(weighted_collateral: Wad, debt_value: Wad, target: Wad, threshold_bps: u64) -> Result<Wad> {
+    if threshold_bps == 0 {
+        return Err(anyhow!("Collateral with no liquidation threshold cannot raise the health factor"));
+    }
+    let shortfall = target.checked_mul(debt_value)?.saturating_sub(weighted_collateral);
+    Ok(shortfall.checked_div(Wad::from_bps(threshold_bps))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alert_level_counts_crossed_thresholds() {
+        let thresholds = [15_000, 12_500, 11_000];
+        assert_eq!(alert_level(Wad::from_bps(16_000), &thresholds), 0);
+        assert_eq!(alert_level(Wad::from_bps(12_000), &thresholds), 2);
+        assert_eq!(alert_level(Wad::from_bps(10_500), &thresholds), 3);
+    }
+
+    #[test]
+    fn test_sizes_repay_and_top_up_to_target() {
+        // 10 ETH at $2000 with an 80% threshold against 15,000 of debt: HF 1.0667
+        let (weighted, debt) = (Wad::from_integer(16_000), Wad::from_integer(15_000));
+        let target = Wad::from_bps(15_000);
+
+        // Repaying R and selling kR of collateral: (16000 - 0.8kR) / (15000 - R) = 1.5
+        let health_after = |repay: Wad, sold_per_repaid: Wad| {
+            weighted
+                .checked_sub(Wad::from_bps(8_000).checked_mul(repay.checked_mul(sold_per_repaid).unwrap()).unwrap())
+                .unwrap()
+                .checked_div(debt.checked_sub(repay).unwrap())
+                .unwrap()
+        };
+        // At par, then paying a 0.05% premium and up to 1% slippage on the sale
+        let costly = Wad::from_bps(10_005).checked_mul(Wad::from_bps(10_100)).unwrap();
+        for sold_per_repaid in [Wad::ONE, costly] {
+            let after = health_after(repay_value(weighted, debt, target, 8_000, sold_per_repaid).unwrap(), sold_per_repaid);
+            assert!(after.saturating_sub(target) < Wad::from_raw(U256::exp10(6)));
+            assert!(target.saturating_sub(after) < Wad::from_raw(U256::exp10(6)));
+        }
+        assert!(repay_value(weighted, debt, target, 8_000, costly).unwrap() > repay_value(weighted, debt, target, 8_000, Wad::ONE).unwrap());
+
+        // (22500 - 16000) / 0.8
+        assert_eq!(top_up_value(weighted, debt, target, 8_000).unwrap(), Wad::from_integer(8_125));
+        assert!(repay_value(weighted, debt, Wad::from_bps(8_000), 8_000, Wad::ONE).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+// src/lending/watchlist.rs
+use ethers::types::Address;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+use anyhow::Result;
+
+use super::lending_pool::{account_data, LendingPool, ReservePosition};
+use crate::math::Wad;
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    positions: HashMap<Address, Vec<ReservePosition>>,
+    health: HashMap<Address, Wad>,
+    /// Accounts ordered by health factor, lowest first.
+    ranked: BTreeSet<(Wad, Address)>,
+    /// Price source → accounts holding an asset priced through it as collateral or debt.
+    exposure: HashMap<Address, HashSet<Address>>,
+    prices: HashMap<Address, Wad>,
+}
+
+/// How the market rounds an account's health factor from its positions.
+type HealthFn<'a> = &'a dyn Fn(&[ReservePosition]) -> Result<Wad>;
+
+impl MonitorState {
+    fn set_positions(&mut self, user: Address, positions: Vec<ReservePosition>, health: HealthFn) -> Result<()> {
+        self.remove(user);
+
+        for position in &positions {
+            self.exposure.entry(position.price_source).or_default().insert(user);
+            self.prices.entry(position.price_source).or_insert(position.price);
+        }
+        self.positions.insert(user, positions);
+        self.recompute(user, health)
+    }
+
+    fn remove(&mut self, user: Address) {
+        if let Some(positions) = self.positions.remove(&user) {
+            for position in positions {
+                if let Some(users) = self.exposure.get_mut(&position.price_source) {
+                    users.remove(&user);
+                    if users.is_empty() {
+                        self.exposure.remove(&position.price_source);
+                        self.prices.remove(&position.price_source);
+                    }
+                }
+            }
+        }
+        if let Some(health_factor) = self.health.remove(&user) {
+            self.ranked.remove(&(health_factor, user));
+        }
+    }
+
+    fn recompute(&mut self, user: Address, health: HealthFn) -> Result<()> {
+        let positions = match self.positions.get(&user) {
+            Some(positions) => positions,
+            None => return Ok(()),
+        };
+        let health_factor = health(positions)?;
+
+        if let Some(previous) = self.health.insert(user, health_factor) {
+            self.ranked.remove(&(previous, user));
+        }
+        self.ranked.insert((health_factor, user));
+        Ok(())
+    }
+
+    /// Applies new prices and recomputes only the accounts exposed to a price source that moved.
+    fn apply_prices(&mut self, prices: HashMap<Address, Wad>, health: HealthFn) -> Result<HashSet<Address>> {
+        let mut affected = HashSet::new();
+        for (source, price) in prices {
+            if self.prices.get(&source) == Some(&price) {
+                continue;
+            }
+            self.prices.insert(source, price);
+            affected.extend(self.exposure.get(&source).into_iter().flatten().copied());
+        }
+
+        for &user in &affected {
+            if let Some(positions) = self.positions.get_mut(&user) {
+                for position in positions.iter_mut() {
+                    if let Some(price) = self.prices.get(&position.price_source) {
+                        position.price = *price;
+                    }
+                }
+            }
+            self.recompute(user, health)?;
+        }
+
+        Ok(affected)
+    }
+}
+
+/// Health factors of one market's borrowers, computed locally from cached positions.
+///
+/// Positions are re-read only for accounts whose balances changed on chain, and health factors
+/// are recomputed, with the market's own rounding, only for accounts exposed to a price that moved.
+pub struct HealthMonitor {
+    pool: Arc<dyn LendingPool>,
+    state: RwLock<MonitorState>,
+}
+
+impl HealthMonitor {
+    pub fn new(pool: Arc<dyn LendingPool>) -> Self {
+        Self {
+            pool,
+            state: RwLock::new(MonitorState::default()),
+        }
+    }
+
+    /// Tracks exactly `borrowers`, re-reading positions for new accounts and for those in `touched`.
+    pub async fn sync_accounts(&self, borrowers: &[Address], touched: &HashSet<Address>) -> Result<()> {
+        let open: HashSet<Address> = borrowers.iter().copied().collect();
+        let stale: Vec<Address> = {
+            let mut state = self.state.write().await;
+            let closed: Vec<Address> = state.positions.keys().filter(|user| !open.contains(user)).copied().collect();
+            for user in closed {
+                state.remove(user);
+            }
+            borrowers
+                .iter()
+                .filter(|user| touched.contains(user) || !state.positions.contains_key(user))
+                .copied()
+                .collect()
+        };
+
+        for user in stale {
+            match self.pool.get_user_positions(user).await {
+                Ok(positions) => {
+                    self.state.write().await.set_positions(user, positions, &|p| self.pool.health_factor(p))?
+                }
+                Err(e) => warn!("Failed to load positions of {:?} on {}: {}", user, self.pool.name(), e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Polls every price source any tracked account depends on and returns the accounts whose
+    /// health factor was recomputed.
+    pub async fn update_prices(&self) -> Result<HashSet<Address>> {
+        let sources: Vec<Address> = self.state.read().await.exposure.keys().copied().collect();
+        if sources.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let prices = self.pool.asset_prices(&sources).await?;
+        let affected = self
+            .state
+            .write()
+            .await
+            .apply_prices(sources.into_iter().zip(prices).collect(), &|p| self.pool.health_factor(p))?;
+        if !affected.is_empty() {
+            debug!("Recomputed {} health factors on {}", affected.len(), self.pool.name());
+        }
+
+        Ok(affected)
+    }
+
+    /// Locally computed health factor of a tracked account.
+    pub async fn health_factor(&self, user: Address) -> Option<Wad> {
+        self.state.read().await.health.get(&user).copied()
+    }
+
+    /// The `limit` accounts closest to liquidation, lowest health factor first.
+    pub async fn watchlist(&self, limit: usize) -> Vec<(Address, Wad)> {
+        self.state
+            .read()
+            .await
+            .ranked
+            .iter()
+            .take(limit)
+            .map(|(health_factor, user)| (*user, *health_factor))
+            .collect()
+    }
+
+    /// Total debt of `user` in the protocol's base currency, from its cached positions.
+    pub async fn debt_value(&self, user: Address) -> Option<Wad> {
+        let state = self.state.read().await;
+        let positions = state.positions.get(&user)?;
+        account_data(positions).ok().map(|data| Wad::from_raw(data.debt_value))
+    }
+
+    /// Accounts whose locally computed health factor is below `threshold`.
+    pub async fn below(&self, threshold: Wad) -> Vec<Address> {
+        self.state
+            .read()
+            .await
+            .ranked
+            .iter()
+            .take_while(|(health_factor, _)| *health_factor < threshold)
+            .map(|(_, user)| *user)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::U256;
+
+    fn position(asset: u64, price: u64, collateral: u64, debt: u64) -> ReservePosition {
+        ReservePosition {
+            asset: Address::from_low_u64_be(asset),
+            decimals: 18,
+            price: Wad::from_integer(price),
+            price_source: Address::from_low_u64_be(asset),
+            collateral: U256::from(collateral) * U256::exp10(18),
+            debt: U256::from(debt) * U256::exp10(18),
+            collateral_enabled: collateral > 0,
+            liquidation_threshold_bps: 8_000,
+            liquidation_bonus_bps: 10_500,
+            protocol_fee_bps: 0,
+        }
+    }
+
+    fn health(positions: &[ReservePosition]) -> Result<Wad> {
+        Ok(account_data(positions)?.health_factor)
+    }
+
+    #[test]
+    fn test_price_move_only_recomputes_exposed_accounts() {
+        let (eth, btc, usdc) = (1, 2, 3);
+        let (alice, bob) = (Address::from_low_u64_be(10), Address::from_low_u64_be(11));
+        let mut state = MonitorState::default();
+        state.set_positions(alice, vec![position(eth, 2_000, 1, 0), position(usdc, 1, 0, 1_500)], &health).unwrap();
+        state.set_positions(bob, vec![position(btc, 30_000, 1, 0), position(usdc, 1, 0, 20_000)], &health).unwrap();
+
+        // Alice: 2000 * 0.8 / 1500 is closer to 1.0 than Bob's 30000 * 0.8 / 20000
+        assert_eq!(state.ranked.iter().next().unwrap().1, alice);
+
+        let affected = state
+            .apply_prices(HashMap::from([
+                (Address::from_low_u64_be(eth), Wad::from_integer(2_000)),
+                (Address::from_low_u64_be(btc), Wad::from_integer(20_000)),
+            ]), &health)
+            .unwrap();
+        assert_eq!(affected, HashSet::from([bob]));
+        assert_eq!(state.health[&bob], Wad::from_bps(8_000));
+        assert_eq!(state.ranked.iter().next().unwrap().1, bob);
+    }
+}
@@ -0,0 +1,9 @@
+pub mod config;
+pub mod execution;
+pub mod lending;
+pub mod math;
+#[path = "Oracle/mod.rs"]
+pub mod oracle;
+pub mod signer;
+pub mod simulation;
+pub mod strategies;
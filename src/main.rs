@@ -1,39 +1,204 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
-
-mod dashboard;
-mod frontrunning;
-mod hft;
-mod liquidation;
-mod sandwich;
-mod flashloan;
-mod arbitrage;
-
-fn main() {
-  let mut dexes = HashMap::new();
-  dexes.insert("0x1234567890abcdef".to_string(), "0x1234567890abcdef".to_string());
-  dexes.insert("0x234567890abcdef1".to_string(), "0x234567890abcdef1".to_string());
-
-  let token = "0x1234567890abcdef".to_string();
-  let gas_price = 20e9;
-  let private_key = "0x1234567890abcdef".to_string();
-
-  let dashboard = dashboard::Dashboard::new(dexes.clone(), token.clone(), gas_price, private_key.clone());
-  let frontrunning = frontrunning::Frontrunning::new(dexes.clone(), token.clone(), gas_price, private_key.clone());
-  let hft = hft::Hft::new(dexes.clone(), token.clone(), gas_price, private_key.clone());
-  let liquidation = liquidation::Liquidation::new(dexes.clone(), token.clone(), gas_price, private_key.clone());
-  let sandwich = sandwich::Sandwich::new(dexes.clone(), token.clone(), gas_price, private_key.clone());
-  let flashloan = flashloan::Flashloan::new(dexes.clone(), token.clone(), gas_price, private_key.clone());
-  let arbitrage = arbitrage::Arbitrage::new(dexes.clone(), token.clone(), gas_price, private_key.clone());
-
-  dashboard.dashboard();
-  frontrunning.frontrunning();
-  hft.hft();
-  liquidation.liquidation();
-  sandwich.sandwich();
-  flashloan.flashloan();
-  arbitrage.arbitrage();
+use ethers::providers::{Middleware, Provider, Ws};
+use ethers::types::{Address, U256};
+use futures::StreamExt;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use ragnor_mev_bot::config::{Config, GlobalConfig};
+use ragnor_mev_bot::execution::{
+  ApprovalManager, ApprovalsConfig, BundleRelay, DeadlineCalculator, ExecutorConfig, FlashLoanProvidersConfig, FlashLoanRouter,
+  MultiActionExecutor, RouteFinder, SlippageGuard, Submitter, SwapCalldataBuilder, SwapQuoter, WalletPool, WalletPoolConfig,
+};
+use ragnor_mev_bot::lending::{
+  AaveLendingPool, AaveMarketsConfig, AuctionHouse, BorrowerIndex, BorrowerIndexConfig, ClipperAuctions, ClipperConfig, CometLendingPool,
+  CompoundLendingPool, FlashLiquidator, LendingPool, PositionUnwinder, ProtectionConfig,
+};
+use ragnor_mev_bot::oracle::{FeedUpdateWatcher, PriceOracle, PriceOracleConfig};
+use ragnor_mev_bot::signer::{PolicyConfig, PolicyEngine};
+use ragnor_mev_bot::simulation::EvmSimulator;
+use ragnor_mev_bot::strategies::{
+  ArbitrageConfig, ArbitrageStrategy, Dex, FlashLoanStrategy, LiquidationConfig, LiquidationStrategy, ProfitFilter, SelfProtectionStrategy,
+  Strategy,
+};
+
+const ARBITRAGE: &str = "arbitrage";
+const LIQUIDATION: &str = "liquidation";
+const SELF_PROTECTION: &str = "self_protection";
+
+// The multi-threaded runtime is required: the simulator blocks worker threads in place on node reads
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+  tracing_subscriber::fmt::init();
+
+  let config = Config::new()?;
+  let global = GlobalConfig::load("config/global_config.json")?;
+  if global.dry_run.enabled {
+    warn!("Dry run: transactions are journaled to {} instead of broadcast", global.dry_run.journal_path);
+  }
+  let provider = Arc::new(Provider::<Ws>::connect(&config.node_url).await?);
+  let weth: Address = config.tokens.weth.parse()?;
+
+  // Our own contracts are allowlisted from their deployment configs, so one not deployed yet stops startup
+  let mut policy = PolicyConfig::load("config/signing_policy.json")?;
+  let mut approvals = ApprovalsConfig::load("config/approvals_config.json")?;
+  let mut index = BorrowerIndexConfig::load("config/borrower_index.json")?;
+  let executor = ExecutorConfig::load("config/executor.json")?;
+  let aave_markets = AaveMarketsConfig::load("config/aave_markets.json")?;
+  let clippers = ClipperConfig::load("config/clipper_auctions.json")?;
+  if enabled(&global, ARBITRAGE).is_some() || enabled(&global, LIQUIDATION).is_some() {
+    executor.register(&mut approvals, &mut policy)?;
+  }
+  if enabled(&global, LIQUIDATION).is_some() {
+    aave_markets.register(&mut index, &mut approvals, &mut policy)?;
+    clippers.register(&mut policy)?;
+  }
+  let protection = match enabled(&global, SELF_PROTECTION) {
+    Some(path) => {
+      let protection = ProtectionConfig::load(path)?;
+      protection.register(&mut policy)?;
+      Some(protection)
+    }
+    None => None,
+  };
+
+  // Keys stay in the signer backends; every transaction goes through the policy and the submitter
+  let policy = Arc::new(PolicyEngine::new(policy));
+  let wallets = Arc::new(WalletPool::from_config(provider.clone(), &WalletPoolConfig::load("config/wallet_pool.json")?, &global.dry_run).await?);
+  let simulator = Arc::new(EvmSimulator::connect(provider.clone()).await?);
+  let mut submitter = Submitter::new(provider.clone(), policy, U256::from(config.priority_fee), &global.dry_run).with_simulator(simulator.clone());
+  if let Some(url) = &config.bundle_relay_url {
+    submitter = submitter.with_relay(BundleRelay::new(url));
+  }
+  let submitter = Arc::new(submitter);
+  info!("Trading from {:?}", wallets.addresses());
+
+  let oracle = Arc::new(PriceOracle::from_config(provider.clone(), &PriceOracleConfig::load("config/price_oracle.json")?));
+  let profit_filter = Arc::new(ProfitFilter::new(oracle.clone(), weth, config.min_profit, U256::from(config.priority_fee)));
+  let quoter = Arc::new(SwapQuoter::new(provider.clone()));
+  let routes = Arc::new(RouteFinder::from_config(quoter.clone(), &config)?);
+  let guard = SlippageGuard::from_config(&config)?;
+  let deadlines = Arc::new(DeadlineCalculator::from_config(provider.clone(), &config));
+  let flash_loans = FlashLoanRouter::from_config(&FlashLoanProvidersConfig::load("config/flash_loan_providers.json")?, provider.clone());
+  let funding = Arc::new(FlashLoanStrategy::new(provider.clone(), Arc::new(flash_loans)));
+  let executor = Arc::new(MultiActionExecutor::new(executor.executor));
+  let approvals = Arc::new(ApprovalManager::new(provider.clone(), submitter.clone(), approvals));
+
+  // Shared by liquidation and self-protection; absorb points accrue to the executor
+  let mut lending_pools: Vec<Arc<dyn LendingPool>> = Vec::new();
+  for market in &aave_markets.markets {
+    lending_pools.push(Arc::new(AaveLendingPool::connect(market, provider.clone()).await?));
+  }
+  let compound = LiquidationConfig::load(config_path(&global, LIQUIDATION)?)?;
+  for market in &compound.compound_v2 {
+    lending_pools.push(Arc::new(CompoundLendingPool::connect(market.name.clone(), market.comptroller, weth, provider.clone()).await?));
+  }
+  for market in &compound.compound_v3 {
+    lending_pools.push(Arc::new(CometLendingPool::connect(market.name.clone(), market.comet, executor.address(), provider.clone()).await?));
+  }
+
+  let mut strategies: Vec<(&str, Arc<dyn Strategy>)> = Vec::new();
+  if enabled(&global, LIQUIDATION).is_some() {
+    let mut auctions: Vec<Arc<dyn AuctionHouse>> = Vec::new();
+    for market in &clippers.clippers {
+      auctions.push(Arc::new(ClipperAuctions::connect(market, clippers.taker, clippers.dai, provider.clone()).await?));
+    }
+    let liquidation = Arc::new(
+      LiquidationStrategy::new(
+        wallets.clone(),
+        submitter.clone(),
+        lending_pools.clone(),
+        Arc::new(BorrowerIndex::open(provider.clone(), index).await?),
+        profit_filter.clone(),
+        Arc::new(FlashLiquidator::new(executor.clone(), routes.clone(), guard.clone())),
+        auctions,
+        routes.clone(),
+        funding.clone(),
+        executor.clone(),
+        approvals.clone(),
+        deadlines.clone(),
+      )
+      .await,
+    );
+
+    let feeds = FeedUpdateWatcher::connect(&oracle, provider.clone()).await?;
+    let watcher = liquidation.clone();
+    tokio::spawn(async move {
+      if let Err(e) = watcher.watch_feeds(&feeds).await {
+        warn!("Stopped watching Chainlink feeds: {}", e);
+      }
+    });
+    strategies.push((LIQUIDATION, liquidation));
+  }
+
+  if let Some(path) = enabled(&global, ARBITRAGE) {
+    let arbitrage = ArbitrageConfig::load(path)?;
+    let dexes = arbitrage
+      .dexes
+      .iter()
+      .map(|dex| Dex::new(dex.name.clone(), dex.router, arbitrage.pairs.clone(), quoter.clone()))
+      .collect();
+    let routers = arbitrage.dexes.iter().map(|dex| (dex.name.clone(), dex.router)).collect();
+    let swap_builder = Arc::new(SwapCalldataBuilder::new((*quoter).clone(), guard.clone(), executor.address()));
+    let strategy = ArbitrageStrategy::new(
+      wallets.clone(),
+      submitter.clone(),
+      dexes,
+      profit_filter.clone(),
+      funding.clone(),
+      executor.clone(),
+      approvals.clone(),
+      swap_builder,
+      routers,
+      deadlines.clone(),
+      arbitrage.trade_size,
+    )
+    .await;
+    strategies.push((ARBITRAGE, Arc::new(strategy)));
+  }
+
+  if let Some(protection) = protection {
+    let unwinder = Arc::new(PositionUnwinder::connect(&protection, routes.clone(), guard.clone(), provider.clone()).await?);
+    let strategy = SelfProtectionStrategy::new(wallets.clone(), submitter.clone(), lending_pools.clone(), protection.accounts, unwinder);
+    strategies.push((SELF_PROTECTION, Arc::new(strategy)));
+  }
+
+  let top_ups = wallets.clone();
+  tokio::spawn(async move {
+    if let Err(e) = top_ups.watch_blocks().await {
+      warn!("Stopped topping up hot wallets: {}", e);
+    }
+  });
+
+  // Every strategy sees each block, simulated against its post-state
+  info!("Running {:?}", strategies.iter().map(|(name, _)| *name).collect::<Vec<_>>());
+  let mut blocks = provider.subscribe_blocks().await?;
+  while let Some(block) = blocks.next().await {
+    if let Err(e) = simulator.set_block(&block) {
+      warn!("Failed to move the simulator to block {:?}: {}", block.number, e);
+    }
+    let runs = strategies.iter().map(|(name, strategy)| {
+      let block = &block;
+      async move { (*name, strategy.execute(block).await) }
+    });
+    for (name, result) in futures::future::join_all(runs).await {
+      if let Err(e) = result {
+        warn!("{} failed at block {:?}: {}", name, block.number, e);
+      }
+    }
+  }
+
+  Ok(())
 }
 
+/// The config path of strategy `name` when the global config enables it.
+fn enabled<'a>(global: &'a GlobalConfig, name: &str) -> Option<&'a str> {
+  global.strategies.get(name).filter(|entry| entry.enabled).map(|entry| entry.config_path.as_str())
+}
 
+fn config_path<'a>(global: &'a GlobalConfig, name: &str) -> anyhow::Result<&'a str> {
+  global
+    .strategies
+    .get(name)
+    .map(|entry| entry.config_path.as_str())
+    .ok_or_else(|| anyhow::anyhow!("Global config has no {} entry", name))
+}
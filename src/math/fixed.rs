@@ -0,0 +1,196 @@
+// src/math/fixed.rs
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use super::{mul_div, MathError};
+
+macro_rules! fixed_point {
+    ($name:ident, $decimals:expr, $one:expr) => {
+        /// Unsigned fixed-point number with
+        #[doc = concat!(stringify!($decimals), " decimals, stored as its raw scaled integer.")]
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+        pub struct $name(U256);
+
+        impl $name {
+            pub const DECIMALS: u8 = $decimals;
+            pub const ZERO: Self = Self(U256([0, 0, 0, 0]));
+            pub const ONE: Self = Self($one);
+
+            /// Wraps an already-scaled value, e.g. a health factor returned by Aave.
+            pub const fn from_raw(raw: U256) -> Self {
+                Self(raw)
+            }
+
+            pub const fn raw(&self) -> U256 {
+                self.0
+            }
+
+            pub fn from_integer(value: u64) -> Self {
+                Self(U256::from(value) * Self::ONE.0)
+            }
+
+            /// Rescales a token amount with `decimals` decimals.
+            pub fn from_scaled(amount: U256, decimals: u8) -> Result<Self, MathError> {
+                rescale(amount, decimals, $decimals).map(Self)
+            }
+
+            /// Rescales to a token amount with `decimals` decimals, rounding down.
+            pub fn to_scaled(self, decimals: u8) -> Result<U256, MathError> {
+                rescale(self.0, $decimals, decimals)
+            }
+
+            pub fn is_zero(&self) -> bool {
+                self.0.is_zero()
+            }
+
+            pub fn checked_add(self, other: Self) -> Result<Self, MathError> {
+                self.0.checked_add(other.0).map(Self).ok_or(MathError::Overflow)
+            }
+
+            pub fn checked_sub(self, other: Self) -> Result<Self, MathError> {
+                self.0.checked_sub(other.0).map(Self).ok_or(MathError::Underflow)
+            }
+
+            pub fn saturating_sub(self, other: Self) -> Self {
+                Self(self.0.saturating_sub(other.0))
+            }
+
+            /// Fixed-point product, rounded down.
+            pub fn checked_mul(self, other: Self) -> Result<Self, MathError> {
+                mul_div(self.0, other.0, Self::ONE.0).map(Self)
+            }
+
+            /// Fixed-point quotient, rounded down.
+            pub fn checked_div(self, other: Self) -> Result<Self, MathError> {
+                mul_div(self.0, Self::ONE.0, other.0).map(Self)
+            }
+
+            /// Multiplies a raw integer amount (e.g. token units) by this factor, rounded down.
+            pub fn mul_amount(&self, amount: U256) -> Result<U256, MathError> {
+                mul_div(amount, self.0, Self::ONE.0)
+            }
+
+            /// Lossy conversion for logs and dashboards only; never feed the result back into arithmetic.
+            pub fn to_f64(self) -> f64 {
+                self.to_string().parse().unwrap_or(f64::NAN)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let one = Self::ONE.0;
+                let fraction = format!("{:0>width$}", (self.0 % one).to_string(), width = $decimals as usize);
+                let fraction = fraction.trim_end_matches('0');
+                if fraction.is_empty() {
+                    write!(f, "{}", self.0 / one)
+                } else {
+                    write!(f, "{}.{}", self.0 / one, fraction)
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = MathError;
+
+            /// Parses a plain decimal such as `"1.05"` without going through `f64`.
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let invalid = || MathError::Parse(s.to_string());
+                let (whole, fraction) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+                if fraction.len() > $decimals as usize
+                    || (whole.is_empty() && fraction.is_empty())
+                    || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())
+                {
+                    return Err(invalid());
+                }
+
+                let whole = if whole.is_empty() { U256::zero() } else { U256::from_dec_str(whole).map_err(|_| invalid())? };
+                let fraction = if fraction.is_empty() {
+                    U256::zero()
+                } else {
+                    U256::from_dec_str(fraction).map_err(|_| invalid())?
+                        * U256::exp10($decimals as usize - fraction.len())
+                };
+
+                whole
+                    .checked_mul(Self::ONE.0)
+                    .and_then(|scaled| scaled.checked_add(fraction))
+                    .map(Self)
+                    .ok_or(MathError::Overflow)
+            }
+        }
+    };
+}
+
+fixed_point!(Wad, 18, U256([1_000_000_000_000_000_000, 0, 0, 0]));
+fixed_point!(Ray, 27, U256([0x9fd0_803c_e800_0000, 0x33b2e3c, 0, 0]));
+
+impl Ray {
+    /// Drops the extra 9 decimals, rounding down.
+    pub fn to_wad(self) -> Wad {
+        Wad::from_raw(self.0 / U256::exp10(9))
+    }
+}
+
+impl Wad {
+    pub fn to_ray(self) -> Result<Ray, MathError> {
+        self.0.checked_mul(U256::exp10(9)).map(Ray).ok_or(MathError::Overflow)
+    }
+
+    /// Converts basis points (e.g. a liquidation bonus of `10500`) to a factor.
+    pub fn from_bps(bps: u64) -> Self {
+        Self(U256::from(bps) * U256::exp10(14))
+    }
+}
+
+fn rescale(value: U256, from_decimals: u8, to_decimals: u8) -> Result<U256, MathError> {
+    if to_decimals >= from_decimals {
+        value
+            .checked_mul(U256::exp10((to_decimals - from_decimals) as usize))
+            .ok_or(MathError::Overflow)
+    } else {
+        Ok(value / U256::exp10((from_decimals - to_decimals) as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(Wad::ONE.raw(), U256::exp10(18));
+        assert_eq!(Ray::ONE.raw(), U256::exp10(27));
+    }
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        let wad: Wad = "1.05".parse().unwrap();
+
+        assert_eq!(wad.raw(), U256::from(1_050_000_000_000_000_000u128));
+        assert_eq!(wad.to_string(), "1.05");
+        assert_eq!("0.000000000000000001".parse::<Wad>().unwrap().raw(), U256::one());
+        assert!("1.0000000000000000001".parse::<Wad>().is_err());
+        assert!("-1".parse::<Wad>().is_err());
+    }
+
+    #[test]
+    fn test_checked_operations() {
+        let half: Wad = "0.5".parse().unwrap();
+
+        assert_eq!(half.checked_mul(Wad::from_integer(3)).unwrap(), "1.5".parse().unwrap());
+        assert_eq!(Wad::ONE.checked_div(half).unwrap(), Wad::from_integer(2));
+        assert_eq!(half.checked_sub(Wad::ONE), Err(MathError::Underflow));
+        assert_eq!(Wad::ONE.checked_div(Wad::ZERO), Err(MathError::DivisionByZero));
+    }
+
+    #[test]
+    fn test_token_rescaling() {
+        let usdc = Wad::from_scaled(U256::from(1_500_000u64), 6).unwrap();
+
+        assert_eq!(usdc, "1.5".parse().unwrap());
+        assert_eq!(usdc.to_scaled(6).unwrap(), U256::from(1_500_000u64));
+        assert_eq!(Wad::ONE.to_ray().unwrap().to_wad(), Wad::ONE);
+    }
+}
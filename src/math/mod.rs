@@ -0,0 +1,86 @@
+// src/math/mod.rs
+pub mod fixed;
+pub mod q96;
+
+use ethers::types::{U256, U512};
+use thiserror::Error;
+
+pub use fixed::{Ray, Wad};
+pub use q96::{price_to_sqrt_price_x96, sqrt_price_x96_to_price};
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum MathError {
+    #[error("arithmetic overflow")]
+    Overflow,
+    #[error("arithmetic underflow")]
+    Underflow,
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("invalid decimal number: {0}")]
+    Parse(String),
+    #[error("{0} is not a power of ten")]
+    NotPowerOfTen(U256),
+}
+
+/// Decimals of a token or currency from its unit, e.g. `8` for `1e8`; errors unless `unit` is exactly `10^n`.
+pub fn unit_decimals(unit: U256) -> Result<u8, MathError> {
+    (0..=77u8)
+        .find(|&decimals| U256::exp10(decimals as usize) == unit)
+        .ok_or(MathError::NotPowerOfTen(unit))
+}
+
+/// `a * b / denominator` rounded down, with a 512-bit intermediate so the product cannot overflow.
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> Result<U256, MathError> {
+    if denominator.is_zero() {
+        return Err(MathError::DivisionByZero);
+    }
+
+    let result = U512::from(a) * U512::from(b) / U512::from(denominator);
+    U256::try_from(result).map_err(|_| MathError::Overflow)
+}
+
+/// `a * b / denominator` rounded up.
+pub fn mul_div_up(a: U256, b: U256, denominator: U256) -> Result<U256, MathError> {
+    if denominator.is_zero() {
+        return Err(MathError::DivisionByZero);
+    }
+
+    let product = U512::from(a) * U512::from(b);
+    let denominator = U512::from(denominator);
+    let mut result = product / denominator;
+    if !(product % denominator).is_zero() {
+        result += U512::one();
+    }
+    U256::try_from(result).map_err(|_| MathError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div_survives_wide_products() {
+        let big = U256::MAX / 2;
+
+        assert_eq!(mul_div(big, U256::from(4), U256::from(4)).unwrap(), big);
+        assert_eq!(mul_div(big, U256::from(4), U256::from(1)), Err(MathError::Overflow));
+        assert_eq!(mul_div(U256::one(), U256::one(), U256::zero()), Err(MathError::DivisionByZero));
+    }
+
+    #[test]
+    fn test_mul_div_up_rounds_remainder() {
+        assert_eq!(mul_div_up(U256::from(10), U256::from(1), U256::from(3)).unwrap(), U256::from(4));
+        assert_eq!(mul_div_up(U256::from(9), U256::from(1), U256::from(3)).unwrap(), U256::from(3));
+    }
+
+    #[test]
+    fn test_unit_decimals_requires_exact_power_of_ten() {
+        assert_eq!(unit_decimals(U256::one()).unwrap(), 0);
+        assert_eq!(unit_decimals(U256::exp10(8)).unwrap(), 8);
+        assert_eq!(unit_decimals(U256::exp10(77)).unwrap(), 77);
+        // Same digit count as 1e8, but not a unit
+        assert!(unit_decimals(U256::from(999_999_999u64)).is_err());
+        assert!(unit_decimals(U256::from(200_000_000u64)).is_err());
+        assert!(unit_decimals(U256::zero()).is_err());
+    }
+}
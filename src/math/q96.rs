@@ -0,0 +1,52 @@
+// src/math/q96.rs
+use ethers::types::{U256, U512};
+
+use super::{MathError, Wad};
+
+const Q192_SHIFT: usize = 192;
+
+/// Converts a Uniswap V3 `sqrtPriceX96` into the price of token0 in token1, in whole-token units.
+pub fn sqrt_price_x96_to_price(sqrt_price_x96: U256, decimals0: u8, decimals1: u8) -> Result<Wad, MathError> {
+    let sqrt_price = U512::from(sqrt_price_x96);
+    let numerator = sqrt_price * sqrt_price * U512::exp10(Wad::DECIMALS as usize + decimals0 as usize);
+    let denominator = (U512::one() << Q192_SHIFT) * U512::exp10(decimals1 as usize);
+
+    U256::try_from(numerator / denominator)
+        .map(Wad::from_raw)
+        .map_err(|_| MathError::Overflow)
+}
+
+/// Inverse of `sqrt_price_x96_to_price`, rounded down.
+pub fn price_to_sqrt_price_x96(price: Wad, decimals0: u8, decimals1: u8) -> Result<U256, MathError> {
+    let ratio_x192 = U512::from(price.raw())
+        .checked_mul(U512::exp10(decimals1 as usize))
+        .and_then(|scaled| scaled.checked_mul(U512::one() << Q192_SHIFT))
+        .ok_or(MathError::Overflow)?
+        / U512::exp10(Wad::DECIMALS as usize + decimals0 as usize);
+
+    U256::try_from(ratio_x192.integer_sqrt()).map_err(|_| MathError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_price_at_q96() {
+        let one_x96 = U256::one() << 96;
+
+        assert_eq!(sqrt_price_x96_to_price(one_x96, 18, 18).unwrap(), Wad::ONE);
+        assert_eq!(price_to_sqrt_price_x96(Wad::ONE, 18, 18).unwrap(), one_x96);
+    }
+
+    #[test]
+    fn test_decimal_adjustment() {
+        // WETH (18 decimals) priced at 2000 USDC (6 decimals): raw ratio is 2000e6 / 1e18
+        let price = Wad::from_integer(2_000);
+        let sqrt_price_x96 = price_to_sqrt_price_x96(price, 18, 6).unwrap();
+        let round_trip = sqrt_price_x96_to_price(sqrt_price_x96, 18, 6).unwrap();
+
+        // Both directions round down, so the round trip can only lose a few wei
+        assert!(price.checked_sub(round_trip).unwrap() < Wad::from_raw(U256::exp10(7)));
+    }
+}
@@ -0,0 +1,169 @@
+// src/signer/mod.rs
+pub mod policy;
+pub mod remote;
+pub mod secret;
+
+use async_trait::async_trait;
+use ethers::{
+    signers::{coins_bip39::English, to_eip155_v, LocalWallet, MnemonicBuilder, Signer, WalletError},
+    types::{transaction::eip2718::TypedTransaction, transaction::eip712::Eip712, Address, Signature, H256},
+    utils::hash_message,
+};
+use serde::Deserialize;
+use std::env;
+use thiserror::Error;
+
+pub use policy::{PolicyConfig, PolicyEngine, PolicySigner, PolicyViolation};
+pub use remote::RemoteSigner;
+pub use secret::Secret;
+
+#[derive(Debug, Error)]
+pub enum SignerError {
+    #[error(transparent)]
+    Wallet(#[from] WalletError),
+    #[error("signing policy: {0}")]
+    Policy(#[from] PolicyViolation),
+    #[error("remote signer: {0}")]
+    Remote(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("typed data: {0}")]
+    TypedData(String),
+    #[error("signer configuration: {0}")]
+    Config(String),
+}
+
+/// Where signing keys come from. Never holds a raw private key.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// Encrypted JSON v3 keystore; the password comes from a file or an interactive prompt.
+    Keystore { path: String, password_file: Option<String> },
+    /// A signing process reached over a local Unix socket.
+    Remote { socket_path: String },
+    /// A test mnemonic, for local forks and test networks only.
+    Mnemonic { phrase: Secret, index: u32 },
+}
+
+impl SignerConfig {
+    /// Reads `SIGNER` (`keystore`, `remote` or `mnemonic`) and the variables of that backend.
+    pub fn from_env() -> Result<Self, SignerError> {
+        let backend = env::var("SIGNER").unwrap_or_else(|_| "keystore".to_string());
+        let var = |name: &str| env::var(name).map_err(|_| SignerError::Config(format!("{} is not set", name)));
+
+        match backend.as_str() {
+            "keystore" => Ok(SignerConfig::Keystore {
+                path: var("KEYSTORE_PATH")?,
+                password_file: env::var("KEYSTORE_PASSWORD_FILE").ok(),
+            }),
+            "remote" => Ok(SignerConfig::Remote {
+                socket_path: var("REMOTE_SIGNER_SOCKET")?,
+            }),
+            "mnemonic" => Ok(SignerConfig::Mnemonic {
+                phrase: Secret::new(var("TEST_MNEMONIC")?),
+                index: match env::var("MNEMONIC_INDEX") {
+                    Ok(index) => index
+                        .parse()
+                        .map_err(|_| SignerError::Config(format!("Invalid MNEMONIC_INDEX: {}", index)))?,
+                    Err(_) => 0,
+                },
+            }),
+            other => Err(SignerError::Config(format!("Unknown signer backend: {}", other))),
+        }
+    }
+}
+
+/// The bot's transaction signer, usable anywhere ethers expects a `Signer`.
+#[derive(Debug, Clone)]
+pub enum BotSigner {
+    Local(LocalWallet),
+    Remote { signer: RemoteSigner, chain_id: u64 },
+}
+
+impl BotSigner {
+    pub async fn from_config(config: &SignerConfig, chain_id: u64) -> Result<Self, SignerError> {
+        match config {
+            SignerConfig::Keystore { path, password_file } => {
+                let password = match password_file {
+                    Some(file) => Secret::from_file(file)?,
+                    None => Secret::new(rpassword::prompt_password(format!("Password for {}: ", path))?),
+                };
+                let wallet = LocalWallet::decrypt_keystore(path, password.expose())?;
+                Ok(BotSigner::Local(wallet.with_chain_id(chain_id)))
+            }
+            SignerConfig::Remote { socket_path } => Ok(BotSigner::Remote {
+                signer: RemoteSigner::connect(socket_path).await?,
+                chain_id,
+            }),
+            SignerConfig::Mnemonic { phrase, index } => {
+                let wallet = MnemonicBuilder::<English>::default()
+                    .phrase(phrase.expose())
+                    .index(*index)?
+                    .build()?;
+                Ok(BotSigner::Local(wallet.with_chain_id(chain_id)))
+            }
+        }
+    }
+
+    pub async fn sign_hash(&self, hash: H256) -> Result<Signature, SignerError> {
+        match self {
+            BotSigner::Local(wallet) => Ok(wallet.sign_hash(hash)?),
+            BotSigner::Remote { signer, .. } => signer.sign_hash(hash).await,
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for BotSigner {
+    type Error = SignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        self.sign_hash(hash_message(message)).await
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            BotSigner::Local(wallet) => Ok(wallet.sign_transaction(tx).await?),
+            BotSigner::Remote { signer, chain_id } => {
+                let mut tx = tx.clone();
+                let chain_id = tx.chain_id().map(|id| id.as_u64()).unwrap_or(*chain_id);
+                tx.set_chain_id(chain_id);
+
+                // `sign_hash` guarantees v is 27 or 28
+                let mut signature = signer.sign_hash(tx.sighash()).await?;
+                signature.v = to_eip155_v((signature.v - 27) as u8, chain_id);
+                Ok(signature)
+            }
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        let digest = payload
+            .encode_eip712()
+            .map_err(|e| SignerError::TypedData(e.to_string()))?;
+        self.sign_hash(H256::from(digest)).await
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            BotSigner::Local(wallet) => wallet.address(),
+            BotSigner::Remote { signer, .. } => signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            BotSigner::Local(wallet) => wallet.chain_id(),
+            BotSigner::Remote { chain_id, .. } => *chain_id,
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            BotSigner::Local(wallet) => BotSigner::Local(wallet.with_chain_id(chain_id)),
+            BotSigner::Remote { signer, .. } => BotSigner::Remote { signer, chain_id: chain_id.into() },
+        }
+    }
+}
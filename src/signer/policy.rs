@@ -0,0 +1,749 @@
+// src/signer/policy.rs
+use async_trait::async_trait;
+use ethers::{
+    abi::{self, ParamType, Token},
+    signers::Signer,
+    types::{transaction::eip2718::TypedTransaction, transaction::eip712::Eip712, Address, Signature, U256},
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tracing::warn;
+
+use super::{BotSigner, SignerError};
+use crate::execution::{decode_execute, Action};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+const APPROVE_SELECTOR: &str = "095ea7b3";
+const EXECUTE_SELECTOR: &str = "09c5eabe";
+// Maker prices are rays; a take owes `amt * max / RAY` DAI at most
+const RAY: u128 = 1_000_000_000_000_000_000_000_000_000;
+
+#[derive(Debug, Error)]
+pub enum PolicyViolation {
+    #[error("no signing policy for strategy {0}")]
+    UnknownStrategy(String),
+    #[error("contract creation is not allowed")]
+    ContractCreation,
+    #[error("target {0:?} is not allowlisted")]
+    TargetNotAllowed(Address),
+    #[error("selector 0x{0} is not allowlisted for target {1:?}")]
+    SelectorNotAllowed(String, Address),
+    #[error("value {value} exceeds {limit} limit of {cap}")]
+    ValueCap { value: U256, cap: U256, limit: &'static str },
+    #[error("{amount} of token {token:?} exceeds {limit} limit of {cap}")]
+    TokenCap { token: Address, amount: U256, cap: U256, limit: &'static str },
+    #[error("typed-data signatures are not allowed")]
+    TypedDataNotAllowed,
+    #[error("executor program is malformed")]
+    MalformedProgram,
+    #[error("executor program pays {0:?}, which is not the signing wallet")]
+    ForeignRecipient(Address),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenLimits {
+    pub per_tx: U256,
+    pub per_day: U256,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StrategyPolicy {
+    /// Allowlisted targets and, for each, the 4-byte selectors (hex, no `0x`) that may be called.
+    pub targets: HashMap<Address, Vec<String>>,
+    pub max_value_per_tx: U256,
+    pub max_value_per_day: U256,
+    #[serde(default)]
+    pub token_limits: HashMap<Address, TokenLimits>,
+    /// Token each market takes payment in where the calldata does not name it: a cToken's
+    /// underlying for `liquidateBorrow`, Comet's base token for `buyCollateral`, DAI for
+    /// `ClipperTaker.take`.
+    #[serde(default)]
+    pub market_tokens: HashMap<Address, Address>,
+    #[serde(default)]
+    pub allow_typed_data: bool,
+}
+
+/// `config/signing_policy.json`, kept apart from strategy configs so strategies cannot widen their own limits.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicyConfig {
+    pub strategies: HashMap<String, StrategyPolicy>,
+}
+
+impl PolicyConfig {
+    /// Fails on a zero-address target, which stands for a contract that was never deployed.
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let config: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if let Some((strategy, _)) = config.strategies.iter().find(|(_, policy)| policy.targets.contains_key(&Address::zero())) {
+            anyhow::bail!("{} signing policy allowlists the zero address", strategy);
+        }
+
+        Ok(config)
+    }
+
+    /// Allowlists `selectors` on `target` for a strategy the file already has limits for; used
+    /// for contracts whose addresses live in their own deployment configs. Fails on the zero
+    /// address, so an undeployed contract stops startup instead of being callable.
+    pub fn allow(&mut self, strategy: &str, target: Address, selectors: &[&str]) -> anyhow::Result<()> {
+        if target.is_zero() {
+            anyhow::bail!("Cannot allowlist the zero address for {}; is the contract deployed?", strategy);
+        }
+        let policy = self.strategies
+            .get_mut(strategy)
+            .ok_or_else(|| PolicyViolation::UnknownStrategy(strategy.to_string()))?;
+        let allowed = policy.targets.entry(target).or_default();
+        for selector in selectors {
+            if !allowed.iter().any(|existing| existing.trim_start_matches("0x").eq_ignore_ascii_case(selector)) {
+                allowed.push(selector.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records the token `market` takes payment in, so calls to it are capped by that token's limits.
+    pub fn set_market_token(&mut self, strategy: &str, market: Address, token: Address) -> anyhow::Result<()> {
+        let policy = self.strategies
+            .get_mut(strategy)
+            .ok_or_else(|| PolicyViolation::UnknownStrategy(strategy.to_string()))?;
+        policy.market_tokens.insert(market, token);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct DailyUsage {
+    day: u64,
+    value: U256,
+    tokens: HashMap<Address, U256>,
+}
+
+/// Checks every transaction against the strategy's policy and tracks daily spend.
+#[derive(Debug)]
+pub struct PolicyEngine {
+    config: PolicyConfig,
+    usage: Mutex<HashMap<String, DailyUsage>>,
+}
+
+impl PolicyEngine {
+    pub fn new(config: PolicyConfig) -> Self {
+        Self {
+            config,
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checks `tx` and, if it is within policy, counts it against today's limits.
+    pub fn authorize(&self, strategy: &str, tx: &TypedTransaction) -> Result<(), PolicyViolation> {
+        let result = self.check_and_record(strategy, tx, current_day());
+        if let Err(violation) = &result {
+            warn!("Refused to sign for {}: {} (to {:?}, value {:?})", strategy, violation, tx.to(), tx.value());
+        }
+        result
+    }
+
+    /// Checks `tx` like `authorize` without counting it, for transactions that are never sent.
+    pub fn check(&self, strategy: &str, tx: &TypedTransaction) -> Result<(), PolicyViolation> {
+        let result = self.evaluate(strategy, tx, current_day(), false);
+        if let Err(violation) = &result {
+            warn!("Policy would refuse {}: {} (to {:?}, value {:?})", strategy, violation, tx.to(), tx.value());
+        }
+        result
+    }
+
+    fn check_and_record(&self, strategy: &str, tx: &TypedTransaction, day: u64) -> Result<(), PolicyViolation> {
+        self.evaluate(strategy, tx, day, true)
+    }
+
+    fn evaluate(&self, strategy: &str, tx: &TypedTransaction, day: u64, record: bool) -> Result<(), PolicyViolation> {
+        let policy = self.config.strategies
+            .get(strategy)
+            .ok_or_else(|| PolicyViolation::UnknownStrategy(strategy.to_string()))?;
+
+        let target = *tx
+            .to()
+            .and_then(|to| to.as_address())
+            .ok_or(PolicyViolation::ContractCreation)?;
+        let selectors = policy.targets.get(&target).ok_or(PolicyViolation::TargetNotAllowed(target))?;
+
+        let data = tx.data().map(|data| data.as_ref()).unwrap_or_default();
+        let selector = hex::encode(data.get(..4).unwrap_or_default());
+        if !selectors.iter().any(|allowed| allowed.trim_start_matches("0x").eq_ignore_ascii_case(&selector)) {
+            return Err(PolicyViolation::SelectorNotAllowed(selector, target));
+        }
+
+        let value = tx.value().copied().unwrap_or_default();
+        if value > policy.max_value_per_tx {
+            return Err(PolicyViolation::ValueCap { value, cap: policy.max_value_per_tx, limit: "per-transaction" });
+        }
+
+        if selector == EXECUTE_SELECTOR {
+            let actions = decode_execute(data).map_err(|_| PolicyViolation::MalformedProgram)?;
+            check_program(policy, &actions, tx.from().copied())?;
+        }
+
+        let spend = token_spend(target, data, &policy.market_tokens);
+        if let Some((token, amount)) = spend {
+            if let Some(limits) = policy.token_limits.get(&token) {
+                if amount > limits.per_tx {
+                    return Err(PolicyViolation::TokenCap { token, amount, cap: limits.per_tx, limit: "per-transaction" });
+                }
+            }
+        }
+
+        let mut usage = self.usage.lock().unwrap();
+        let today = usage.entry(strategy.to_string()).or_default();
+        if today.day != day {
+            *today = DailyUsage { day, ..Default::default() };
+        }
+
+        let value_today = today.value.saturating_add(value);
+        if value_today > policy.max_value_per_day {
+            return Err(PolicyViolation::ValueCap { value: value_today, cap: policy.max_value_per_day, limit: "daily" });
+        }
+
+        // An allowance moves nothing by itself; what the spender later takes is counted when it does
+        if let Some((token, amount)) = spend.filter(|_| selector != APPROVE_SELECTOR) {
+            let token_today = today.tokens.get(&token).copied().unwrap_or_default().saturating_add(amount);
+            if let Some(limits) = policy.token_limits.get(&token) {
+                if token_today > limits.per_day {
+                    return Err(PolicyViolation::TokenCap { token, amount: token_today, cap: limits.per_day, limit: "daily" });
+                }
+            }
+            if record {
+                today.tokens.insert(token, token_today);
+            }
+        }
+        if record {
+            today.value = value_today;
+        }
+
+        Ok(())
+    }
+
+    pub fn allows_typed_data(&self, strategy: &str) -> bool {
+        self.config.strategies.get(strategy).map(|policy| policy.allow_typed_data).unwrap_or(false)
+    }
+}
+
+/// Every contract an executor program calls, approves or liquidates on must be allowlisted for
+/// the strategy, with `Call` data limited to the target's selectors, and everything it pays out
+/// must go to the signing wallet.
+fn check_program(policy: &StrategyPolicy, actions: &[Action], from: Option<Address>) -> Result<(), PolicyViolation> {
+    for action in actions {
+        match action {
+            Action::FlashBorrow { program, .. } => check_program(policy, program, from)?,
+            Action::Call { target, data } => {
+                let selectors = policy.targets.get(target).ok_or(PolicyViolation::TargetNotAllowed(*target))?;
+                let selector = hex::encode(data.get(..4).unwrap_or_default());
+                if !selectors.iter().any(|allowed| allowed.trim_start_matches("0x").eq_ignore_ascii_case(&selector)) {
+                    return Err(PolicyViolation::SelectorNotAllowed(selector, *target));
+                }
+            }
+            Action::Approve { spender: target, .. } | Action::Liquidate { market: target, .. } if !policy.targets.contains_key(target) => {
+                return Err(PolicyViolation::TargetNotAllowed(*target));
+            }
+            Action::Transfer { to, .. } if Some(*to) != from => return Err(PolicyViolation::ForeignRecipient(*to)),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// The token and amount a call can move out of our wallet, or that our contracts put at risk on
+/// its behalf, for the calls we know how to read.
+///
+/// Executor programs count what they pull from the wallet or, failing that, borrow: the wallet
+/// does not pay a loan, but a bad fill loses up to that much of the contract's balance. Compound
+/// `liquidateBorrow`, Comet `buyCollateral` and `ClipperTaker.take` do not name their token, so
+/// it is looked up in `market_tokens`.
+fn token_spend(target: Address, data: &[u8], market_tokens: &HashMap<Address, Address>) -> Option<(Address, U256)> {
+    if data.len() < 4 {
+        return None;
+    }
+    let (selector, args) = data.split_at(4);
+
+    match hex::encode(selector).as_str() {
+        // transfer(address,uint256), approve(address,uint256)
+        "a9059cbb" | "095ea7b3" => {
+            let tokens = abi::decode(&[ParamType::Address, ParamType::Uint(256)], args).ok()?;
+            Some((target, tokens[1].clone().into_uint()?))
+        }
+        // swapExactTokensForTokens, swapExactTokensForETH and their fee-on-transfer variants spend
+        // amountIn; swapTokensForExactTokens and swapTokensForExactETH at most amountInMax
+        selector @ ("38ed1739" | "18cbafe5" | "5c11d795" | "791ac947" | "8803dbee" | "4a25d94a") => {
+            let amount_index = if matches!(selector, "8803dbee" | "4a25d94a") { 1 } else { 0 };
+            let tokens = abi::decode(
+                &[
+                    ParamType::Uint(256),
+                    ParamType::Uint(256),
+                    ParamType::Array(Box::new(ParamType::Address)),
+                    ParamType::Address,
+                    ParamType::Uint(256),
+                ],
+                args,
+            ).ok()?;
+            let token_in = match &tokens[2] {
+                Token::Array(path) => path.first()?.clone().into_address()?,
+                _ => return None,
+            };
+            Some((token_in, tokens[amount_index].clone().into_uint()?))
+        }
+        // Aave liquidationCall(collateral, debt, user, debtToCover, receiveAToken)
+        "00a718a9" => {
+            let tokens = abi::decode(
+                &[ParamType::Address, ParamType::Address, ParamType::Address, ParamType::Uint(256), ParamType::Bool],
+                args,
+            ).ok()?;
+            Some((tokens[1].clone().into_address()?, tokens[3].clone().into_uint()?))
+        }
+        // Compound liquidateBorrow(borrower, repayAmount, cTokenCollateral)
+        "f5e3c462" => {
+            let tokens = abi::decode(&[ParamType::Address, ParamType::Uint(256), ParamType::Address], args).ok()?;
+            Some((*market_tokens.get(&target)?, tokens[1].clone().into_uint()?))
+        }
+        // Comet buyCollateral(asset, minAmount, baseAmount, recipient)
+        "e4e6e779" => {
+            let tokens = abi::decode(&[ParamType::Address, ParamType::Uint(256), ParamType::Uint(256), ParamType::Address], args).ok()?;
+            Some((*market_tokens.get(&target)?, tokens[2].clone().into_uint()?))
+        }
+        // ClipperTaker.take(order): the most DAI the lot can cost, `amt` at price `max`
+        "1bbc0222" => {
+            let fields = vec![
+                ParamType::Address,
+                ParamType::Address,
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Uint(256),
+                ParamType::Address,
+                ParamType::Array(Box::new(ParamType::Address)),
+                ParamType::Uint(256),
+            ];
+            let order = abi::decode(&[ParamType::Tuple(fields)], args).ok()?.into_iter().next()?.into_tuple()?;
+            let (amt, max) = (order[3].clone().into_uint()?, order[4].clone().into_uint()?);
+            Some((*market_tokens.get(&target)?, amt.full_mul(max).checked_div(RAY.into())?.try_into().ok()?))
+        }
+        // MultiActionExecutor.execute(program): the wallet's capital it pulls, else the loan it takes
+        EXECUTE_SELECTOR => {
+            let actions = decode_execute(data).ok()?;
+            actions
+                .iter()
+                .find_map(|action| match action {
+                    Action::Pull { token, amount } => Some((*token, *amount)),
+                    _ => None,
+                })
+                .or_else(|| {
+                    actions.iter().find_map(|action| match action {
+                        Action::FlashBorrow { token, amount, .. } => Some((*token, *amount)),
+                        _ => None,
+                    })
+                })
+        }
+        // PositionUnwinder.unwind(order): the debt repaid, in its underlying
+        "83ca93de" => {
+            let fields = vec![
+                ParamType::Uint(8),
+                ParamType::Address,
+                ParamType::Address,
+                ParamType::Address,
+                ParamType::Address,
+                ParamType::Address,
+                ParamType::Uint(256),
+                ParamType::Bool,
+                ParamType::Bool,
+                ParamType::Address,
+                ParamType::Array(Box::new(ParamType::Address)),
+                ParamType::Uint(256),
+            ];
+            let order = abi::decode(&[ParamType::Tuple(fields)], args).ok()?.into_iter().next()?.into_tuple()?;
+            Some((order[5].clone().into_address()?, order[6].clone().into_uint()?))
+        }
+        _ => None,
+    }
+}
+
+fn current_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or_default()
+}
+
+/// A strategy's view of the signer: every signature goes through the policy engine first.
+#[derive(Debug, Clone)]
+pub struct PolicySigner {
+    inner: BotSigner,
+    engine: Arc<PolicyEngine>,
+    strategy: String,
+}
+
+impl PolicySigner {
+    pub fn new(inner: BotSigner, engine: Arc<PolicyEngine>, strategy: impl Into<String>) -> Self {
+        Self {
+            inner,
+            engine,
+            strategy: strategy.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for PolicySigner {
+    type Error = SignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        if !self.engine.allows_typed_data(&self.strategy) {
+            warn!("Refused to sign message for {}", self.strategy);
+            return Err(PolicyViolation::TypedDataNotAllowed.into());
+        }
+        self.inner.sign_message(message).await
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        self.engine.authorize(&self.strategy, tx)?;
+        self.inner.sign_transaction(tx).await
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        if !self.engine.allows_typed_data(&self.strategy) {
+            warn!("Refused to sign typed data for {}", self.strategy);
+            return Err(PolicyViolation::TypedDataNotAllowed.into());
+        }
+        self.inner.sign_typed_data(payload).await
+    }
+
+    fn address(&self) -> Address {
+        self.inner.address()
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.inner.chain_id()
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        Self {
+            inner: self.inner.with_chain_id(chain_id),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::TransactionRequest;
+
+    fn engine(router: Address) -> PolicyEngine {
+        let policy = StrategyPolicy {
+            targets: HashMap::from([(router, vec!["7ff36ab5".to_string()])]),
+            max_value_per_tx: U256::from(10),
+            max_value_per_day: U256::from(15),
+            token_limits: HashMap::new(),
+            market_tokens: HashMap::new(),
+            allow_typed_data: false,
+        };
+        PolicyEngine::new(PolicyConfig {
+            strategies: HashMap::from([("arbitrage".to_string(), policy)]),
+        })
+    }
+
+    fn swap(to: Address, value: u64) -> TypedTransaction {
+        TransactionRequest::new()
+            .to(to)
+            .value(value)
+            .data(hex::decode("7ff36ab5").unwrap())
+            .into()
+    }
+
+    #[test]
+    fn test_rejects_unknown_target_and_selector() {
+        let router = Address::from_low_u64_be(1);
+        let engine = engine(router);
+
+        assert!(matches!(
+            engine.check_and_record("arbitrage", &swap(Address::from_low_u64_be(2), 1), 0),
+            Err(PolicyViolation::TargetNotAllowed(_))
+        ));
+
+        let approve: TypedTransaction = TransactionRequest::new().to(router).data(hex::decode("095ea7b3").unwrap()).into();
+        assert!(matches!(
+            engine.check_and_record("arbitrage", &approve, 0),
+            Err(PolicyViolation::SelectorNotAllowed(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_daily_value_cap_resets_next_day() {
+        let router = Address::from_low_u64_be(1);
+        let engine = engine(router);
+
+        assert!(engine.check_and_record("arbitrage", &swap(router, 10), 0).is_ok());
+        assert!(matches!(
+            engine.check_and_record("arbitrage", &swap(router, 10), 0),
+            Err(PolicyViolation::ValueCap { limit: "daily", .. })
+        ));
+        assert!(engine.check_and_record("arbitrage", &swap(router, 10), 1).is_ok());
+    }
+
+    #[test]
+    fn test_exact_output_swaps_spend_amount_in_max() {
+        let (router, token_in) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let data = crate::execution::swap::encode_swap_tokens_for_exact_tokens(
+            U256::from(5),
+            U256::from(700),
+            vec![token_in, Address::from_low_u64_be(3)],
+            router,
+            U256::zero(),
+        )
+        .unwrap();
+
+        assert_eq!(token_spend(router, &data, &HashMap::new()), Some((token_in, U256::from(700))));
+    }
+
+    fn call(to: &str, signature: &str, args: &[Token]) -> TypedTransaction {
+        let mut data = ethers::utils::id(signature).to_vec();
+        data.extend(abi::encode(args));
+        TransactionRequest::new().to(to.parse::<Address>().unwrap()).data(data).into()
+    }
+
+    fn execute(to: &str, actions: &[Action]) -> TypedTransaction {
+        crate::execution::MultiActionExecutor::new(to.parse().unwrap()).execute_tx(actions).unwrap()
+    }
+
+    fn address(s: &str) -> Token {
+        Token::Address(s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_shipped_policy_authorizes_every_strategy() {
+        let config_path = |name: &str| format!("{}/config/{}", env!("CARGO_MANIFEST_DIR"), name);
+        let mut policy = PolicyConfig::load(&config_path("signing_policy.json")).unwrap();
+        crate::lending::AaveMarketsConfig::load(&config_path("aave_markets.json"))
+            .unwrap()
+            .register(
+                &mut crate::lending::BorrowerIndexConfig::load(&config_path("borrower_index.json")).unwrap(),
+                &mut crate::execution::ApprovalsConfig::load(&config_path("approvals_config.json")).unwrap(),
+                &mut policy,
+            )
+            .unwrap();
+        // Stand-ins for our own deployments, which the shipped configs leave unset
+        let (executor, taker, unwinder) = (
+            "0x00000000000000000000000000000000000000e1",
+            "0x00000000000000000000000000000000000000e2",
+            "0x00000000000000000000000000000000000000e3",
+        );
+        let mut approvals = crate::execution::ApprovalsConfig::load(&config_path("approvals_config.json")).unwrap();
+        crate::execution::ExecutorConfig { executor: executor.parse().unwrap() }.register(&mut approvals, &mut policy).unwrap();
+        let mut clipper = crate::lending::ClipperConfig::load(&config_path("clipper_auctions.json")).unwrap();
+        clipper.taker = taker.parse().unwrap();
+        clipper.register(&mut policy).unwrap();
+        let mut protection = crate::lending::ProtectionConfig::load(&config_path("self_protection.json")).unwrap();
+        protection.unwinder = unwinder.parse().unwrap();
+        protection.register(&mut policy).unwrap();
+        let engine = PolicyEngine::new(policy);
+        let (weth, usdc) = ("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2", "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        let placeholder = "0x0000000000000000000000000000000000000000";
+        let user = address("0x0000000000000000000000000000000000000001");
+        let take = Token::Tuple(vec![
+            address("0xc67963a226eddd77B91aD8c421630A1b0AdFF270"),
+            address(placeholder),
+            Token::Uint(1.into()),
+            Token::Uint(U256::exp10(18)),
+            Token::Uint(U256::exp10(27) * 2_000),
+            address(placeholder),
+            Token::Array(vec![]),
+            Token::Uint(0.into()),
+        ]);
+        let order = Token::Tuple(vec![
+            Token::Uint(0.into()),
+            address(placeholder),
+            user.clone(),
+            address(weth),
+            address(weth),
+            address(usdc),
+            Token::Uint(1_000.into()),
+            Token::Bool(false),
+            Token::Bool(false),
+            address(placeholder),
+            Token::Array(vec![]),
+            Token::Uint(0.into()),
+        ]);
+
+        let transactions = [
+            ("arbitrage", call(usdc, "approve(address,uint256)", &[address(executor), Token::Uint(1_000.into())])),
+            ("arbitrage", execute(executor, &[Action::Pull { token: usdc.parse().unwrap(), amount: 1_000.into() }])),
+            ("liquidation", call("0x87870Bca3F3fD6335C3F4ce8392D69350B4fA4E2", "liquidationCall(address,address,address,uint256,bool)", &[address(weth), address(usdc), user.clone(), Token::Uint(1_000.into()), Token::Bool(false)])),
+            ("liquidation", call("0x39AA39c021dfbaE8faC545936693aC917d5E7563", "liquidateBorrow(address,uint256,address)", &[user.clone(), Token::Uint(1_000.into()), address(placeholder)])),
+            ("liquidation", call("0xc3d688B66703497DAA19211EEdff47f25384cdc3", "absorb(address,address[])", &[address(placeholder), Token::Array(vec![user.clone()])])),
+            ("liquidation", call("0xc67963a226eddd77B91aD8c421630A1b0AdFF270", "redo(uint256,address)", &[Token::Uint(1.into()), address(placeholder)])),
+            ("liquidation", call("0xc3d688B66703497DAA19211EEdff47f25384cdc3", "buyCollateral(address,uint256,uint256,address)", &[address(weth), Token::Uint(1.into()), Token::Uint(1_000.into()), user.clone()])),
+            ("liquidation", call(taker, "take((address,address,uint256,uint256,uint256,address,address[],uint256))", &[take])),
+            ("liquidation", execute(executor, &[Action::CheckMinBalance { token: weth.parse().unwrap(), minimum: 0.into() }])),
+            ("self_protection", call(unwinder, "unwind((uint8,address,address,address,address,address,uint256,bool,bool,address,address[],uint256))", &[order])),
+            ("approvals", call(weth, "approve(address,uint256)", &[address(placeholder), Token::Uint(0.into())])),
+            ("approvals", call("0x000000000022D473030F116dDEE9F6B43aC78BA3", "lockdown((address,address)[])", &[Token::Array(vec![Token::Tuple(vec![address(weth), address(placeholder)])])])),
+        ];
+        for (strategy, tx) in &transactions {
+            assert!(engine.check_and_record(strategy, tx, 0).is_ok(), "{} refused {:?}", strategy, tx.data());
+        }
+
+        // Liquidation paths are capped in the token they pay with: 10k USDC a transaction
+        let repay = call("0x39AA39c021dfbaE8faC545936693aC917d5E7563", "liquidateBorrow(address,uint256,address)", &[user.clone(), Token::Uint(10_001_000_000u64.into()), address(placeholder)]);
+        assert!(matches!(engine.check_and_record("liquidation", &repay, 0), Err(PolicyViolation::TokenCap { limit: "per-transaction", .. })));
+        let buy = call("0xc3d688B66703497DAA19211EEdff47f25384cdc3", "buyCollateral(address,uint256,uint256,address)", &[address(weth), Token::Uint(1.into()), Token::Uint(10_001_000_000u64.into()), user.clone()]);
+        assert!(matches!(engine.check_and_record("liquidation", &buy, 0), Err(PolicyViolation::TokenCap { limit: "per-transaction", .. })));
+
+        // Revocations only
+        let approve = call(weth, "approve(address,uint256)", &[address(placeholder), Token::Uint(1.into())]);
+        assert!(matches!(engine.check_and_record("approvals", &approve, 0), Err(PolicyViolation::TokenCap { .. })));
+    }
+
+    #[test]
+    fn test_executor_programs_count_the_capital_at_risk() {
+        let (token, other) = (Address::from_low_u64_be(7), Address::from_low_u64_be(8));
+        let quote = crate::execution::FlashLoanQuote {
+            source: crate::execution::FlashLoanSource::Balancer,
+            lender: Address::zero(),
+            token,
+            amount: U256::from(5_000),
+            fee: U256::zero(),
+        };
+        let borrow = Action::flash_borrow(&quote, vec![]);
+        let transfer = Action::Transfer { token: other, to: Address::zero(), amount: crate::execution::actions::BALANCE };
+
+        let flash = execute("0x0000000000000000000000000000000000000001", &[borrow.clone(), transfer.clone()]);
+        assert_eq!(token_spend(Address::zero(), flash.data().unwrap(), &HashMap::new()), Some((token, U256::from(5_000))));
+
+        // Wallet capital the program pulls comes first
+        let pulled = execute("0x0000000000000000000000000000000000000001", &[borrow, Action::Pull { token: other, amount: U256::from(700) }]);
+        assert_eq!(token_spend(Address::zero(), pulled.data().unwrap(), &HashMap::new()), Some((other, U256::from(700))));
+        let neither = execute("0x0000000000000000000000000000000000000001", &[transfer]);
+        assert_eq!(token_spend(Address::zero(), neither.data().unwrap(), &HashMap::new()), None);
+    }
+
+    #[test]
+    fn test_takes_are_capped_at_their_most_expensive_fill() {
+        let (taker, dai) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let order = Token::Tuple(vec![
+            Token::Address(Address::zero()),
+            Token::Address(Address::zero()),
+            Token::Uint(1.into()),
+            Token::Uint(U256::exp10(18) * 3),
+            Token::Uint(U256::exp10(27) * 1_500),
+            Token::Address(Address::zero()),
+            Token::Array(vec![]),
+            Token::Uint(0.into()),
+        ]);
+        let mut data = hex::decode("1bbc0222").unwrap();
+        data.extend(abi::encode(&[order]));
+
+        assert_eq!(token_spend(taker, &data, &HashMap::from([(taker, dai)])), Some((dai, U256::exp10(18) * 4_500)));
+        // A market we have no token for is not read
+        assert_eq!(token_spend(taker, &data, &HashMap::new()), None);
+    }
+
+    #[test]
+    fn test_approvals_are_capped_but_not_spent() {
+        let (token, spender) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        let policy = StrategyPolicy {
+            targets: HashMap::from([(token, vec!["095ea7b3".to_string()])]),
+            max_value_per_tx: U256::zero(),
+            max_value_per_day: U256::zero(),
+            token_limits: HashMap::from([(token, TokenLimits { per_tx: U256::from(100), per_day: U256::from(150) })]),
+            market_tokens: HashMap::new(),
+            allow_typed_data: false,
+        };
+        let engine = PolicyEngine::new(PolicyConfig {
+            strategies: HashMap::from([("arbitrage".to_string(), policy)]),
+        });
+        let approve = |amount: u64| -> TypedTransaction {
+            let mut data = hex::decode("095ea7b3").unwrap();
+            data.extend(abi::encode(&[Token::Address(spender), Token::Uint(amount.into())]));
+            TransactionRequest::new().to(token).data(data).into()
+        };
+
+        for _ in 0..3 {
+            assert!(engine.check_and_record("arbitrage", &approve(100), 0).is_ok());
+        }
+        assert!(matches!(
+            engine.check_and_record("arbitrage", &approve(101), 0),
+            Err(PolicyViolation::TokenCap { limit: "per-transaction", .. })
+        ));
+    }
+
+    #[test]
+    fn test_executor_programs_only_reach_allowlisted_contracts_and_pay_the_wallet() {
+        let (executor, router, wallet) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2), Address::from_low_u64_be(3));
+        let token = Address::from_low_u64_be(4);
+        let policy = StrategyPolicy {
+            targets: HashMap::from([(executor, vec!["09c5eabe".to_string()]), (router, vec!["38ed1739".to_string()])]),
+            max_value_per_tx: U256::zero(),
+            max_value_per_day: U256::zero(),
+            token_limits: HashMap::new(),
+            market_tokens: HashMap::new(),
+            allow_typed_data: false,
+        };
+        let engine = PolicyEngine::new(PolicyConfig {
+            strategies: HashMap::from([("arbitrage".to_string(), policy)]),
+        });
+        let run = |actions: &[Action]| {
+            let mut tx = crate::execution::MultiActionExecutor::new(executor).execute_tx(actions).unwrap();
+            tx.set_from(wallet);
+            engine.check("arbitrage", &tx)
+        };
+        let swap = Action::Call { target: router, data: hex::decode("38ed1739").unwrap().into() };
+        let payout = Action::Transfer { token, to: wallet, amount: crate::execution::actions::BALANCE };
+
+        assert!(run(&[Action::Approve { token, spender: router, amount: U256::one() }, swap.clone(), payout.clone()]).is_ok());
+        assert!(matches!(
+            run(&[Action::Call { target: router, data: hex::decode("095ea7b3").unwrap().into() }]),
+            Err(PolicyViolation::SelectorNotAllowed(_, _))
+        ));
+        assert!(matches!(
+            run(&[Action::Approve { token, spender: Address::from_low_u64_be(5), amount: U256::MAX }]),
+            Err(PolicyViolation::TargetNotAllowed(_))
+        ));
+        // Checked inside loans too
+        let quote = crate::execution::FlashLoanQuote {
+            source: crate::execution::FlashLoanSource::Balancer,
+            lender: Address::zero(),
+            token,
+            amount: U256::from(5_000),
+            fee: U256::zero(),
+        };
+        let drain = Action::Transfer { token, to: Address::from_low_u64_be(5), amount: crate::execution::actions::BALANCE };
+        assert!(matches!(
+            run(&[Action::flash_borrow(&quote, vec![swap, drain]), payout]),
+            Err(PolicyViolation::ForeignRecipient(_))
+        ));
+    }
+
+    #[test]
+    fn test_zero_address_targets_are_refused() {
+        let json = r#"{"strategies": {"arbitrage": {"targets": {"0x0000000000000000000000000000000000000000": ["09c5eabe"]}, "max_value_per_tx": "0x0", "max_value_per_day": "0x0"}}}"#;
+        let path = std::env::temp_dir().join(format!("zero-target-policy-{}.json", std::process::id()));
+        std::fs::write(&path, json).unwrap();
+        let loaded = PolicyConfig::load(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.is_err());
+
+        let mut config: PolicyConfig = serde_json::from_str(json).unwrap();
+        assert!(config.allow("arbitrage", Address::zero(), &["09c5eabe"]).is_err());
+        assert!(config.allow("arbitrage", Address::from_low_u64_be(1), &["09c5eabe"]).is_ok());
+    }
+
+    #[test]
+    fn test_check_does_not_spend_daily_limits() {
+        let router = Address::from_low_u64_be(1);
+        let engine = engine(router);
+
+        for _ in 0..3 {
+            assert!(engine.evaluate("arbitrage", &swap(router, 10), 0, false).is_ok());
+        }
+        assert!(engine.check_and_record("arbitrage", &swap(router, 10), 0).is_ok());
+    }
+}
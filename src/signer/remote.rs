@@ -0,0 +1,105 @@
+// src/signer/remote.rs
+use ethers::types::{Address, Signature, H256};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+
+use super::SignerError;
+
+#[derive(Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    Address,
+    SignHash { hash: H256 },
+}
+
+#[derive(Deserialize)]
+struct Response {
+    address: Option<Address>,
+    signature: Option<Signature>,
+    error: Option<String>,
+}
+
+/// Signs through a separate process listening on a local Unix socket.
+///
+/// The protocol is one JSON object per line in each direction, so the signing
+/// process can keep keys in a hardware module or another user's address space.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    socket_path: PathBuf,
+    address: Address,
+}
+
+impl RemoteSigner {
+    pub async fn connect(socket_path: impl Into<PathBuf>) -> Result<Self, SignerError> {
+        let socket_path = socket_path.into();
+        let response = request(&socket_path, &Request::Address).await?;
+        let address = response
+            .address
+            .ok_or_else(|| SignerError::Remote("Signer did not report an address".to_string()))?;
+
+        Ok(Self { socket_path, address })
+    }
+
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Returns a signature whose `v` is `27` or `28`, like `LocalWallet::sign_hash`.
+    pub async fn sign_hash(&self, hash: H256) -> Result<Signature, SignerError> {
+        let response = request(&self.socket_path, &Request::SignHash { hash }).await?;
+        let mut signature = response
+            .signature
+            .ok_or_else(|| SignerError::Remote("Signer returned no signature".to_string()))?;
+        signature.v = normalize_v(signature.v)?;
+
+        // Never trust the remote side to have signed what we asked for
+        let recovered = signature.recover(hash).map_err(|e| SignerError::Remote(e.to_string()))?;
+        if recovered != self.address {
+            return Err(SignerError::Remote(format!("Signature recovers to {:?}, expected {:?}", recovered, self.address)));
+        }
+
+        Ok(signature)
+    }
+}
+
+/// Signers differ on whether the recovery id is reported as `0`/`1` or `27`/`28`.
+fn normalize_v(v: u64) -> Result<u64, SignerError> {
+    match v {
+        0 | 1 => Ok(v + 27),
+        27 | 28 => Ok(v),
+        other => Err(SignerError::Remote(format!("Signer returned unexpected v {}", other))),
+    }
+}
+
+async fn request(socket_path: &PathBuf, request: &Request) -> Result<Response, SignerError> {
+    let mut stream = UnixStream::connect(socket_path).await?;
+    let mut line = serde_json::to_vec(request)?;
+    line.push(b'\n');
+    stream.write_all(&line).await?;
+
+    let mut reader = BufReader::new(stream);
+    let mut reply = String::new();
+    reader.read_line(&mut reply).await?;
+
+    let response: Response = serde_json::from_str(&reply)?;
+    if let Some(error) = response.error {
+        return Err(SignerError::Remote(error));
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_v_accepts_both_recovery_id_forms() {
+        assert_eq!(normalize_v(0).unwrap(), 27);
+        assert_eq!(normalize_v(1).unwrap(), 28);
+        assert_eq!(normalize_v(28).unwrap(), 28);
+        assert!(normalize_v(37).is_err());
+    }
+}
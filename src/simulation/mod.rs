@@ -0,0 +1,6 @@
+// src/simulation/mod.rs
+pub mod node_db;
+pub mod simulator;
+
+pub use node_db::NodeDb;
+pub use simulator::{EvmSimulator, Simulation};
@@ -0,0 +1,158 @@
+// src/simulation/node_db.rs
+use ethers::{
+    providers::{Middleware, Provider, ProviderError, Ws},
+    types::{Address, BlockId, H256, U256},
+    utils::keccak256,
+};
+use revm::{
+    primitives::{AccountInfo, Address as EvmAddress, Bytecode, B256, U256 as EvmU256},
+    DatabaseRef,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::runtime::Handle;
+
+/// What has been read from the node for one block, shared by every simulation on it.
+#[derive(Default)]
+struct NodeCache {
+    accounts: RwLock<HashMap<EvmAddress, AccountInfo>>,
+    storage: RwLock<HashMap<(EvmAddress, EvmU256), EvmU256>>,
+    block_hashes: RwLock<HashMap<EvmU256, B256>>,
+}
+
+/// Reads accounts, storage and block hashes from the node as they stood after one block.
+///
+/// revm's database interface is synchronous, so each read blocks the calling worker thread on the
+/// request; this needs the multi-threaded runtime. Every account and slot is fetched once and
+/// cached for clones of the same `NodeDb`. The cache is only locked to look a value up or insert
+/// it, never while a request is in flight, so simulations on the same block run in parallel.
+#[derive(Clone)]
+pub struct NodeDb {
+    provider: Arc<Provider<Ws>>,
+    block: BlockId,
+    runtime: Handle,
+    cache: Arc<NodeCache>,
+}
+
+impl NodeDb {
+    pub fn new(provider: Arc<Provider<Ws>>, block_number: u64) -> Self {
+        Self {
+            provider,
+            block: block_number.into(),
+            runtime: Handle::current(),
+            cache: Arc::new(NodeCache::default()),
+        }
+    }
+
+    /// Accounts and storage slots fetched so far.
+    pub fn cached(&self) -> (usize, usize) {
+        (read(&self.cache.accounts).len(), read(&self.cache.storage).len())
+    }
+
+    fn block_on<F: Future>(&self, request: F) -> F::Output {
+        tokio::task::block_in_place(|| self.runtime.block_on(request))
+    }
+}
+
+// The maps only ever gain complete entries, so one left behind by a panicking thread is still valid
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl DatabaseRef for NodeDb {
+    type Error = ProviderError;
+
+    fn basic_ref(&self, address: EvmAddress) -> Result<Option<AccountInfo>, Self::Error> {
+        if let Some(info) = read(&self.cache.accounts).get(&address) {
+            return Ok(Some(info.clone()));
+        }
+
+        let account = from_evm_address(address);
+        let (balance, nonce, code) = self.block_on(async {
+            futures::try_join!(
+                self.provider.get_balance(account, Some(self.block)),
+                self.provider.get_transaction_count(account, Some(self.block)),
+                self.provider.get_code(account, Some(self.block)),
+            )
+        })?;
+        let info = AccountInfo::new(
+            to_evm_u256(balance),
+            nonce.as_u64(),
+            B256::from(keccak256(&code)),
+            Bytecode::new_raw(code.0.into()),
+        );
+
+        write(&self.cache.accounts).insert(address, info.clone());
+        Ok(Some(info))
+    }
+
+    fn code_by_hash_ref(&self, _code_hash: B256) -> Result<Bytecode, Self::Error> {
+        // `basic_ref` returns the code with the account, so revm never asks for it by hash
+        Err(ProviderError::CustomError("Contract code is loaded with its account".to_string()))
+    }
+
+    fn storage_ref(&self, address: EvmAddress, index: EvmU256) -> Result<EvmU256, Self::Error> {
+        if let Some(value) = read(&self.cache.storage).get(&(address, index)) {
+            return Ok(*value);
+        }
+
+        let slot = H256(index.to_be_bytes());
+        let value = self.block_on(self.provider.get_storage_at(from_evm_address(address), slot, Some(self.block)))?;
+        let value = to_evm_u256(U256::from_big_endian(value.as_bytes()));
+
+        write(&self.cache.storage).insert((address, index), value);
+        Ok(value)
+    }
+
+    fn block_hash_ref(&self, number: EvmU256) -> Result<B256, Self::Error> {
+        if let Some(hash) = read(&self.cache.block_hashes).get(&number) {
+            return Ok(*hash);
+        }
+
+        let block_number = from_evm_u256(number).as_u64();
+        let block = self
+            .block_on(self.provider.get_block(block_number))?
+            .ok_or_else(|| ProviderError::CustomError(format!("Block {} not found", block_number)))?;
+        let hash = B256::from(block.hash.unwrap_or_default().0);
+
+        write(&self.cache.block_hashes).insert(number, hash);
+        Ok(hash)
+    }
+}
+
+pub fn to_evm_address(address: Address) -> EvmAddress {
+    EvmAddress::from(address.0)
+}
+
+pub fn from_evm_address(address: EvmAddress) -> Address {
+    Address::from(address.into_array())
+}
+
+// Both are four little-endian u64 limbs
+pub fn to_evm_u256(value: U256) -> EvmU256 {
+    EvmU256::from_limbs(value.0)
+}
+
+pub fn from_evm_u256(value: EvmU256) -> U256 {
+    U256(value.into_limbs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions_round_trip() {
+        let address = Address::from_low_u64_be(0xdead_beef);
+        let value = U256::MAX - U256::exp10(30);
+
+        assert_eq!(from_evm_address(to_evm_address(address)), address);
+        assert_eq!(from_evm_u256(to_evm_u256(value)), value);
+        assert_eq!(to_evm_u256(U256::from(7)), EvmU256::from(7u64));
+    }
+}
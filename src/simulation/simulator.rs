@@ -0,0 +1,295 @@
+// src/simulation/simulator.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::{Middleware, Provider, Ws},
+    types::{transaction::eip2718::TypedTransaction, Address, Block, Bytes, Log, H256, U256},
+};
+use revm::{
+    primitives::{BlockEnv, ExecutionResult, Output, SpecId, TxKind, B256},
+    DatabaseRef, Evm,
+};
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+use super::node_db::{from_evm_address, to_evm_address, to_evm_u256, NodeDb};
+
+/// Fork whose rules simulations follow. Has to move with mainnet, or opcodes and gas costs
+/// introduced since stop matching what the chain executes.
+pub const SPEC_ID: SpecId = SpecId::CANCUN;
+
+/// Outcome of one simulated transaction.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// Block whose post-state the transaction ran on.
+    pub block_number: u64,
+    pub success: bool,
+    pub gas_used: u64,
+    /// Return data, or the revert data when the call reverted.
+    pub output: Bytes,
+    pub logs: Vec<Log>,
+    /// Why the EVM halted, e.g. out of gas, when it did not return or revert.
+    pub halt_reason: Option<String>,
+}
+
+struct BlockState {
+    block_number: u64,
+    env: BlockEnv,
+    db: NodeDb,
+}
+
+/// Runs transactions in an embedded EVM against the state after the latest block, the way
+/// `eth_call` on that block would.
+///
+/// Accounts and storage are fetched from the node the first time a simulation touches them and
+/// kept until the next block, so repeated simulations of the same contracts cost no round trips.
+/// Simulations never see each other's writes.
+pub struct EvmSimulator {
+    provider: Arc<Provider<Ws>>,
+    chain_id: u64,
+    state: RwLock<Option<Arc<BlockState>>>,
+}
+
+impl EvmSimulator {
+    /// State is read through `NodeDb`, which waits on the node with `tokio::task::block_in_place`:
+    /// `simulate` panics when called from a current-thread runtime, so run the bot (and any test
+    /// that simulates against a node) on the multi-threaded one.
+    pub async fn connect(provider: Arc<Provider<Ws>>) -> Result<Self> {
+        let chain_id = provider.get_chainid().await?.as_u64();
+
+        Ok(Self {
+            provider,
+            chain_id,
+            state: RwLock::new(None),
+        })
+    }
+
+    /// Moves simulations onto the state after `block`, dropping what was cached for the previous one.
+    pub fn set_block(&self, block: &Block<H256>) -> Result<()> {
+        let block_number = block.number.ok_or_else(|| anyhow!("Block has no number"))?.as_u64();
+        if self.current()?.map(|state| state.block_number) == Some(block_number) {
+            return Ok(());
+        }
+
+        let mut env = BlockEnv {
+            number: to_evm_u256(block_number.into()),
+            coinbase: to_evm_address(block.author.unwrap_or_default()),
+            timestamp: to_evm_u256(block.timestamp),
+            gas_limit: to_evm_u256(block.gas_limit),
+            basefee: to_evm_u256(block.base_fee_per_gas.unwrap_or_default()),
+            difficulty: to_evm_u256(block.difficulty),
+            prevrandao: block.mix_hash.map(|hash| B256::from(hash.0)),
+            ..Default::default()
+        };
+        if let Some(excess_blob_gas) = block.excess_blob_gas {
+            env.set_blob_excess_gas_and_price(excess_blob_gas.as_u64());
+        }
+
+        let state = BlockState {
+            block_number,
+            env,
+            db: NodeDb::new(self.provider.clone(), block_number),
+        };
+        *self.state.write().map_err(|_| anyhow!("Simulator state poisoned"))? = Some(Arc::new(state));
+        debug!("Simulating against block {}", block_number);
+
+        Ok(())
+    }
+
+    /// Runs `tx` on the current block's state. Blocks the calling thread while state the cache
+    /// does not hold yet is fetched.
+    pub fn simulate(&self, tx: &TypedTransaction) -> Result<Simulation> {
+        let state = self.current()?.ok_or_else(|| anyhow!("Simulator has no block yet"))?;
+        transact(state.db.clone(), self.chain_id, state.env.clone(), tx)
+    }
+
+    /// Simulates a call of `data` on `to` from `from`.
+    pub fn call(&self, from: Address, to: Address, data: Bytes, value: U256) -> Result<Simulation> {
+        let tx: TypedTransaction = ethers::types::TransactionRequest::new()
+            .from(from)
+            .to(to)
+            .data(data)
+            .value(value)
+            .into();
+        self.simulate(&tx)
+    }
+
+    /// Block simulations currently run on, once `set_block` has been called.
+    pub fn block_number(&self) -> Option<u64> {
+        self.current().ok().flatten().map(|state| state.block_number)
+    }
+
+    /// Accounts and storage slots cached for the current block.
+    pub fn cached(&self) -> (usize, usize) {
+        match self.current() {
+            Ok(Some(state)) => state.db.cached(),
+            _ => (0, 0),
+        }
+    }
+
+    // Only held long enough to clone the handle, so no simulation waits on another's fetches
+    fn current(&self) -> Result<Option<Arc<BlockState>>> {
+        Ok(self.state.read().map_err(|_| anyhow!("Simulator state poisoned"))?.clone())
+    }
+}
+
+/// Executes `tx` on `db` in `block` without committing anything.
+fn transact<DB>(db: DB, chain_id: u64, block: BlockEnv, tx: &TypedTransaction) -> Result<Simulation>
+where
+    DB: DatabaseRef,
+    DB::Error: Debug,
+{
+    let block_number = block.number.saturating_to::<u64>();
+    // Capped at the block's gas limit, which is also the default
+    let block_gas_limit = U256::from(block.gas_limit.saturating_to::<u64>());
+    let gas_limit = tx.gas().map_or(block_gas_limit, |gas| (*gas).min(block_gas_limit)).as_u64();
+
+    let mut evm = Evm::builder()
+        .with_ref_db(db)
+        .with_spec_id(SPEC_ID)
+        .modify_cfg_env(|cfg| {
+            cfg.chain_id = chain_id;
+            // As `eth_call` does: no fee or balance requirement on the caller, and any caller may send
+            cfg.disable_base_fee = true;
+            cfg.disable_balance_check = true;
+            cfg.disable_eip3607 = true;
+        })
+        .with_block_env(block)
+        .modify_tx_env(|env| {
+            env.caller = to_evm_address(tx.from().copied().unwrap_or_default());
+            env.transact_to = match tx.to().and_then(|to| to.as_address()) {
+                Some(to) => TxKind::Call(to_evm_address(*to)),
+                None => TxKind::Create,
+            };
+            env.data = tx.data().cloned().unwrap_or_default().0.into();
+            env.value = to_evm_u256(tx.value().copied().unwrap_or_default());
+            env.gas_limit = gas_limit;
+            env.gas_price = Default::default();
+            env.gas_priority_fee = None;
+            // Any nonce goes, as in `eth_call`
+            env.nonce = None;
+        })
+        .build();
+
+    let result = evm.transact().map_err(|e| anyhow!("Simulation failed: {:?}", e))?.result;
+
+    Ok(match result {
+        ExecutionResult::Success { gas_used, output, logs, .. } => Simulation {
+            block_number,
+            success: true,
+            gas_used,
+            output: match output {
+                Output::Call(data) => data.0.into(),
+                Output::Create(data, _) => data.0.into(),
+            },
+            logs: logs
+                .into_iter()
+                .map(|log| Log {
+                    address: from_evm_address(log.address),
+                    topics: log.data.topics().iter().map(|topic| H256(topic.0)).collect(),
+                    data: log.data.data.0.into(),
+                    ..Default::default()
+                })
+                .collect(),
+            halt_reason: None,
+        },
+        ExecutionResult::Revert { gas_used, output } => Simulation {
+            block_number,
+            success: false,
+            gas_used,
+            output: output.0.into(),
+            logs: Vec::new(),
+            halt_reason: None,
+        },
+        ExecutionResult::Halt { reason, gas_used } => Simulation {
+            block_number,
+            success: false,
+            gas_used,
+            output: Bytes::default(),
+            logs: Vec::new(),
+            halt_reason: Some(format!("{:?}", reason)),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::TransactionRequest;
+    use revm::{
+        db::{CacheDB, EmptyDB},
+        primitives::{AccountInfo, Bytecode},
+    };
+
+    #[test]
+    fn test_runs_cancun_transient_storage() {
+        // TSTORE 42 at key 0, TLOAD it back and return it as one word
+        let code: Bytes = "0x602a60005d60005c5f5260205ff3".parse().unwrap();
+        let contract = Address::from_low_u64_be(0xc0de);
+        let code = Bytecode::new_raw(code.0.into());
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(to_evm_address(contract), AccountInfo::new(Default::default(), 0, code.hash_slow(), code));
+
+        let block = BlockEnv {
+            gas_limit: to_evm_u256(30_000_000u64.into()),
+            ..Default::default()
+        };
+        let tx: TypedTransaction = TransactionRequest::new().from(Address::from_low_u64_be(1)).to(contract).into();
+        let simulation = transact(db, 1, block, &tx).unwrap();
+
+        assert!(simulation.success, "halted: {:?}", simulation.halt_reason);
+        assert_eq!(U256::from_big_endian(&simulation.output), U256::from(42));
+        // Intrinsic cost, TSTORE and TLOAD, four PUSH1s, two PUSH0s, and MSTORE with one word of memory
+        assert_eq!(simulation.gas_used, 21_000 + 2 * 100 + 4 * 3 + 2 * 2 + 3 + 3);
+    }
+
+    fn calldata(signature: &str, args: &[ethers::abi::Token]) -> Bytes {
+        let mut data = ethers::utils::id(signature).to_vec();
+        data.extend(ethers::abi::encode(args));
+        data.into()
+    }
+
+    // Multi-threaded, as `NodeDb` blocks in place while it fetches
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore = "needs FORK_URL, the websocket endpoint of a mainnet node"]
+    async fn test_matches_the_node_on_mainnet_state() {
+        use ethers::abi::Token;
+        use ethers::types::{BlockId, BlockNumber};
+
+        let provider = Arc::new(Provider::<Ws>::connect(std::env::var("FORK_URL").unwrap()).await.unwrap());
+        let block = provider.get_block(BlockNumber::Latest).await.unwrap().unwrap();
+        let at = Some(BlockId::from(block.number.unwrap()));
+        let simulator = EvmSimulator::connect(provider.clone()).await.unwrap();
+        simulator.set_block(&block).unwrap();
+
+        let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap();
+        let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap();
+        let router: Address = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D".parse().unwrap();
+        let call = |to: Address, data: Bytes| -> TypedTransaction {
+            TransactionRequest::new().from(Address::from_low_u64_be(1)).to(to).data(data).into()
+        };
+        let balance = call(weth, calldata("balanceOf(address)", &[Token::Address(router)]));
+        let quote = call(
+            router,
+            calldata(
+                "getAmountsOut(uint256,address[])",
+                &[Token::Uint(U256::exp10(18)), Token::Array(vec![Token::Address(weth), Token::Address(usdc)])],
+            ),
+        );
+
+        for tx in [&balance, &quote] {
+            let simulation = simulator.simulate(tx).unwrap();
+            assert!(simulation.success, "halted: {:?}", simulation.halt_reason);
+            assert_eq!(simulation.block_number, block.number.unwrap().as_u64());
+            assert_eq!(simulation.output, provider.call(tx, at).await.unwrap());
+        }
+        // Without subcalls, the least gas the node finds sufficient is exactly what the call uses
+        let simulation = simulator.simulate(&balance).unwrap();
+        assert_eq!(U256::from(simulation.gas_used), provider.estimate_gas(&balance, at).await.unwrap());
+
+        // A second run is served from the block's cache
+        let cached = simulator.cached();
+        simulator.simulate(&quote).unwrap();
+        assert_eq!(simulator.cached(), cached);
+    }
+}
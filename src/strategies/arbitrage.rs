@@ -1,137 +1,356 @@
+// src/strategies/arbitrage.rs
+use async_trait::async_trait;
+use ethers::prelude::*;
+use serde::Deserialize;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
-use web3::types::{Address, U256};
-use web3::Web3;
-
-pub struct Arbitrage {
-    pub dexes: HashMap<String, String>,
-    pub token: String,
-    pub gas_price: u64,
-    pub private_key: String,
-    pub web3: Web3,
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+use anyhow::{anyhow, Result};
+
+use super::flash_loan::{FlashLoanStrategy, Funded, Funding};
+use super::profit::{CapitalRequirement, NetProfit, Opportunity, ProfitEstimate, ProfitFilter};
+use super::Strategy;
+use crate::execution::actions::BALANCE;
+use crate::execution::swap::encode_swap_exact_tokens_for_tokens;
+use crate::execution::{
+    Action, ApprovalManager, DeadlineCalculator, FlashLoanQuote, MultiActionExecutor, Submission, Submitter, SwapCalldataBuilder, SwapQuoter,
+    WalletLease, WalletPool,
+};
+
+// Two V2 swaps plus transfers
+const ARBITRAGE_GAS_UNITS: u64 = 250_000;
+
+/// `config/arbitrage_config.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArbitrageConfig {
+    pub dexes: Vec<DexConfig>,
+    /// Pairs watched on every DEX.
+    pub pairs: Vec<TokenPair>,
+    /// Token0 each round trip starts with.
+    pub trade_size: U256,
+}
+
+impl ArbitrageConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DexConfig {
+    pub name: String,
+    /// Uniswap V2 style router.
+    pub router: Address,
 }
 
-impl Arbitrage {
-    pub fn new(dexes: HashMap<String, String>, token: String, gas_price: u64, private_key: String, web3: Web3) -> Self {
-        Arbitrage {
+pub struct ArbitrageStrategy {
+    wallets: Arc<WalletPool>,
+    submitter: Arc<Submitter>,
+    dexes: Vec<Dex>,
+    profit_filter: Arc<ProfitFilter>,
+    funding: Arc<FlashLoanStrategy>,
+    /// Runs both legs in one transaction, so a half-filled round trip reverts.
+    executor: Arc<MultiActionExecutor>,
+    approvals: Arc<ApprovalManager>,
+    /// Swap calldata bounded by a fresh quote; rebuilt per trade for the wallet it pays out to.
+    swap_builder: Arc<SwapCalldataBuilder>,
+    /// DEX name → its Uniswap V2 style router.
+    routers: HashMap<String, Address>,
+    deadlines: Arc<DeadlineCalculator>,
+    trade_size: U256,
+}
+
+impl ArbitrageStrategy {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new(
+        wallets: Arc<WalletPool>,
+        submitter: Arc<Submitter>,
+        dexes: Vec<Dex>,
+        profit_filter: Arc<ProfitFilter>,
+        funding: Arc<FlashLoanStrategy>,
+        executor: Arc<MultiActionExecutor>,
+        approvals: Arc<ApprovalManager>,
+        swap_builder: Arc<SwapCalldataBuilder>,
+        routers: HashMap<String, Address>,
+        deadlines: Arc<DeadlineCalculator>,
+        trade_size: U256,
+    ) -> Self {
+        Self {
+            wallets,
+            submitter,
             dexes,
-            token,
-            gas_price,
-            private_key,
-            web3,
+            profit_filter,
+            funding,
+            executor,
+            approvals,
+            swap_builder,
+            routers,
+            deadlines,
+            trade_size,
         }
     }
 
-    pub fn arbitrage(&self) {
-        // Identify profitable trades
+    async fn find_opportunities(&self) -> Vec<ArbitrageOpportunity> {
         let mut opportunities = Vec::new();
-        for (dex1, price1) in &self.dexes {
-            for (dex2, price2) in &self.dexes {
-                if price1 > price2 {
-                    // Buy on DEX2 and sell on DEX1
-                    opportunities.push((dex2.clone(), dex1.clone()));
-                } else if price2 > price1 {
-                    // Buy on DEX1 and sell on DEX2
-                    opportunities.push((dex1.clone(), dex2.clone()));
+
+        for pair in &self.get_token_pairs().await {
+            let prices = self.get_prices(pair).await;
+            
+            if let Some(opportunity) = self.analyze_prices(pair, &prices).await {
+                opportunities.push(opportunity);
+            }
+        }
+
+        opportunities
+    }
+
+    async fn get_token_pairs(&self) -> Vec<TokenPair> {
+        // Get token pairs from DEXes
+        let mut pairs = Vec::new();
+        for dex in &self.dexes {
+            match dex.get_token_pairs().await {
+                Ok(pairs_from_dex) => pairs.extend(pairs_from_dex),
+                Err(e) => warn!("Failed to list pairs on {}: {}", dex.name, e),
+            }
+        }
+        pairs
+    }
+
+    /// Token1 each DEX pays for `trade_size` of token0: the buy leg of a round trip. A DEX that
+    /// cannot quote is left out, so the others are still compared.
+    async fn get_prices(&self, pair: &TokenPair) -> Vec<(String, U256)> {
+        let mut prices = Vec::new();
+        for dex in &self.dexes {
+            match dex.get_price(pair, self.trade_size).await {
+                Ok(price) => prices.push((dex.name.clone(), price)),
+                Err(e) => warn!("Failed to price {:?}/{:?} on {}: {}", pair.token0, pair.token1, dex.name, e),
+            }
+        }
+        prices
+    }
+
+    /// The most profitable round trip: token0 → token1 on one DEX and back on another.
+    async fn analyze_prices(&self, pair: &TokenPair, prices: &Vec<(String, U256)>) -> Option<ArbitrageOpportunity> {
+        let mut best_opportunity = None;
+
+        for (buy_dex, bought) in prices {
+            for sell_dex in self.dexes.iter().filter(|dex| dex.name != *buy_dex) {
+                if let Some(opportunity) = self.calculate_arbitrage(pair, buy_dex, *bought, sell_dex).await {
+                    if best_opportunity.as_ref().is_none_or(|best: &ArbitrageOpportunity| opportunity.profit.gross_profit > best.profit.gross_profit) {
+                        best_opportunity = Some(opportunity);
+                    }
                 }
             }
         }
 
-        // Execute trades
-        for (dex1, dex2) in opportunities {
-            self.buy_on_dex(dex2.clone());
-            self.sell_on_dex(dex1.clone());
+        best_opportunity
+    }
+
+    /// Quotes selling the `bought` token1 back to token0 on `sell_dex`; a round trip pays when it
+    /// returns more token0 than it started with.
+    async fn calculate_arbitrage(&self, pair: &TokenPair, buy_dex: &str, bought: U256, sell_dex: &Dex) -> Option<ArbitrageOpportunity> {
+        let returned = match sell_dex.get_return(pair, bought).await {
+            Ok(returned) => returned,
+            Err(e) => {
+                debug!("Failed to quote selling {:?} on {}: {}", pair.token1, sell_dex.name, e);
+                return None;
+            }
+        };
+        if returned <= self.trade_size {
+            return None;
         }
+
+        Some(ArbitrageOpportunity {
+            buy_dex: buy_dex.to_string(),
+            sell_dex: sell_dex.name.clone(),
+            token_pair: (pair.token0, pair.token1),
+            amount_in: self.trade_size,
+            profit: ProfitEstimate {
+                profit_token: pair.token0,
+                profit_token_decimals: pair.token0_decimals,
+                gross_profit: returned - self.trade_size,
+                fee_cost: U256::zero(),
+                gas_units: U256::from(ARBITRAGE_GAS_UNITS),
+            },
+        })
     }
 
-    pub fn buy_on_dex(&self, dex: String) {
-        // Set the Uniswap V2 Router address
-        let uniswap_v2_router = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D";
+    /// Runs both legs through the executor on the leased wallet's own token0, which the executor
+    /// pulls once approved; it reverts unless the wallet gets back `amount_in` plus `net`.
+    async fn execute_arbitrage(&self, funded: &Funded<ArbitrageOpportunity>, net: &NetProfit, lease: &WalletLease) -> Result<()> {
+        let opportunity = &funded.opportunity;
+        let token0 = opportunity.token_pair.0;
+        let executor = self.executor.address();
+        let min_profit = self.profit_filter.net_in_profit_token(funded.profit(), net).await?;
 
-        // Set the WETH address
-        let weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+        let window = self.deadlines.bundle_window().await?;
+        let mut actions = vec![Action::Pull { token: token0, amount: opportunity.amount_in }];
+        actions.extend(self.round_trip_actions(opportunity, opportunity.amount_in + min_profit, window.deadline).await?);
+        actions.extend(self.payout_actions(opportunity, opportunity.amount_in + min_profit, lease.address()));
 
-        // Set the token address
-        let token = self.token.clone();
+        self.approvals.ensure_allowance("arbitrage", lease, token0, executor, opportunity.amount_in).await?;
+        let tx = self.executor.execute_tx(&actions)?;
+        if let Submission::Broadcast(_) = self.submitter.submit_bundle("arbitrage", lease, tx, &window).await? {
+            self.approvals.record_spend(lease.address(), token0, executor, opportunity.amount_in).await;
+        }
 
-        // Set the amount of ETH to swap
-        let amount_in = 1 ether;
+        Ok(())
+    }
+
+    /// Runs both legs inside the quoted loan of token0; the executor reverts unless the round trip
+    /// repays it and leaves `net` in token0.
+    async fn execute_flash_arbitrage(&self, funded: &Funded<ArbitrageOpportunity>, quote: &FlashLoanQuote, net: &NetProfit, lease: &WalletLease) -> Result<()> {
+        let window = self.deadlines.bundle_window().await?;
+        let legs = self.round_trip_actions(&funded.opportunity, quote.amount + quote.fee, window.deadline).await?;
+        let min_profit = self.profit_filter.net_in_profit_token(funded.profit(), net).await?;
 
-        // Set the amount of tokens to receive
-        let amount_out = self.get_price(dex.clone());
+        let mut actions = vec![Action::flash_borrow(quote, legs)];
+        actions.extend(self.payout_actions(&funded.opportunity, min_profit, lease.address()));
+        let tx = self.executor.execute_tx(&actions)?;
+        self.submitter.submit_bundle("arbitrage", lease, tx, &window).await?;
 
-        // Set the deadline for the transaction
-        let deadline = block.timestamp + 15 minutes;
+        Ok(())
+    }
 
-        // Create a path for the swap
-        let path = vec![weth, token];
+    /// Both legs, paying out to the executor. The buy leg may slip within the guard's tolerance,
+    /// so the sell leg spends only what it is guaranteed to return and must bring back at least
+    /// `sell_min_out` of token0.
+    async fn round_trip_actions(&self, opportunity: &ArbitrageOpportunity, sell_min_out: U256, deadline: U256) -> Result<Vec<Action>> {
+        let (buy_router, sell_router) = (self.router(&opportunity.buy_dex)?, self.router(&opportunity.sell_dex)?);
+        let (token0, token1) = opportunity.token_pair;
+        let executor = self.executor.address();
 
-        // Use the Uniswap V2 Router to swap ETH for the token
-        self.web3.eth().send_transaction(
-            TransactionRequest {
-                from: self.private_key.clone(),
-                to: uniswap_v2_router,
-                value: amount_in,
-                gas: self.gas_price,
-                gas_price: self.gas_price,
-                data: encode_swap_exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(
-                    amount_in,
-                    amount_out,
-                    path,
-                    deadline,
-                ),
-            },
-        );
+        let bought = self.swap_builder.min_amount_out(buy_router, opportunity.amount_in, &[token0, token1]).await?;
+        let buy = encode_swap_exact_tokens_for_tokens(opportunity.amount_in, bought, vec![token0, token1], executor, deadline)?;
+        let sell = encode_swap_exact_tokens_for_tokens(bought, sell_min_out, vec![token1, token0], executor, deadline)?;
+
+        Ok(vec![
+            Action::Approve { token: token0, spender: buy_router, amount: opportunity.amount_in },
+            Action::Call { target: buy_router, data: buy },
+            Action::Approve { token: token1, spender: sell_router, amount: bought },
+            Action::Call { target: sell_router, data: sell },
+        ])
+    }
+
+    /// Reverts unless the executor holds `minimum` of token0, then hands both tokens to the wallet:
+    /// the buy leg usually returns more token1 than the sell leg was sized to spend.
+    fn payout_actions(&self, opportunity: &ArbitrageOpportunity, minimum: U256, wallet: Address) -> [Action; 3] {
+        let (token0, token1) = opportunity.token_pair;
+        [
+            Action::CheckMinBalance { token: token0, minimum },
+            Action::Transfer { token: token0, to: wallet, amount: BALANCE },
+            Action::Transfer { token: token1, to: wallet, amount: BALANCE },
+        ]
     }
 
-    pub fn sell_on_dex(&self, dex: String) {
-        // Set the Uniswap V2 Router address
-        let uniswap_v2_router = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D";
+    fn router(&self, dex: &str) -> Result<Address> {
+        self.routers.get(dex).copied().ok_or_else(|| anyhow!("No router for {}", dex))
+    }
+}
+
+#[async_trait]
+impl Strategy for ArbitrageStrategy {
+    async fn execute(&self, block: &Block<H256>) -> Result<()> {
+        let opportunities = self.find_opportunities().await;
+        let gas_price = block.base_fee_per_gas.unwrap_or_default();
+
+        // One wallet per opportunity, so each is funded against the balance of the wallet that trades it
+        for opportunity in opportunities {
+            let lease = match self.wallets.acquire().await {
+                Some(lease) => lease,
+                None => {
+                    warn!("No free wallet for arbitrage");
+                    break;
+                }
+            };
+            let route = format!("{} -> {}", opportunity.buy_dex, opportunity.sell_dex);
+            let funded = match self.funding.fund(opportunity, lease.address()).await {
+                Ok(Some(funded)) => funded,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to fund arbitrage {}: {}", route, e);
+                    continue;
+                }
+            };
+            let net = match self.profit_filter.net_profit(funded.profit(), gas_price).await {
+                Ok(net) => net,
+                Err(e) => {
+                    warn!("Failed to value arbitrage {}: {}", route, e);
+                    continue;
+                }
+            };
+            if !self.profit_filter.clears(&net) {
+                continue;
+            }
+
+            info!("Executing arbitrage {} for {} wei net (${:.2})", route, net.net_eth, net.net_usd);
+            let result = match &funded.funding {
+                Funding::Wallet => self.execute_arbitrage(&funded, &net, &lease).await,
+                Funding::FlashLoan(quote) => self.execute_flash_arbitrage(&funded, quote, &net, &lease).await,
+            };
+            if let Err(e) = result {
+                warn!("Failed to execute arbitrage {}: {}", route, e);
+            }
+        }
 
-        // Set the WETH address
-        let weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+        Ok(())
+    }
+}
 
-        // Set the token address
-        let token = self.token.clone();
+#[derive(Debug)]
+pub struct ArbitrageOpportunity {
+    pub buy_dex: String,
+    pub sell_dex: String,
+    pub token_pair: (Address, Address),
+    /// Amount of token0 the trade starts with.
+    pub amount_in: U256,
+    pub profit: ProfitEstimate,
+}
 
-        // Set the amount of tokens to swap
-        let amount_in = self.get_price(dex.clone());
+impl Opportunity for ArbitrageOpportunity {
+    fn profit(&self) -> &ProfitEstimate {
+        &self.profit
+    }
 
-        // Set the amount of ETH to receive
-        let amount_out = 1 ether;
+    fn capital(&self) -> Option<CapitalRequirement> {
+        Some(CapitalRequirement {
+            token: self.token_pair.0,
+            amount: self.amount_in,
+        })
+    }
+}
 
-        // Set the deadline for the transaction
-        let deadline = block.timestamp + 15 minutes;
+/// A Uniswap V2 style DEX and the pairs we watch on it.
+pub struct Dex {
+    pub name: String,
+    pub router: Address,
+    pub pairs: Vec<TokenPair>,
+    quoter: Arc<SwapQuoter>,
+}
 
-        // Create a path for the swap
-        let path = vec![token, weth];
+impl Dex {
+    pub fn new(name: String, router: Address, pairs: Vec<TokenPair>, quoter: Arc<SwapQuoter>) -> Self {
+        Self { name, router, pairs, quoter }
+    }
 
-        // Use the Uniswap V2 Router to swap the token for ETH
-        self.web3.eth().send_transaction(
-            TransactionRequest {
-                from: self.private_key.clone(),
-                to: uniswap_v2_router,
-                value: amount_in,
-                gas: self.gas_price,
-                gas_price: self.gas_price,
-                data: encode_swap_exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(
-                    amount_in,
-                    amount_out,
-                    path,
-                    deadline,
-                ),
-            },
-        );
+    async fn get_token_pairs(&self) -> Result<Vec<TokenPair>> {
+        Ok(self.pairs.clone())
     }
 
-    pub fn get_price(&self, dex: String) -> U256 {
-        // Get the price of the token on the specified DEX
-        let url = format!("https://api.binance.com/api/v3/ticker/price?symbol={}", dex);
-        let response = reqwest::get(url).unwrap();
-        let json: serde_json::Value = serde_json::from_str(&response.text().unwrap()).unwrap();
-        let price = json["price"].as_str().unwrap();
-        U256::from_dec(price).unwrap()
+    /// Token1 paid by the router for `amount` of token0.
+    async fn get_price(&self, pair: &TokenPair, amount: U256) -> Result<U256> {
+        self.quoter.quote_exact_in(self.router, amount, &[pair.token0, pair.token1]).await
+    }
+
+    /// Token0 paid by the router for `amount` of token1.
+    async fn get_return(&self, pair: &TokenPair, amount: U256) -> Result<U256> {
+        self.quoter.quote_exact_in(self.router, amount, &[pair.token1, pair.token0]).await
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenPair {
+    pub token0: Address,
+    pub token1: Address,
+    pub token0_decimals: u8,
+}
@@ -0,0 +1,102 @@
+// src/strategies/flash_loan.rs
+use ethers::{
+    prelude::*,
+    providers::{Provider, Ws},
+};
+use std::sync::Arc;
+use tracing::debug;
+use anyhow::Result;
+
+use super::profit::{Opportunity, ProfitEstimate};
+use crate::execution::{FlashLoanQuote, FlashLoanRouter};
+
+// Taking and repaying the loan, plus running the program through the executor
+const FLASH_LOAN_GAS_UNITS: u64 = 150_000;
+
+abigen!(
+    CapitalToken,
+    r#"[
+        function balanceOf(address account) external view returns (uint256)
+    ]"#
+);
+
+/// Not a strategy of its own but a funding step for the others: an opportunity that needs more
+/// capital than the executing wallet holds is wrapped in the cheapest flash loan that covers it,
+/// and valued with that loan's fee and gas.
+pub struct FlashLoanStrategy {
+    provider: Arc<Provider<Ws>>,
+    flash_loans: Arc<FlashLoanRouter>,
+}
+
+impl FlashLoanStrategy {
+    pub fn new(provider: Arc<Provider<Ws>>, flash_loans: Arc<FlashLoanRouter>) -> Self {
+        Self { provider, flash_loans }
+    }
+
+    /// How `opportunity` is paid for if `holder` executes it, or `None` when neither the wallet
+    /// nor any flash-loan source can fund it.
+    pub async fn fund<T: Opportunity>(&self, opportunity: T, holder: Address) -> Result<Option<Funded<T>>> {
+        let capital = match opportunity.capital() {
+            Some(capital) => capital,
+            None => return Ok(Some(Funded::from_wallet(opportunity))),
+        };
+        let balance = CapitalToken::new(capital.token, self.provider.clone()).balance_of(holder).call().await?;
+        if balance >= capital.amount {
+            return Ok(Some(Funded::from_wallet(opportunity)));
+        }
+
+        let quote = match self.flash_loans.cheapest(capital.token, capital.amount).await {
+            Some(quote) => quote,
+            None => {
+                debug!("No flash-loan source covers {} of {:?}", capital.amount, capital.token);
+                return Ok(None);
+            }
+        };
+        let profit = match opportunity.profit().with_flash_loan(quote.token, quote.fee, FLASH_LOAN_GAS_UNITS) {
+            Some(profit) => profit,
+            None => {
+                debug!("Flash loan of {:?} cannot be netted against profit in another token", quote.token);
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(Funded {
+            opportunity,
+            funding: Funding::FlashLoan(quote),
+            profit,
+        }))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Funding {
+    /// The executing wallet already holds the capital.
+    Wallet,
+    /// The capital is borrowed for the transaction and the opportunity runs inside the loan.
+    FlashLoan(FlashLoanQuote),
+}
+
+/// An opportunity with how its capital is raised.
+#[derive(Debug)]
+pub struct Funded<T> {
+    pub opportunity: T,
+    pub funding: Funding,
+    /// The opportunity's own estimate plus the loan's fee and gas.
+    pub profit: ProfitEstimate,
+}
+
+impl<T: Opportunity> Funded<T> {
+    fn from_wallet(opportunity: T) -> Self {
+        Self {
+            profit: opportunity.profit().clone(),
+            opportunity,
+            funding: Funding::Wallet,
+        }
+    }
+}
+
+impl<T: Opportunity> Opportunity for Funded<T> {
+    fn profit(&self) -> &ProfitEstimate {
+        &self.profit
+    }
+}
@@ -1,137 +1,812 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
-use web3::types::{Address, U256};
-use web3::Web3;
-
-pub struct Liquidation {
-    pub dexes: HashMap<String, String>,
-    pub token: String,
-    pub gas_price: u64,
-    pub private_key: String,
-    pub web3: Web3,
+// src/strategies/liquidation.rs
+use async_trait::async_trait;
+use ethers::prelude::*;
+use std::collections::{HashMap, HashSet};
+use futures::StreamExt;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+use anyhow::{anyhow, Result};
+
+use super::flash_loan::{FlashLoanStrategy, Funded, Funding};
+use super::profit::{CapitalRequirement, NetProfit, Opportunity, ProfitEstimate, ProfitFilter};
+use super::Strategy;
+use crate::execution::actions::BALANCE;
+use crate::execution::swap::encode_swap_exact_tokens_for_tokens;
+use crate::execution::{
+    Action, ApprovalManager, DeadlineCalculator, FlashLoanQuote, MultiActionExecutor, RouteFinder, Submission, Submitter, SwapRoute, WalletLease,
+    WalletPool,
+};
+use crate::lending::{
+    AuctionHouse, AuctionLot, BorrowerIndex, CollateralSale, CometMarketConfig, CompoundMarketConfig, FlashLiquidationLeg, FlashLiquidationPlan,
+    FlashLiquidator, HealthMonitor, LendingPool, LiquidationCandidate, LiquidationParams,
+};
+use crate::math::Wad;
+use crate::oracle::{FeedUpdate, FeedUpdateWatcher};
+
+const LIQUIDATION_GAS_UNITS: u64 = 500_000;
+// Flash loan, liquidation, redeem and swap in one transaction
+const FLASH_LIQUIDATION_GAS_UNITS: u64 = 900_000;
+const COLLATERAL_PURCHASE_GAS_UNITS: u64 = 200_000;
+// Take with the in-callback DEX sale, and a bare redo
+const AUCTION_TAKE_GAS_UNITS: u64 = 450_000;
+const AUCTION_REDO_GAS_UNITS: u64 = 150_000;
+// Auction lots are taken only when the DEX pays at least this much over the auction price
+const AUCTION_TAKE_MARGIN_BPS: u64 = 100;
+const BPS: u64 = 10_000;
+// Accounts per market logged as closest to liquidation
+const WATCHLIST_SIZE: usize = 5;
+
+/// `config/liquidation_config.json`: the Compound deployments liquidated beside the Aave markets
+/// of `config/aave_markets.json` and the auctions of `config/clipper_auctions.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LiquidationConfig {
+    #[serde(default)]
+    pub compound_v2: Vec<CompoundMarketConfig>,
+    #[serde(default)]
+    pub compound_v3: Vec<CometMarketConfig>,
 }
 
-impl Liquidation {
-    pub fn new(dexes: HashMap<String, String>, token: String, gas_price: u64, private_key: String, web3: Web3) -> Self {
-        Liquidation {
-            dexes,
-            token,
-            gas_price,
-            private_key,
-            web3,
+impl LiquidationConfig {
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+pub struct LiquidationStrategy {
+    wallets: Arc<WalletPool>,
+    submitter: Arc<Submitter>,
+    lending_pools: Vec<Arc<dyn LendingPool>>,
+    /// One per lending pool, in the same order.
+    monitors: Vec<HealthMonitor>,
+    borrower_index: Arc<BorrowerIndex>,
+    profit_filter: Arc<ProfitFilter>,
+    /// Builds liquidations funded with a flash loan of the debt asset, for protocols the executor supports.
+    flash_liquidator: Arc<FlashLiquidator>,
+    /// Dutch-auction liquidations, taken rather than triggered.
+    auctions: Vec<Arc<dyn AuctionHouse>>,
+    routes: Arc<RouteFinder>,
+    /// Flash-borrows the debt a liquidation repays, or what a collateral purchase costs, when the
+    /// wallet cannot pay it.
+    funding: Arc<FlashLoanStrategy>,
+    /// Runs flash-funded collateral purchases.
+    executor: Arc<MultiActionExecutor>,
+    /// Allowances the leased wallets give the protocols they repay debt or buy collateral from.
+    approvals: Arc<ApprovalManager>,
+    deadlines: Arc<DeadlineCalculator>,
+    /// Base fee of the latest block, for valuing liquidations found between blocks. None until a
+    /// block with a base fee has been seen.
+    gas_price: RwLock<Option<U256>>,
+    /// (pool, user) → block a liquidation was last submitted in, so a feed update and the block
+    /// scan do not both submit one.
+    queued: RwLock<HashMap<(usize, Address), u64>>,
+}
+
+impl LiquidationStrategy {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new(
+        wallets: Arc<WalletPool>,
+        submitter: Arc<Submitter>,
+        lending_pools: Vec<Arc<dyn LendingPool>>,
+        borrower_index: Arc<BorrowerIndex>,
+        profit_filter: Arc<ProfitFilter>,
+        flash_liquidator: Arc<FlashLiquidator>,
+        auctions: Vec<Arc<dyn AuctionHouse>>,
+        routes: Arc<RouteFinder>,
+        funding: Arc<FlashLoanStrategy>,
+        executor: Arc<MultiActionExecutor>,
+        approvals: Arc<ApprovalManager>,
+        deadlines: Arc<DeadlineCalculator>,
+    ) -> Self {
+        Self {
+            wallets,
+            submitter,
+            monitors: lending_pools.iter().map(|pool| HealthMonitor::new(pool.clone())).collect(),
+            lending_pools,
+            borrower_index,
+            profit_filter,
+            flash_liquidator,
+            auctions,
+            routes,
+            funding,
+            executor,
+            approvals,
+            deadlines,
+            gas_price: RwLock::new(None),
+            queued: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Re-evaluates borrowers as soon as a Chainlink feed posts a new answer, instead of waiting for
+    /// the next block scan. Runs until the subscription ends.
+    pub async fn watch_feeds(&self, watcher: &FeedUpdateWatcher) -> Result<()> {
+        let mut updates = watcher.subscribe().await?;
+        while let Some(update) = updates.next().await {
+            if let Err(e) = self.on_feed_update(&update).await {
+                warn!("Failed to re-evaluate borrowers after {:?} moved: {}", update.token, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reprices every market through its own oracle, which reads the new answer in the same block,
+    /// and liquidates the recomputed accounts that fell below 1.
+    async fn on_feed_update(&self, update: &FeedUpdate) -> Result<()> {
+        // Gas would be costed at zero before the first block
+        let gas_price = match *self.gas_price.read().await {
+            Some(gas_price) => gas_price,
+            None => {
+                debug!("Skipping {:?} feed update until a block's base fee is known", update.token);
+                return Ok(());
+            }
+        };
+
+        for (pool_index, pool) in self.lending_pools.iter().enumerate() {
+            let monitor = &self.monitors[pool_index];
+            let affected = match monitor.update_prices().await {
+                Ok(affected) => affected,
+                Err(e) => {
+                    warn!("Failed to reprice {} after {:?} moved: {}", pool.name(), update.token, e);
+                    continue;
+                }
+            };
+            if affected.is_empty() {
+                continue;
+            }
+            debug!("{:?} feed update moved {} accounts on {}", update.token, affected.len(), pool.name());
+
+            for user in affected {
+                if monitor.health_factor(user).await.is_none_or(|health_factor| health_factor >= Wad::ONE) {
+                    continue;
+                }
+                if let Some((opportunity, net)) = self.evaluate(pool_index, user, gas_price).await {
+                    if let Err(e) = self.liquidate(&opportunity, &net, update.block_number).await {
+                        warn!("Failed to liquidate {:?} on {}: {}", user, pool.name(), e);
+                    }
+                }
+            }
         }
+
+        Ok(())
     }
 
-    pub fn liquidation(&self) {
-        // Identify profitable trades
+    /// Checks on chain only the accounts whose locally computed health factor is below 1, and keeps
+    /// the liquidations that clear the profit filter once their seized collateral is sold. A market
+    /// that fails to sync is skipped for this block.
+    async fn find_opportunities(&self, touched: &HashMap<String, HashSet<Address>>, gas_price: U256) -> Vec<(LiquidationOpportunity, NetProfit)> {
         let mut opportunities = Vec::new();
-        for (dex1, price1) in &self.dexes {
-            for (dex2, price2) in &self.dexes {
-                if price1 > price2 {
-                    // Buy on DEX2 and sell on DEX1
-                    opportunities.push((dex2.clone(), dex1.clone()));
-                } else if price2 > price1 {
-                    // Buy on DEX1 and sell on DEX2
-                    opportunities.push((dex1.clone(), dex2.clone()));
+        let untouched = HashSet::new();
+
+        for (pool_index, pool) in self.lending_pools.iter().enumerate() {
+            let monitor = &self.monitors[pool_index];
+            let borrowers = self.borrower_index.borrowers(pool.name()).await;
+            if let Err(e) = monitor.sync_accounts(&borrowers, touched.get(pool.name()).unwrap_or(&untouched)).await {
+                warn!("Failed to sync {} accounts: {}", pool.name(), e);
+                continue;
+            }
+            if let Err(e) = monitor.update_prices().await {
+                warn!("Failed to reprice {}: {}", pool.name(), e);
+                continue;
+            }
+            debug!("{} watchlist: {:?}", pool.name(), monitor.watchlist(WATCHLIST_SIZE).await);
+
+            for user in monitor.below(Wad::ONE).await {
+                if let Some(opportunity) = self.evaluate(pool_index, user, gas_price).await {
+                    opportunities.push(opportunity);
                 }
             }
         }
 
-        // Execute trades
-        for (dex1, dex2) in opportunities {
-            self.buy_on_dex(dex2.clone());
-            self.sell_on_dex(dex1.clone());
+        opportunities
+    }
+
+    /// Checks `user` on chain and values its liquidation, or `None` when it is dust, healthy on
+    /// chain, unroutable or below the profit filter.
+    async fn evaluate(&self, pool_index: usize, user: Address, gas_price: U256) -> Option<(LiquidationOpportunity, NetProfit)> {
+        let pool = &self.lending_pools[pool_index];
+        let monitor = &self.monitors[pool_index];
+        if monitor.debt_value(user).await.is_none_or(|value| value < pool.dust_debt_value()) {
+            return None;
+        }
+
+        let candidate = match pool.find_liquidation(user).await {
+            Ok(Some(candidate)) => candidate,
+            Ok(None) => return None,
+            Err(e) => {
+                warn!("Failed to check {:?} on {}: {}", user, pool.name(), e);
+                return None;
+            }
+        };
+
+        let leg = pool.flash_liquidation_leg(&candidate.params);
+        let economics = match self.economics(&candidate, leg.as_ref()).await {
+            Some(economics) => economics,
+            None => {
+                debug!("No route sells the collateral of {:?} on {}", user, pool.name());
+                return None;
+            }
+        };
+        let profit = economics.estimate();
+        let net = match self.profit_filter.net_profit(&profit, gas_price).await {
+            Ok(net) => net,
+            Err(e) => {
+                warn!("Failed to value the liquidation of {:?} on {}: {}", user, pool.name(), e);
+                return None;
+            }
+        };
+        if !self.profit_filter.clears(&net) {
+            debug!("Liquidating {:?} on {} nets {} wei, below the minimum", user, pool.name(), net.net_eth);
+            return None;
         }
+
+        Some((
+            LiquidationOpportunity {
+                user,
+                pool: pool_index,
+                health_factor: candidate.health_factor,
+                params: candidate.params,
+                leg,
+                economics,
+                profit,
+            },
+            net,
+        ))
     }
 
-    pub fn buy_on_dex(&self, dex: String) {
-        // Set the Uniswap V2 Router address
-        let uniswap_v2_router = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D";
+    /// Values a liquidation paid from the wallet by selling the surplus the protocol prices it at
+    /// on the DEX in one swap, at its actual size. For Comet's absorb that surplus is the
+    /// store-front discount on the collateral it moves into the protocol's reserves.
+    async fn economics(&self, candidate: &LiquidationCandidate, leg: Option<&FlashLiquidationLeg>) -> Option<LiquidationEconomics> {
+        // The debt token is the cToken market on Compound; the wallet repays its underlying
+        let debt_token = leg.map(|leg| leg.debt_underlying).unwrap_or(candidate.params.debt_token);
+        let collateral_sold = candidate.gross_profit;
+        let sale_proceeds = if collateral_sold.is_zero() {
+            U256::zero()
+        } else {
+            self.routes
+                .best_exact_in(candidate.collateral_underlying, debt_token, collateral_sold)
+                .await?
+                .amount_out
+        };
 
-        // Set the WETH address
-        let weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+        Some(LiquidationEconomics {
+            debt_token,
+            debt_decimals: candidate.debt_decimals,
+            seized_collateral: candidate.seized_collateral,
+            collateral_sold,
+            sale_proceeds,
+            debt_repaid: U256::zero(),
+            flash_fee: U256::zero(),
+            gas_units: LIQUIDATION_GAS_UNITS,
+        })
+    }
 
-        // Set the token address
-        let token = self.token.clone();
+    /// Values a flash liquidation by selling all of the seized collateral against the debt repaid
+    /// and the loan's fee.
+    async fn flash_economics(&self, plan: &FlashLiquidationPlan, debt_decimals: u8) -> Option<LiquidationEconomics> {
+        let (collateral, debt) = (plan.leg.collateral_underlying, plan.leg.debt_underlying);
+        let sale_proceeds = if plan.path.is_empty() {
+            plan.seized_collateral
+        } else {
+            self.routes.best_exact_in(collateral, debt, plan.seized_collateral).await?.amount_out
+        };
 
-        // Set the amount of ETH to swap
-        let amount_in = 1 ether;
+        Some(LiquidationEconomics {
+            debt_token: debt,
+            debt_decimals,
+            seized_collateral: plan.seized_collateral,
+            collateral_sold: plan.seized_collateral,
+            sale_proceeds,
+            debt_repaid: plan.params.debt_to_cover,
+            flash_fee: plan.loan.fee,
+            gas_units: FLASH_LIQUIDATION_GAS_UNITS,
+        })
+    }
 
-        // Set the amount of tokens to receive
-        let amount_out = self.get_price(dex.clone());
+    /// Discounted collateral the protocols are selling that is worth more on the DEX than it costs.
+    async fn find_collateral_purchases(&self) -> Vec<CollateralPurchase> {
+        let mut purchases = Vec::new();
 
-        // Set the deadline for the transaction
-        let deadline = block.timestamp + 15 minutes;
+        for (pool_index, pool) in self.lending_pools.iter().enumerate() {
+            let sales = match pool.collateral_for_sale().await {
+                Ok(sales) => sales,
+                Err(e) => {
+                    warn!("Failed to read collateral for sale on {}: {}", pool.name(), e);
+                    continue;
+                }
+            };
+            for sale in sales {
+                let route = match self.routes.best_exact_in(sale.asset, sale.payment_token, sale.collateral_amount).await {
+                    Some(route) if route.amount_out > sale.payment_amount => route,
+                    _ => continue,
+                };
 
-        // Create a path for the swap
-        let path = vec![weth, token];
+                purchases.push(CollateralPurchase {
+                    pool: pool_index,
+                    profit: ProfitEstimate {
+                        profit_token: sale.payment_token,
+                        profit_token_decimals: sale.payment_decimals,
+                        gross_profit: route.amount_out - sale.payment_amount,
+                        fee_cost: U256::zero(),
+                        gas_units: U256::from(COLLATERAL_PURCHASE_GAS_UNITS),
+                    },
+                    sale,
+                    route,
+                });
+            }
+        }
 
-        // Use the Uniswap V2 Router to swap ETH for the token
-        self.web3.eth().send_transaction(
-            TransactionRequest {
-                from: self.private_key.clone(),
-                to: uniswap_v2_router,
-                value: amount_in,
-                gas: self.gas_price,
-                gas_price: self.gas_price,
-                data: encode_swap_exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(
-                    amount_in,
-                    amount_out,
-                    path,
-                    deadline,
-                ),
-            },
-        );
+        purchases
+    }
+
+    /// Auctions that need resetting, and lots the DEX values above their current price by the take margin.
+    async fn find_auction_actions(&self, timestamp: u64) -> Vec<AuctionOpportunity> {
+        let mut actions = Vec::new();
+
+        for (house_index, house) in self.auctions.iter().enumerate() {
+            let lots = match house.lots(timestamp).await {
+                Ok(lots) => lots,
+                Err(e) => {
+                    warn!("Failed to read lots on {}: {}", house.name(), e);
+                    continue;
+                }
+            };
+            for lot in lots {
+                if lot.needs_redo {
+                    actions.push(AuctionOpportunity {
+                        house: house_index,
+                        profit: ProfitEstimate {
+                            profit_token: lot.payment_token,
+                            profit_token_decimals: lot.payment_decimals,
+                            gross_profit: lot.redo_incentive,
+                            fee_cost: U256::zero(),
+                            gas_units: U256::from(AUCTION_REDO_GAS_UNITS),
+                        },
+                        action: AuctionAction::Redo,
+                        lot,
+                    });
+                    continue;
+                }
+                if lot.collateral_amount.is_zero() {
+                    continue;
+                }
+
+                let route = match self.routes.best_exact_in(lot.collateral, lot.payment_token, lot.collateral_amount).await {
+                    Some(route) => route,
+                    None => continue,
+                };
+                let required = lot.payment_amount * (BPS + AUCTION_TAKE_MARGIN_BPS) / BPS;
+                if route.amount_out < required {
+                    continue;
+                }
+
+                actions.push(AuctionOpportunity {
+                    house: house_index,
+                    profit: ProfitEstimate {
+                        profit_token: lot.payment_token,
+                        profit_token_decimals: lot.payment_decimals,
+                        gross_profit: route.amount_out - lot.payment_amount,
+                        fee_cost: U256::zero(),
+                        gas_units: U256::from(AUCTION_TAKE_GAS_UNITS),
+                    },
+                    action: AuctionAction::Take(route),
+                    lot,
+                });
+            }
+        }
+
+        actions
     }
 
-    pub fn sell_on_dex(&self, dex: String) {
-        // Set the Uniswap V2 Router address
-        let uniswap_v2_router = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D";
+    /// Submits `opportunity` unless it was already submitted in `block_number`. The key is claimed
+    /// before submitting, so a concurrent feed update and block scan cannot both pass the check,
+    /// and released again if the submission fails.
+    async fn liquidate(&self, opportunity: &LiquidationOpportunity, net: &NetProfit, block_number: u64) -> Result<()> {
+        let key = (opportunity.pool, opportunity.user);
+        {
+            let mut queued = self.queued.write().await;
+            if queued.get(&key) == Some(&block_number) {
+                return Ok(());
+            }
+            queued.retain(|_, queued_in| *queued_in >= block_number);
+            queued.insert(key, block_number);
+        }
 
-        // Set the WETH address
-        let weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+        info!(
+            "Liquidating {:?} on {} (HF {}): selling {} of {} seized collateral for {}, repaying {} plus {} fee, {} wei net (${:.2})",
+            opportunity.user,
+            self.lending_pools[opportunity.pool].name(),
+            opportunity.health_factor,
+            opportunity.economics.collateral_sold,
+            opportunity.economics.seized_collateral,
+            opportunity.economics.sale_proceeds,
+            opportunity.economics.debt_repaid,
+            opportunity.economics.flash_fee,
+            net.net_eth,
+            net.net_usd
+        );
+        if let Err(e) = self.execute_liquidation(opportunity).await {
+            let mut queued = self.queued.write().await;
+            if queued.get(&key) == Some(&block_number) {
+                queued.remove(&key);
+            }
+            return Err(e);
+        }
 
-        // Set the token address
-        let token = self.token.clone();
+        Ok(())
+    }
 
-        // Set the amount of tokens to swap
-        let amount_in = self.get_price(dex.clone());
+    /// Pays the debt from the leased wallet when it holds enough, and otherwise flash-borrows it.
+    async fn execute_liquidation(&self, opportunity: &LiquidationOpportunity) -> Result<()> {
+        let lease = match self.wallets.acquire().await {
+            Some(lease) => lease,
+            None => {
+                warn!("No free wallet to liquidate {:?}", opportunity.user);
+                return Ok(());
+            }
+        };
 
-        // Set the amount of ETH to receive
-        let amount_out = 1 ether;
+        let funded = match self.funding.fund(opportunity.clone(), lease.address()).await? {
+            Some(funded) => funded,
+            None => return Ok(()),
+        };
+        match &funded.funding {
+            Funding::Wallet => self.execute_wallet_liquidation(opportunity, &lease).await,
+            Funding::FlashLoan(quote) => self.execute_flash_liquidation(opportunity, quote, &lease).await,
+        }
+    }
 
-        // Set the deadline for the transaction
-        let deadline = block.timestamp + 15 minutes;
+    /// Plans the liquidation inside `quote` and submits it through the executor if it still clears
+    /// the profit filter once the whole seizure is sold to repay the loan.
+    async fn execute_flash_liquidation(&self, opportunity: &LiquidationOpportunity, quote: &FlashLoanQuote, lease: &WalletLease) -> Result<()> {
+        let pool = self.lending_pools[opportunity.pool].name();
+        let leg = opportunity.leg.clone().ok_or_else(|| anyhow!("{} cannot flash-fund liquidations", pool))?;
+        let plan = match self.flash_liquidator.plan(leg, &opportunity.params, opportunity.economics.seized_collateral, quote.clone()).await? {
+            Some(plan) => plan,
+            None => {
+                debug!("No route repays a flash liquidation of {:?} on {}", opportunity.user, pool);
+                return Ok(());
+            }
+        };
+        let economics = match self.flash_economics(&plan, opportunity.economics.debt_decimals).await {
+            Some(economics) => economics,
+            None => {
+                debug!("No route sells the collateral of {:?} on {}", opportunity.user, pool);
+                return Ok(());
+            }
+        };
 
-        // Create a path for the swap
-        let path = vec![token, weth];
+        let gas_price = self.gas_price.read().await.unwrap_or_default();
+        let net = self.profit_filter.net_profit(&economics.estimate(), gas_price).await?;
+        if !self.profit_filter.clears(&net) {
+            debug!("Flash-liquidating {:?} on {} nets {} wei, below the minimum", opportunity.user, pool, net.net_eth);
+            return Ok(());
+        }
 
-        // Use the Uniswap V2 Router to swap the token for ETH
-        self.web3.eth().send_transaction(
-            TransactionRequest {
-                from: self.private_key.clone(),
-                to: uniswap_v2_router,
-                value: amount_in,
-                gas: self.gas_price,
-                gas_price: self.gas_price,
-                data: encode_swap_exact_tokens_for_tokens_supporting_fee_on_transfer_tokens(
-                    amount_in,
-                    amount_out,
-                    path,
-                    deadline,
-                ),
-            },
+        info!(
+            "Flash-borrowing {} from {:?} to liquidate {:?} on {}: selling {} collateral for {}, {} wei net (${:.2})",
+            quote.amount, quote.source, opportunity.user, pool, economics.collateral_sold, economics.sale_proceeds, net.net_eth, net.net_usd
         );
+        let window = self.deadlines.bundle_window().await?;
+        let tx = self.flash_liquidator.liquidation_tx(&plan, lease.address(), window.deadline)?;
+        self.submitter.submit_bundle("liquidation", lease, tx, &window).await?;
+
+        Ok(())
     }
 
-    pub fn get_price(&self, dex: String) -> U256 {
-        // Get the price of the token on the specified DEX
-        let url = format!("https://api.binance.com/api/v3/ticker/price?symbol={}", dex);
-        let response = reqwest::get(url).unwrap();
-        let json: serde_json::Value = serde_json::from_str(&response.text().unwrap()).unwrap();
-        let price = json["price"].as_str().unwrap();
-        U256::from_dec(price).unwrap()
+    /// Repays the debt from the leased wallet, approving the market to pull it first. Native ETH
+    /// debt is sent as value and Comet's absorb pulls nothing, so those need no allowance.
+    async fn execute_wallet_liquidation(&self, opportunity: &LiquidationOpportunity, lease: &WalletLease) -> Result<()> {
+        let pool = &self.lending_pools[opportunity.pool];
+        let tx = pool.liquidation_tx(&opportunity.params);
+        let pulled = match &opportunity.leg {
+            Some(leg) if !leg.debt_is_native => Some((leg.debt_underlying, leg.market)),
+            _ => None,
+        };
+
+        let debt_to_cover = opportunity.params.debt_to_cover;
+        if let Some((token, market)) = pulled {
+            self.approvals.ensure_allowance("liquidation", lease, token, market, debt_to_cover).await?;
+        }
+        if let Submission::Broadcast(_) = self.submitter.submit("liquidation", lease, tx).await? {
+            if let Some((token, market)) = pulled {
+                self.approvals.record_spend(lease.address(), token, market, debt_to_cover).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Buys from the wallet, or through the executor inside a flash loan of the payment token that
+    /// the sale of the collateral repays; the executor reverts unless the fill leaves `net` in the
+    /// payment token, and pays it to the wallet.
+    async fn execute_collateral_purchase(&self, funded: &Funded<CollateralPurchase>, net: &NetProfit, lease: &WalletLease) -> Result<()> {
+        let purchase = &funded.opportunity;
+        let pool = &self.lending_pools[purchase.pool];
+
+        let (tx, window) = match &funded.funding {
+            Funding::Wallet => return self.buy_collateral_from_wallet(purchase, lease).await,
+            Funding::FlashLoan(quote) => {
+                let window = self.deadlines.bundle_window().await?;
+                let executor = self.executor.address();
+                let buy = match pool.buy_collateral_tx(&purchase.sale, executor) {
+                    Some(tx) => tx,
+                    None => return Ok(()),
+                };
+                let market = *buy.to_addr().ok_or_else(|| anyhow!("Collateral purchase has no target"))?;
+                let (payment_token, asset) = (purchase.sale.payment_token, purchase.sale.asset);
+                let sell = encode_swap_exact_tokens_for_tokens(
+                    purchase.sale.collateral_amount,
+                    quote.amount + quote.fee,
+                    purchase.route.path.clone(),
+                    executor,
+                    window.deadline,
+                )?;
+
+                let min_profit = self.profit_filter.net_in_profit_token(funded.profit(), net).await?;
+                let actions = [
+                    Action::flash_borrow(
+                        quote,
+                        vec![
+                            Action::Approve { token: payment_token, spender: market, amount: purchase.sale.payment_amount },
+                            Action::Call { target: market, data: buy.data().cloned().unwrap_or_default() },
+                            // The protocol may take less than quoted, and USDT will not re-approve over a leftover
+                            Action::Approve { token: payment_token, spender: market, amount: U256::zero() },
+                            Action::Approve { token: asset, spender: purchase.route.router, amount: purchase.sale.collateral_amount },
+                            Action::Call { target: purchase.route.router, data: sell },
+                        ],
+                    ),
+                    Action::CheckMinBalance { token: payment_token, minimum: min_profit },
+                    Action::Transfer { token: payment_token, to: lease.address(), amount: BALANCE },
+                ];
+                (self.executor.execute_tx(&actions)?, window)
+            }
+        };
+        self.submitter.submit_bundle("liquidation", lease, tx, &window).await?;
+
+        Ok(())
+    }
+
+    /// Pays for the collateral from the leased wallet, approving the market for the payment first.
+    async fn buy_collateral_from_wallet(&self, purchase: &CollateralPurchase, lease: &WalletLease) -> Result<()> {
+        let tx = match self.lending_pools[purchase.pool].buy_collateral_tx(&purchase.sale, lease.address()) {
+            Some(tx) => tx,
+            None => return Ok(()),
+        };
+        let market = *tx.to_addr().ok_or_else(|| anyhow!("Collateral purchase has no target"))?;
+        let (token, amount) = (purchase.sale.payment_token, purchase.sale.payment_amount);
+
+        self.approvals.ensure_allowance("liquidation", lease, token, market, amount).await?;
+        if let Submission::Broadcast(_) = self.submitter.submit("liquidation", lease, tx).await? {
+            self.approvals.record_spend(lease.address(), token, market, amount).await;
+        }
+
+        Ok(())
+    }
+
+    async fn execute_auction_action(&self, opportunity: &AuctionOpportunity) -> Result<()> {
+        let lease = match self.wallets.acquire().await {
+            Some(lease) => lease,
+            None => {
+                warn!("No free wallet for auction {} on {}", opportunity.lot.id, self.auctions[opportunity.house].name());
+                return Ok(());
+            }
+        };
+
+        let house = &self.auctions[opportunity.house];
+        let tx = match &opportunity.action {
+            // Revert unless the sale still clears the margin at inclusion
+            AuctionAction::Take(route) => {
+                house.take_tx(&opportunity.lot, route, opportunity.lot.payment_amount * AUCTION_TAKE_MARGIN_BPS / BPS)
+            }
+            AuctionAction::Redo => house.redo_tx(&opportunity.lot),
+        };
+        self.submitter.submit("liquidation", &lease, tx).await?;
+
+        Ok(())
     }
 }
 
+#[async_trait]
+impl Strategy for LiquidationStrategy {
+    async fn execute(&self, block: &Block<H256>) -> Result<()> {
+        // Accounts still get repriced if the index falls behind; only their positions go stale
+        let touched = match block.number {
+            Some(number) => self.borrower_index.sync(number.as_u64()).await.unwrap_or_else(|e| {
+                warn!("Failed to sync the borrower index to block {}: {}", number, e);
+                HashMap::new()
+            }),
+            None => HashMap::new(),
+        };
+
+        let gas_price = block.base_fee_per_gas.unwrap_or_default();
+        if block.base_fee_per_gas.is_some() {
+            *self.gas_price.write().await = Some(gas_price);
+        }
+
+        let block_number = block.number.map(|number| number.as_u64()).unwrap_or_default();
+        for (opportunity, net) in self.find_opportunities(&touched, gas_price).await {
+            if let Err(e) = self.liquidate(&opportunity, &net, block_number).await {
+                warn!("Failed to liquidate {:?} on {}: {}", opportunity.user, self.lending_pools[opportunity.pool].name(), e);
+            }
+        }
+
+        for purchase in self.find_collateral_purchases().await {
+            let lease = match self.wallets.acquire().await {
+                Some(lease) => lease,
+                None => {
+                    warn!("No free wallet to buy {:?} collateral", purchase.sale.asset);
+                    break;
+                }
+            };
+            let asset = purchase.sale.asset;
+            let funded = match self.funding.fund(purchase, lease.address()).await {
+                Ok(Some(funded)) => funded,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to fund a purchase of {:?} collateral: {}", asset, e);
+                    continue;
+                }
+            };
+            let net = match self.profit_filter.net_profit(funded.profit(), gas_price).await {
+                Ok(net) => net,
+                Err(e) => {
+                    warn!("Failed to value a purchase of {:?} collateral: {}", asset, e);
+                    continue;
+                }
+            };
+            if !self.profit_filter.clears(&net) {
+                continue;
+            }
+
+            info!(
+                "Buying {} of {:?} from {}{} for {} wei net (${:.2})",
+                funded.opportunity.sale.collateral_amount,
+                funded.opportunity.sale.asset,
+                self.lending_pools[funded.opportunity.pool].name(),
+                match &funded.funding {
+                    Funding::Wallet => String::new(),
+                    Funding::FlashLoan(quote) => format!(" with a {:?} flash loan", quote.source),
+                },
+                net.net_eth,
+                net.net_usd
+            );
+            if let Err(e) = self.execute_collateral_purchase(&funded, &net, &lease).await {
+                warn!("Failed to buy {:?} collateral: {}", asset, e);
+            }
+        }
+
+        if let Some(number) = block.number {
+            for house in &self.auctions {
+                if let Err(e) = house.sync(number.as_u64()).await {
+                    warn!("Failed to sync auctions on {}: {}", house.name(), e);
+                }
+            }
+        }
+        let actions = self.find_auction_actions(block.timestamp.as_u64()).await;
+        for (opportunity, net) in self.profit_filter.retain_profitable(actions, gas_price).await {
+            info!(
+                "{} auction {} on {} at {} for {} wei net (${:.2})",
+                match opportunity.action {
+                    AuctionAction::Take(_) => "Taking",
+                    AuctionAction::Redo => "Resetting",
+                },
+                opportunity.lot.id,
+                self.auctions[opportunity.house].name(),
+                opportunity.lot.price,
+                net.net_eth,
+                net.net_usd
+            );
+            if let Err(e) = self.execute_auction_action(&opportunity).await {
+                warn!("Failed to act on auction {} on {}: {}", opportunity.lot.id, self.auctions[opportunity.house].name(), e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A liquidation's cash flows, in debt units unless noted.
+#[derive(Debug, Clone)]
+pub struct LiquidationEconomics {
+    pub debt_token: Address,
+    pub debt_decimals: u8,
+    /// Collateral the protocol pays out, after its fees, in collateral units.
+    pub seized_collateral: U256,
+    /// Collateral the valuation sells, in collateral units.
+    pub collateral_sold: U256,
+    /// What `collateral_sold` fetches on the best DEX route, price impact included.
+    pub sale_proceeds: U256,
+    /// Debt we repay out of the proceeds; zero when the protocol does not take payment up front.
+    pub debt_repaid: U256,
+    /// Flash-loan premium; zero when the debt is paid from the wallet.
+    pub flash_fee: U256,
+    pub gas_units: u64,
+}
+
+impl LiquidationEconomics {
+    pub fn estimate(&self) -> ProfitEstimate {
+        ProfitEstimate {
+            profit_token: self.debt_token,
+            profit_token_decimals: self.debt_decimals,
+            gross_profit: self.sale_proceeds.saturating_sub(self.debt_repaid),
+            fee_cost: self.flash_fee,
+            gas_units: U256::from(self.gas_units),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LiquidationOpportunity {
+    pub user: Address,
+    /// Index into the strategy's lending pools.
+    pub pool: usize,
+    pub health_factor: Wad,
+    pub params: LiquidationParams,
+    /// How the executor performs it inside a flash loan; `None` where it cannot, so the wallet pays.
+    pub leg: Option<FlashLiquidationLeg>,
+    /// Valued as paid from the wallet.
+    pub economics: LiquidationEconomics,
+    pub profit: ProfitEstimate,
+}
+
+impl Opportunity for LiquidationOpportunity {
+    fn profit(&self) -> &ProfitEstimate {
+        &self.profit
+    }
+
+    /// The debt repaid, in its underlying, for liquidations a flash loan can fund.
+    fn capital(&self) -> Option<CapitalRequirement> {
+        self.leg.as_ref().map(|leg| CapitalRequirement {
+            token: leg.debt_underlying,
+            amount: self.params.debt_to_cover,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum AuctionAction {
+    /// Buy the lot, paying for it by selling the collateral through this route.
+    Take(SwapRoute),
+    Redo,
+}
+
+#[derive(Debug)]
+pub struct AuctionOpportunity {
+    /// Index into the strategy's auction houses.
+    pub house: usize,
+    pub lot: AuctionLot,
+    pub action: AuctionAction,
+    pub profit: ProfitEstimate,
+}
+
+impl Opportunity for AuctionOpportunity {
+    fn profit(&self) -> &ProfitEstimate {
+        &self.profit
+    }
+}
+
+/// Buying protocol-held collateral and selling it on the DEX.
+#[derive(Debug)]
+pub struct CollateralPurchase {
+    pub pool: usize,
+    pub sale: CollateralSale,
+    /// Where the collateral is sold.
+    pub route: SwapRoute,
+    pub profit: ProfitEstimate,
+}
+
+impl Opportunity for CollateralPurchase {
+    fn profit(&self) -> &ProfitEstimate {
+        &self.profit
+    }
+
+    fn capital(&self) -> Option<CapitalRequirement> {
+        Some(CapitalRequirement {
+            token: self.sale.payment_token,
+            amount: self.sale.payment_amount,
+        })
+    }
+}
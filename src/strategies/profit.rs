@@ -0,0 +1,236 @@
+// src/strategies/profit.rs
+use anyhow::{anyhow, Result};
+use ethers::types::{Address, I256, U256};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::math::{mul_div, Wad};
+use crate::oracle::PriceOracle;
+
+/// What an opportunity is expected to earn and cost, before any conversion.
+#[derive(Debug, Clone)]
+pub struct ProfitEstimate {
+    /// Token the gross profit and fees are denominated in.
+    pub profit_token: Address,
+    pub profit_token_decimals: u8,
+    pub gross_profit: U256,
+    /// Protocol fees (flash-loan premiums, DEX fees not already in the quote), in `profit_token`.
+    pub fee_cost: U256,
+    pub gas_units: U256,
+}
+
+impl ProfitEstimate {
+    /// The estimate once funded by a flash loan costing `fee` of `fee_token` and `gas_units` more
+    /// gas, or `None` when the fee is not in the profit token and cannot be netted against it.
+    pub fn with_flash_loan(&self, fee_token: Address, fee: U256, gas_units: u64) -> Option<ProfitEstimate> {
+        if fee_token != self.profit_token {
+            return None;
+        }
+
+        Some(ProfitEstimate {
+            fee_cost: self.fee_cost.checked_add(fee)?,
+            gas_units: self.gas_units.checked_add(U256::from(gas_units))?,
+            ..self.clone()
+        })
+    }
+}
+
+/// Capital an opportunity must hold before it executes, e.g. a swap's input or a purchase price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapitalRequirement {
+    pub token: Address,
+    pub amount: U256,
+}
+
+/// A `ProfitEstimate` expressed in wei, plus the USD value of the result for reporting.
+#[derive(Debug, Clone)]
+pub struct NetProfit {
+    pub gross_eth: U256,
+    pub fee_eth: U256,
+    /// Base fee for the gas used.
+    pub gas_cost_eth: U256,
+    /// Priority fee for the gas used, which goes to the block builder.
+    pub builder_tip_eth: U256,
+    pub net_eth: I256,
+    pub net_usd: f64,
+}
+
+pub trait Opportunity {
+    fn profit(&self) -> &ProfitEstimate;
+    /// What executing it takes up front; `None` when it needs nothing or raises it itself.
+    fn capital(&self) -> Option<CapitalRequirement> {
+        None
+    }
+}
+
+/// Drops opportunities whose profit after gas, fees and tips is below `min_profit`.
+pub struct ProfitFilter {
+    oracle: Arc<PriceOracle>,
+    weth: Address,
+    /// Minimum net profit in ETH.
+    min_profit: Wad,
+    /// Priority fee per gas the submitter pays, in wei.
+    priority_fee: U256,
+}
+
+impl ProfitFilter {
+    pub fn new(oracle: Arc<PriceOracle>, weth: Address, min_profit: Wad, priority_fee: U256) -> Self {
+        Self {
+            oracle,
+            weth,
+            min_profit,
+            priority_fee,
+        }
+    }
+
+    /// `estimate` in wei, paying `gas_price` base fee plus the configured priority fee per gas.
+    pub async fn net_profit(&self, estimate: &ProfitEstimate, gas_price: U256) -> Result<NetProfit> {
+        let eth_usd = self.oracle.get_price(self.weth).await?;
+        let (gross_eth, fee_eth) = if estimate.profit_token == self.weth {
+            (estimate.gross_profit, estimate.fee_cost)
+        } else {
+            let token_usd = self.oracle.get_price(estimate.profit_token).await?;
+            (
+                to_wei(estimate.gross_profit, estimate.profit_token_decimals, token_usd, eth_usd)?,
+                to_wei(estimate.fee_cost, estimate.profit_token_decimals, token_usd, eth_usd)?,
+            )
+        };
+
+        net_of_costs(estimate, gross_eth, fee_eth, gas_price, self.priority_fee, eth_usd)
+    }
+
+    pub fn clears(&self, net: &NetProfit) -> bool {
+        net.net_eth >= I256::from_raw(self.min_profit.raw())
+    }
+
+    /// `net` expressed in `estimate`'s profit token, or zero for a loss; what a flash-funded fill
+    /// must leave once the loan is repaid.
+    pub async fn net_in_profit_token(&self, estimate: &ProfitEstimate, net: &NetProfit) -> Result<U256> {
+        if net.net_eth.is_negative() {
+            return Ok(U256::zero());
+        }
+        let net_eth = net.net_eth.into_raw();
+        if estimate.profit_token == self.weth {
+            return Ok(net_eth);
+        }
+
+        let eth_usd = self.oracle.get_price(self.weth).await?;
+        let token_usd = self.oracle.get_price(estimate.profit_token).await?;
+        from_wei(net_eth, estimate.profit_token_decimals, token_usd, eth_usd)
+    }
+
+    /// Keeps the opportunities that clear `min_profit` at `gas_price`, paired with their net profit;
+    /// one that cannot be valued is logged and dropped.
+    pub async fn retain_profitable<T: Opportunity>(&self, opportunities: Vec<T>, gas_price: U256) -> Vec<(T, NetProfit)> {
+        let mut profitable = Vec::new();
+        for opportunity in opportunities {
+            let net = match self.net_profit(opportunity.profit(), gas_price).await {
+                Ok(net) => net,
+                Err(e) => {
+                    warn!("Failed to value an opportunity in {:?}: {}", opportunity.profit().profit_token, e);
+                    continue;
+                }
+            };
+            if self.clears(&net) {
+                profitable.push((opportunity, net));
+            } else {
+                debug!("Discarding opportunity with net profit {} wei (${:.2})", net.net_eth, net.net_usd);
+            }
+        }
+
+        profitable
+    }
+}
+
+/// `gross_eth` less the fees, base fee and builder tip of `estimate`'s gas.
+fn net_of_costs(estimate: &ProfitEstimate, gross_eth: U256, fee_eth: U256, gas_price: U256, priority_fee: U256, eth_usd: Wad) -> Result<NetProfit> {
+    let gas_cost_eth = estimate.gas_units
+        .checked_mul(gas_price)
+        .ok_or_else(|| anyhow!("Gas cost overflow"))?;
+    let builder_tip_eth = estimate.gas_units
+        .checked_mul(priority_fee)
+        .ok_or_else(|| anyhow!("Builder tip overflow"))?;
+    let costs = fee_eth
+        .checked_add(gas_cost_eth)
+        .and_then(|cost| cost.checked_add(builder_tip_eth))
+        .ok_or_else(|| anyhow!("Cost overflow"))?;
+    let net_eth = I256::from_raw(gross_eth) - I256::from_raw(costs);
+
+    let net_usd = Wad::from_raw(net_eth.unsigned_abs()).checked_mul(eth_usd)?.to_f64();
+    let net_usd = if net_eth.is_negative() { -net_usd } else { net_usd };
+
+    Ok(NetProfit {
+        gross_eth,
+        fee_eth,
+        gas_cost_eth,
+        builder_tip_eth,
+        net_eth,
+        net_usd,
+    })
+}
+
+/// Converts `amount` of a token with `decimals` decimals to wei using USD prices.
+fn to_wei(amount: U256, decimals: u8, token_usd: Wad, eth_usd: Wad) -> Result<U256> {
+    let amount = Wad::from_scaled(amount, decimals)?;
+    Ok(mul_div(amount.raw(), token_usd.raw(), eth_usd.raw())?)
+}
+
+/// Converts `wei` to units of a token with `decimals` decimals using USD prices, rounding down.
+fn from_wei(wei: U256, decimals: u8, token_usd: Wad, eth_usd: Wad) -> Result<U256> {
+    let amount = Wad::from_raw(mul_div(wei, eth_usd.raw(), token_usd.raw())?);
+    Ok(amount.to_scaled(decimals)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_wei_converts_usdc() {
+        // 3000 USDC at $1 with ETH at $2000 is 1.5 ETH
+        let wei = to_wei(U256::from(3_000_000_000u64), 6, Wad::ONE, Wad::from_integer(2_000)).unwrap();
+
+        assert_eq!(wei, U256::from(1_500_000_000_000_000_000u128));
+        assert_eq!(from_wei(wei, 6, Wad::ONE, Wad::from_integer(2_000)).unwrap(), U256::from(3_000_000_000u64));
+    }
+
+    #[test]
+    fn test_flash_loan_fee_adds_to_costs_in_profit_token() {
+        let usdc = Address::from_low_u64_be(1);
+        let estimate = ProfitEstimate {
+            profit_token: usdc,
+            profit_token_decimals: 6,
+            gross_profit: U256::from(1_000_000_000u64),
+            fee_cost: U256::from(3_000_000u64),
+            gas_units: U256::from(200_000u64),
+        };
+
+        let funded = estimate.with_flash_loan(usdc, U256::from(500_000u64), 150_000).unwrap();
+        assert_eq!(funded.fee_cost, U256::from(3_500_000u64));
+        assert_eq!(funded.gas_units, U256::from(350_000u64));
+        assert_eq!(funded.gross_profit, estimate.gross_profit);
+        assert!(estimate.with_flash_loan(Address::from_low_u64_be(2), U256::zero(), 0).is_none());
+    }
+
+    #[test]
+    fn test_builder_tip_is_charged_per_gas() {
+        let estimate = ProfitEstimate {
+            profit_token: Address::zero(),
+            profit_token_decimals: 18,
+            gross_profit: U256::exp10(16),
+            fee_cost: U256::zero(),
+            gas_units: U256::from(300_000u64),
+        };
+        let (gross, fee) = (estimate.gross_profit, estimate.fee_cost);
+        let gwei = U256::exp10(9);
+
+        let untipped = net_of_costs(&estimate, gross, fee, gwei * 10, U256::zero(), Wad::from_integer(2_000)).unwrap();
+        let tipped = net_of_costs(&estimate, gross, fee, gwei * 10, gwei * 2, Wad::from_integer(2_000)).unwrap();
+
+        assert_eq!(tipped.gas_cost_eth, U256::from(3_000_000u64) * gwei);
+        assert_eq!(tipped.builder_tip_eth, U256::from(600_000u64) * gwei);
+        // 0.01 ETH less 0.003 ETH base fee and 0.0006 ETH tip
+        assert_eq!(tipped.net_eth, I256::from(6_400_000_000_000_000i64));
+        assert_eq!(untipped.net_eth - tipped.net_eth, I256::from_raw(tipped.builder_tip_eth));
+    }
+}
@@ -0,0 +1,144 @@
+// src/strategies/self_protection.rs
+use async_trait::async_trait;
+use ethers::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+use anyhow::Result;
+
+use crate::execution::{Submitter, WalletPool};
+use crate::lending::{alert_level, LendingPool, PositionUnwinder, ProtectedAccount, ReservePosition};
+use crate::math::Wad;
+use super::Strategy;
+
+// A deleverage is given this many blocks to land before another is sized from stale positions
+const DELEVERAGE_COOLDOWN_BLOCKS: u64 = 5;
+
+/// Points the health-factor machinery at our own borrowing accounts: alerts as they cross their
+/// thresholds and deleverages them below their floor.
+pub struct SelfProtectionStrategy {
+    wallets: Arc<WalletPool>,
+    submitter: Arc<Submitter>,
+    lending_pools: Vec<Arc<dyn LendingPool>>,
+    accounts: Vec<ProtectedAccount>,
+    unwinder: Arc<PositionUnwinder>,
+    /// (market, account) → alert thresholds last reported crossed.
+    alert_levels: RwLock<HashMap<(String, Address), usize>>,
+    /// (market, account) → block the last deleverage was submitted in.
+    deleveraged: RwLock<HashMap<(String, Address), u64>>,
+}
+
+impl SelfProtectionStrategy {
+    pub fn new(
+        wallets: Arc<WalletPool>,
+        submitter: Arc<Submitter>,
+        lending_pools: Vec<Arc<dyn LendingPool>>,
+        accounts: Vec<ProtectedAccount>,
+        unwinder: Arc<PositionUnwinder>,
+    ) -> Self {
+        Self {
+            wallets,
+            submitter,
+            lending_pools,
+            accounts,
+            unwinder,
+            alert_levels: RwLock::new(HashMap::new()),
+            deleveraged: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Alerts once per threshold crossed downwards, and once more when the account recovers.
+    async fn report(&self, protected: &ProtectedAccount, health_factor: Wad) {
+        let key = (protected.market.clone(), protected.account);
+        let level = alert_level(health_factor, &protected.alert_bps);
+        let previous = self.alert_levels.write().await.insert(key, level).unwrap_or(0);
+
+        if level > previous {
+            error!(
+                "Our account {:?} on {} is at HF {}, below {} of its {} alert thresholds",
+                protected.account,
+                protected.market,
+                health_factor,
+                level,
+                protected.alert_bps.len()
+            );
+        } else if level == 0 && previous > 0 {
+            info!("Our account {:?} on {} recovered to HF {}", protected.account, protected.market, health_factor);
+        }
+    }
+
+    async fn deleverage(&self, pool: &dyn LendingPool, protected: &ProtectedAccount, positions: &[ReservePosition], block_number: u64) -> Result<()> {
+        let policy = match &protected.deleverage {
+            Some(policy) => policy,
+            None => return Ok(()),
+        };
+        let key = (protected.market.clone(), protected.account);
+        if self.deleveraged.read().await.get(&key).is_some_and(|at| block_number < at + DELEVERAGE_COOLDOWN_BLOCKS) {
+            return Ok(());
+        }
+
+        let plan = match self.unwinder.plan(pool, protected.account, positions, policy).await? {
+            Some(plan) => plan,
+            None => {
+                error!("Cannot deleverage our account {:?} on {}: no supported unwind", protected.account, protected.market);
+                return Ok(());
+            }
+        };
+        let lease = match self.wallets.acquire().await {
+            Some(lease) => lease,
+            None => {
+                warn!("No free wallet to deleverage {:?} on {}", protected.account, protected.market);
+                return Ok(());
+            }
+        };
+
+        warn!("Deleveraging our account {:?} on {}: {:?}", protected.account, protected.market, plan);
+        self.submitter
+            .submit("self_protection", &lease, self.unwinder.deleverage_tx(protected.account, &plan))
+            .await?;
+        self.deleveraged.write().await.insert(key, block_number);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Strategy for SelfProtectionStrategy {
+    async fn execute(&self, block: &Block<H256>) -> Result<()> {
+        let block_number = block.number.map(|number| number.as_u64()).unwrap_or_default();
+
+        for protected in &self.accounts {
+            let pool = match self.lending_pools.iter().find(|pool| pool.name() == protected.market) {
+                Some(pool) => pool,
+                None => {
+                    warn!("No lending pool named {} for our account {:?}", protected.market, protected.account);
+                    continue;
+                }
+            };
+            let positions = match pool.get_user_positions(protected.account).await {
+                Ok(positions) => positions,
+                Err(e) => {
+                    error!("Failed to read our account {:?} on {}: {}", protected.account, protected.market, e);
+                    continue;
+                }
+            };
+            let health_factor = match pool.health_factor(&positions) {
+                Ok(health_factor) => health_factor,
+                Err(e) => {
+                    error!("Failed to compute the health factor of our account {:?} on {}: {}", protected.account, protected.market, e);
+                    continue;
+                }
+            };
+            self.report(protected, health_factor).await;
+
+            if protected.deleverage.as_ref().is_some_and(|policy| health_factor < Wad::from_bps(policy.floor_bps)) {
+                if let Err(e) = self.deleverage(pool.as_ref(), protected, &positions, block_number).await {
+                    error!("Failed to deleverage our account {:?} on {}: {}", protected.account, protected.market, e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}